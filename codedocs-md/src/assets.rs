@@ -0,0 +1,165 @@
+//! Images the documents embed, kept in an assets folder next to them.
+
+use crate::links::{
+    extract_links, is_markdown_path, is_relative, link_destination, parent_dir, percent_decode, resolve_path,
+};
+use crate::{line_starts, options, slugify};
+use pulldown_cmark::{Event, Parser};
+use regex_lite::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `[assets]` table of `.codedocs.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetsConfig {
+    /// Where pasted and dropped images go, relative to the document.
+    pub folder: String,
+    /// Images wider than this are scaled down; `0` keeps their size.
+    pub max_width: u32,
+    /// Quality opaque images are re-encoded as JPEG with when that makes
+    /// them smaller; `0` keeps their format.
+    pub jpeg_quality: u8,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        Self {
+            folder: "assets".to_string(),
+            max_width: 0,
+            jpeg_quality: 0,
+        }
+    }
+}
+
+/// Extensions of the images that can be pasted or dropped into a document.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif"];
+
+/// MIME type of an image, by its extension.
+pub fn image_mime(extension: &str) -> Option<&'static str> {
+    Some(match extension.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "avif" => "image/avif",
+        _ => return None,
+    })
+}
+
+/// Names browsers and screenshot tools give pasted images, not worth keeping.
+const GENERIC_NAMES: &[&str] = &["image", "imagen", "clipboard", "screenshot", "pasted", "untitled"];
+
+/// The image extension of `file_name`, lowercased.
+pub fn image_extension(file_name: &str) -> Option<String> {
+    let (_, extension) = file_name.rsplit_once('.')?;
+    let extension = extension.to_lowercase();
+    IMAGE_EXTENSIONS.contains(&extension.as_str()).then_some(extension)
+}
+
+/// File name for an asset: the original name, slugged, followed by the
+/// start of the content hash, so the same image always gets the same name
+/// and different images never collide.
+pub fn asset_file_name(original: &str, hash: &str, extension: &str) -> String {
+    let stem = original.rsplit_once('.').map_or(original, |(stem, _)| stem);
+    let stem = slugify(&stem.replace(['_', '.'], " "));
+    let hash = &hash[..hash.len().min(12)];
+    if stem.is_empty() || GENERIC_NAMES.contains(&stem.as_str()) {
+        format!("{}.{}", hash, extension)
+    } else {
+        format!("{}-{}.{}", stem, hash, extension)
+    }
+}
+
+/// Path to link an asset with from a document in the folder above `folder`.
+pub fn asset_link(folder: &str, file_name: &str) -> String {
+    let folder = folder.trim().trim_matches(['/', '\\']).replace('\\', "/");
+    let path = if folder.is_empty() || folder == "." {
+        file_name.to_string()
+    } else {
+        format!("{}/{}", folder, file_name)
+    };
+    if path.contains(' ') {
+        format!("<{}>", path)
+    } else {
+        path
+    }
+}
+
+/// `![alt](path)` for an image, the alt text taken from its original name.
+pub fn image_markdown(original: &str, link: &str) -> String {
+    let stem = original.rsplit_once('.').map_or(original, |(stem, _)| stem);
+    let alt = stem.replace(['_', '-'], " ").replace(['[', ']'], "");
+    let alt = alt.trim();
+    let alt = if alt.is_empty() || GENERIC_NAMES.contains(&alt.to_lowercase().as_str()) {
+        "imagen"
+    } else {
+        alt
+    };
+    format!("![{}]({})", alt, link)
+}
+
+/// A file a document links or embeds that is not another document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetReference {
+    /// Document the reference is in.
+    pub file: String,
+    /// 1-based line of the reference.
+    pub line: usize,
+    /// Target as written.
+    pub target: String,
+    /// Target resolved against the document's folder.
+    pub path: String,
+}
+
+/// What the asset folders hold that nothing uses, and what the documents
+/// reference that does not exist.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetReport {
+    pub unused: Vec<String>,
+    pub missing: Vec<AssetReference>,
+}
+
+/// Images and relative links to non-markdown files in the document at
+/// `path`, including `src` and `href` attributes in raw HTML.
+pub fn asset_references(path: &str, content: &str) -> Vec<AssetReference> {
+    let line_starts = line_starts(content);
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset);
+    let reference = |line: usize, target: &str, resolved: String| AssetReference {
+        file: path.to_string(),
+        line,
+        target: target.to_string(),
+        path: resolved,
+    };
+    let mut references = Vec::new();
+
+    for link in extract_links(content) {
+        if link.wiki || !is_relative(&link.target) {
+            continue;
+        }
+        let Some((resolved, _)) = link_destination(&link, path, &[]) else { continue };
+        if link.image || !is_markdown_path(&resolved) {
+            references.push(reference(link.line, &link.target, resolved));
+        }
+    }
+
+    let attribute_re = Regex::new(r#"(?i)\b(?:src|href)\s*=\s*["']([^"']+)["']"#).unwrap();
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        let (Event::Html(html) | Event::InlineHtml(html)) = event else { continue };
+        for found in attribute_re.captures_iter(&html) {
+            let Some(target) = found.get(1) else { continue };
+            let target = target.as_str();
+            if !is_relative(target) {
+                continue;
+            }
+            let file_part = target.split(['#', '?']).next().unwrap_or_default();
+            let resolved = resolve_path(parent_dir(path), &percent_decode(file_part));
+            if !is_markdown_path(&resolved) {
+                references.push(reference(line_of(range.start), target, resolved));
+            }
+        }
+    }
+    references.sort_by_key(|r| r.line);
+    references
+}
@@ -0,0 +1,225 @@
+//! Validation of the links, images, anchors and wiki-links in a document.
+
+use crate::edit::TextEdit;
+use crate::links::{extract_links, is_relative, link_destination, link_path, parent_dir, percent_decode};
+use crate::wikilinks::{shortest_wikilink, split_wikilink};
+use crate::{slugify, Heading};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkDiagnostic {
+    pub file: String,
+    pub line: usize,
+    pub target: String,
+    pub reason: String,
+    pub fix: Option<TextEdit>,
+}
+
+/// What the checker knows about the project: its markdown files and the
+/// headings of each one.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectDocs {
+    pub files: Vec<String>,
+    pub headings: HashMap<String, Vec<Heading>>,
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb { previous } else { 1 + previous.min(row[j]).min(row[j + 1]) };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// The candidate closest to `wanted`, if it is close enough to be a typo.
+fn closest<'a>(wanted: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let wanted = wanted.to_lowercase();
+    let length = wanted.chars().count();
+    let limit = (length / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(&wanted, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= limit && *distance < length)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Checks every relative link, image, `#anchor` and wiki-link of one document.
+/// `exists` answers for non-markdown targets such as images.
+pub fn check_document(
+    path: &str,
+    content: &str,
+    project: &ProjectDocs,
+    exists: &dyn Fn(&str) -> bool,
+) -> Vec<LinkDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let document_dir = parent_dir(path);
+
+    for link in extract_links(content) {
+        if !link.wiki && !link.target.starts_with('#') && !is_relative(&link.target) {
+            continue;
+        }
+        let mut report = |reason: String, replacement: Option<String>| {
+            diagnostics.push(LinkDiagnostic {
+                file: path.to_string(),
+                line: link.line,
+                target: link.target.clone(),
+                reason,
+                fix: replacement.and_then(|r| link.target_edit(content, r)),
+            });
+        };
+
+        let Some((target, anchor)) = link_destination(&link, path, &project.files) else {
+            let (page, heading) = split_wikilink(&link.target);
+            let links: Vec<String> = project.files.iter().map(|f| shortest_wikilink(f, &project.files)).collect();
+            let suggestion = closest(page, links.iter().map(String::as_str)).map(|page| match heading {
+                Some(heading) => format!("{}#{}", page, heading),
+                None => page.to_string(),
+            });
+            report(format!("La página wiki «{}» no existe", page), suggestion);
+            continue;
+        };
+
+        let is_document = project.files.contains(&target);
+        if !is_document && !exists(&target) {
+            let wanted = file_name(&target);
+            let suggestion = if link.image {
+                None
+            } else {
+                closest(wanted, project.files.iter().map(|f| file_name(f))).and_then(|name| {
+                    let found = project.files.iter().find(|f| file_name(f) == name)?;
+                    let fixed = link_path(document_dir, found);
+                    Some(match &anchor {
+                        Some(anchor) => format!("{}#{}", fixed, anchor),
+                        None => fixed,
+                    })
+                })
+            };
+            let what = if link.image { "La imagen" } else { "El archivo" };
+            report(format!("{} «{}» no existe", what, percent_decode(wanted)), suggestion);
+            continue;
+        }
+
+        let Some(anchor) = anchor else { continue };
+        let Some(headings) = project.headings.get(&target) else { continue };
+        let slug = if link.wiki { slugify(&anchor) } else { anchor.clone() };
+        if headings.iter().any(|h| h.slug == slug) {
+            continue;
+        }
+
+        let suggestion = closest(&slug, headings.iter().map(|h| h.slug.as_str())).map(|found| {
+            if link.wiki {
+                let (page, _) = split_wikilink(&link.target);
+                let text = headings.iter().find(|h| h.slug == found).map(|h| h.text.as_str()).unwrap_or(found);
+                format!("{}#{}", page, text)
+            } else {
+                let base = link.target.split_once('#').map(|(base, _)| base).unwrap_or_default();
+                format!("{}#{}", base, found)
+            }
+        });
+        let place = if target == path { "este documento".to_string() } else { format!("«{}»", file_name(&target)) };
+        report(format!("El ancla «#{}» no existe en {}", anchor, place), suggestion);
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_headings;
+
+    fn project(files: &[(&str, &str)]) -> ProjectDocs {
+        ProjectDocs {
+            files: files.iter().map(|(path, _)| path.to_string()).collect(),
+            headings: files.iter().map(|(path, content)| (path.to_string(), extract_headings(content))).collect(),
+        }
+    }
+
+    /// Reason and suggested destination of each problem in `/p/index.md`.
+    fn problems(content: &str, project: &ProjectDocs) -> Vec<(String, Option<String>)> {
+        check_document("/p/index.md", content, project, &|path| path == "/p/img/logo.png")
+            .into_iter()
+            .map(|d| (d.reason, d.fix.map(|fix| fix.replacement)))
+            .collect()
+    }
+
+    #[test]
+    fn valid_and_external_links_are_not_reported() {
+        let project = project(&[("/p/index.md", "# Inicio\n"), ("/p/b.md", "## Uso\n")]);
+        let content = "# Inicio\n\n[b](b.md#uso) [i](#inicio) [[b#Uso]] ![l](img/logo.png) [w](https://x.org/nada)\n";
+        assert!(problems(content, &project).is_empty());
+    }
+
+    #[test]
+    fn missing_files_and_images_are_reported() {
+        let project = project(&[("/p/index.md", ""), ("/p/guia.md", "")]);
+        let content = "[g](gia.md) [x](nada/zzz.md)\n\n![i](img/mi%20logo.png)\n";
+        assert_eq!(
+            problems(content, &project),
+            [
+                ("El archivo «gia.md» no existe".to_string(), Some("guia.md".to_string())),
+                ("El archivo «zzz.md» no existe".to_string(), None),
+                ("La imagen «mi logo.png» no existe".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_anchors_suggest_the_closest_heading() {
+        let project = project(&[("/p/index.md", "# Inicio\n"), ("/p/b.md", "## Usos\n")]);
+        let content = "# Inicio\n\n[i](#inico) [b](b.md#uso)\n";
+        assert_eq!(
+            problems(content, &project),
+            [
+                ("El ancla «#inico» no existe en este documento".to_string(), Some("#inicio".to_string())),
+                ("El ancla «#uso» no existe en «b.md»".to_string(), Some("b.md#usos".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn wiki_links_suggest_pages_and_heading_text() {
+        let project = project(&[("/p/index.md", ""), ("/p/notas/todo.md", "## Primeros pasos\n")]);
+        let content = "[[tod]] [[todo#Primeros paso]]\n";
+        assert_eq!(
+            problems(content, &project),
+            [
+                ("La página wiki «tod» no existe".to_string(), Some("todo".to_string())),
+                (
+                    "El ancla «#Primeros paso» no existe en «todo.md»".to_string(),
+                    Some("todo#Primeros pasos".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn fixes_replace_only_the_destination() {
+        let project = project(&[("/p/index.md", ""), ("/p/guia.md", "")]);
+        let content = "Ver [la guía](gia.md \"título\").\n";
+        let diagnostics = check_document("/p/index.md", content, &project, &|_| false);
+        let fix = diagnostics[0].fix.clone().unwrap();
+        assert_eq!(crate::edit::apply_edits(content, &[fix]).unwrap(), "Ver [la guía](guia.md \"título\").\n");
+    }
+
+    #[test]
+    fn missing_file_suggestions_are_written_as_valid_destinations() {
+        let project = ProjectDocs { files: vec!["/p/notas/mi guia.md".to_string()], ..Default::default() };
+        let diagnostics = check_document("/p/index.md", "[g](notas/mi-guia.md#uso)\n", &project, &|_| false);
+        let fix = diagnostics[0].fix.as_ref().expect("suggestion");
+        assert_eq!(fix.replacement, "notas/mi%20guia.md#uso");
+    }
+}
@@ -0,0 +1,48 @@
+use crate::assets::AssetsConfig;
+use crate::format::FormatConfig;
+use crate::lint::LintConfig;
+use crate::pdf::PdfConfig;
+use crate::toc::TocOptions;
+use crate::RenderOptions;
+use serde::{Deserialize, Serialize};
+
+/// Per-project settings, stored in `.codedocs.toml` at the project root.
+/// Whether the project's raw HTML is trusted is the user's call, not the
+/// project's, so it is kept by the app and never read from here.
+pub const CONFIG_FILE: &str = ".codedocs.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Rules of the markdown linter; see [`crate::lint`].
+    pub lint: LintConfig,
+    /// Style applied by "Formatear documento"; see [`crate::format`].
+    pub format: FormatConfig,
+    /// Where pasted and dropped images are saved; see [`crate::assets`].
+    pub assets: AssetsConfig,
+    /// Page layout of the PDF export; see [`crate::pdf`].
+    pub pdf: PdfConfig,
+    /// Default table of contents of `[TOC]` and `<!-- toc -->` markers; see [`crate::toc`].
+    pub toc: TocOptions,
+}
+
+impl ProjectConfig {
+    pub fn from_toml(source: &str) -> Result<Self, String> {
+        toml::from_str(source).map_err(|e| e.message().to_string())
+    }
+
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions { toc: self.toc.clone(), ..RenderOptions::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trust_is_not_read_from_the_project() {
+        let config = ProjectConfig::from_toml("trusted_html = true\n").unwrap();
+        assert!(!config.render_options().trusted_html);
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+/// Replaces `original` at `start..end` of a document with `replacement`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Applies the edit if the source still has the original text at the recorded range.
+    pub fn apply(&self, content: &str) -> Option<String> {
+        apply_edits(content, std::slice::from_ref(self))
+    }
+}
+
+/// Applies non-overlapping edits all at once. `None` if any of them no longer
+/// matches the content, so a stale plan never half-applies.
+pub fn apply_edits(content: &str, edits: &[TextEdit]) -> Option<String> {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| edit.start);
+
+    let mut out = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for edit in sorted {
+        if edit.start < cursor || content.get(edit.start..edit.end)? != edit.original {
+            return None;
+        }
+        out.push_str(&content[cursor..edit.start]);
+        out.push_str(&edit.replacement);
+        cursor = edit.end;
+    }
+    out.push_str(&content[cursor..]);
+    Some(out)
+}
+
+/// The edits that take the result of applying `edits` back to the original
+/// content, with ranges into that result.
+pub fn inverse_edits(edits: &[TextEdit]) -> Vec<TextEdit> {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| edit.start);
+
+    let mut shift = 0isize;
+    sorted
+        .into_iter()
+        .map(|edit| {
+            let start = edit.start.saturating_add_signed(shift);
+            shift += edit.replacement.len() as isize - edit.original.len() as isize;
+            TextEdit {
+                start,
+                end: start + edit.replacement.len(),
+                original: edit.replacement.clone(),
+                replacement: edit.original.clone(),
+            }
+        })
+        .collect()
+}
+
+/// All the edits one refactoring makes to a single file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEdits {
+    pub path: String,
+    pub edits: Vec<TextEdit>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: usize, end: usize, original: &str, replacement: &str) -> TextEdit {
+        TextEdit { start, end, original: original.to_string(), replacement: replacement.to_string() }
+    }
+
+    #[test]
+    fn edits_apply_in_any_order() {
+        let edits = [edit(10, 13, "dos", "2"), edit(0, 3, "uno", "1")];
+        assert_eq!(apply_edits("uno, y no dos", &edits).as_deref(), Some("1, y no 2"));
+    }
+
+    #[test]
+    fn stale_edits_apply_nothing() {
+        let edits = [edit(0, 3, "uno", "1"), edit(10, 13, "dos", "2")];
+        assert_eq!(apply_edits("uno, y no tres", &edits), None);
+        assert_eq!(apply_edits("uno", &edits), None);
+        assert_eq!(apply_edits("uno, y no dos", &[edit(0, 5, "uno, ", ""), edit(3, 6, ", y", "")]), None);
+        // A range that splits a character is stale too.
+        assert_eq!(apply_edits("ñu", &[edit(1, 2, "", "x")]), None);
+    }
+
+    #[test]
+    fn inverse_edits_restore_the_original() {
+        let content = "uno, y no dos";
+        let edits = [edit(10, 13, "dos", "tres"), edit(0, 3, "uno", "1")];
+        let edited = apply_edits(content, &edits).unwrap();
+        assert_eq!(apply_edits(&edited, &inverse_edits(&edits)).as_deref(), Some(content));
+    }
+}
@@ -0,0 +1,370 @@
+//! A project bundled as an EPUB 3 book: its chapters as XHTML, the
+//! navigation document and the package document that lists them.
+
+use crate::export::document_title;
+use crate::frontmatter::{parse_front_matter, MetaValue};
+use crate::highlight::highlight_css;
+use crate::links::is_relative;
+use crate::site::NavItem;
+use crate::{escape_html, unescape_html, Heading};
+use regex_lite::{Captures, Regex};
+
+const EPUB_CSS: &str = include_str!("epub.css");
+
+/// The first file of the archive, stored uncompressed.
+pub const MIMETYPE: &str = "application/epub+zip";
+
+/// Where the reading system finds the package document.
+pub const CONTAINER_XML: &str = concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n",
+    "<rootfiles>\n<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n",
+    "</rootfiles>\n</container>\n"
+);
+
+/// What the package document says about the book, from the front matter
+/// of its first page.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookMetadata {
+    pub title: String,
+    pub authors: Vec<String>,
+    pub language: String,
+    /// `identifier` or `isbn`; empty if the front matter has neither.
+    pub identifier: String,
+    pub date: String,
+    pub description: String,
+    pub publisher: String,
+    /// Cover image as written in the front matter.
+    pub cover: Option<String>,
+}
+
+/// The metadata of a book whose first page is the document at `path`.
+pub fn book_metadata(content: &str, path: &str) -> BookMetadata {
+    let front_matter = parse_front_matter(content);
+    let field = |keys: &[&str]| {
+        front_matter
+            .as_ref()
+            .and_then(|fm| keys.iter().find_map(|key| fm.get(key)))
+            .map(MetaValue::display)
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+    let authors = match front_matter.as_ref().and_then(|fm| fm.get("authors").or_else(|| fm.get("author"))) {
+        Some(MetaValue::List(items)) => items.clone(),
+        Some(value) => vec![value.display()],
+        None => Vec::new(),
+    };
+    let isbn = field(&["isbn"]);
+    let identifier = match field(&["identifier"]) {
+        id if !id.is_empty() => id,
+        _ if !isbn.is_empty() => format!("urn:isbn:{}", isbn),
+        _ => String::new(),
+    };
+    let language = field(&["lang", "language"]);
+    let cover = field(&["cover", "image"]);
+    BookMetadata {
+        title: document_title(content, path),
+        authors: authors.into_iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect(),
+        language: if language.is_empty() { "es".to_string() } else { language },
+        identifier,
+        date: field(&["date"]),
+        description: field(&["description", "subtitle"]),
+        publisher: field(&["publisher"]),
+        cover: (!cover.is_empty()).then_some(cover),
+    }
+}
+
+/// Rendered HTML as well-formed XHTML. The sanitizer's output is already
+/// balanced; void elements still need closing and `&nbsp;` is not an XML
+/// entity. Task checkboxes become ☐ and ☒, which every reader shows.
+pub fn xhtml(html: &str) -> String {
+    let checkbox_re = Regex::new(r#"<input\b((?:[^>"']|"[^"]*"|'[^']*')*)>"#).unwrap();
+    let html = checkbox_re.replace_all(html, |found: &Captures| {
+        let checked = Regex::new(r"\bchecked\b").unwrap().is_match(&found[1]);
+        format!("<span class=\"task\">{}</span>", if checked { "☒" } else { "☐" })
+    });
+    let void_re = Regex::new(
+        r#"<(area|base|br|col|embed|hr|img|link|meta|param|source|track|wbr)\b((?:[^>"'/]|"[^"]*"|'[^']*')*)/?>"#,
+    )
+    .unwrap();
+    void_re
+        .replace_all(&html, |found: &Captures| format!("<{}{} />", &found[1], found[2].trim_end()))
+        .replace("&nbsp;", "&#160;")
+}
+
+/// Replaces every `<img>` with the image `image_for` gives for its `src`,
+/// or with its alt text if there is none: books cannot show images from
+/// the web or files they do not carry.
+pub fn book_images(html: &str, mut image_for: impl FnMut(&str) -> Option<String>) -> String {
+    let img_re = Regex::new(r#"<img\b(?:[^>"']|"[^"]*"|'[^']*')*>"#).unwrap();
+    let attribute = |tag: &str, name: &str| {
+        Regex::new(&format!(r#"\b{}="([^"]*)""#, name))
+            .unwrap()
+            .captures(tag)
+            .map(|found| unescape_html(&found[1]))
+    };
+    img_re
+        .replace_all(html, |found: &Captures| {
+            let tag = &found[0];
+            let src = attribute(tag, "src").unwrap_or_default();
+            let alt = attribute(tag, "alt").unwrap_or_default();
+            match image_for(&src) {
+                Some(image) => format!("<img src=\"{}\" alt=\"{}\">", escape_html(&image), escape_html(&alt)),
+                None => format!("<span class=\"missing-image\">{}</span>", escape_html(&alt)),
+            }
+        })
+        .into_owned()
+}
+
+/// Drops the links to files the book does not carry; those to `chapters`,
+/// anchors and the web stay.
+pub fn unlink_files(html: &str, chapters: &[String]) -> String {
+    let href_re = Regex::new(r#"\bhref="([^"]*)""#).unwrap();
+    href_re
+        .replace_all(html, |found: &Captures| {
+            let href = unescape_html(&found[1]);
+            let file = href.split('#').next().unwrap_or_default();
+            if !is_relative(&href) || file.is_empty() || chapters.iter().any(|chapter| chapter == file) {
+                found[0].to_string()
+            } else {
+                "href=\"#\"".to_string()
+            }
+        })
+        .into_owned()
+}
+
+/// A chapter of the book.
+pub fn chapter_page(title: &str, language: &str, body: &str) -> String {
+    format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n",
+            "<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" ",
+            "lang=\"{0}\" xml:lang=\"{0}\">\n<head>\n<meta charset=\"UTF-8\"/>\n<title>{1}</title>\n",
+            "<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n",
+            "<body>\n<section epub:type=\"chapter\" role=\"doc-chapter\">\n{2}\n</section>\n</body>\n</html>\n"
+        ),
+        escape_html(language),
+        escape_html(title),
+        body
+    )
+}
+
+/// The `<li>` items of `headings` in `file`, nested by level.
+fn heading_items(headings: &[&Heading], file: &str, out: &mut String) {
+    let mut ix = 0;
+    while ix < headings.len() {
+        let heading = headings[ix];
+        let end = headings[ix + 1..]
+            .iter()
+            .position(|next| next.level <= heading.level)
+            .map_or(headings.len(), |offset| ix + 1 + offset);
+        out.push_str(&format!(
+            "<li><a href=\"{}#{}\">{}</a>",
+            escape_html(file),
+            escape_html(&heading.slug),
+            escape_html(&heading.text)
+        ));
+        if end > ix + 1 {
+            out.push_str("<ol>");
+            heading_items(&headings[ix + 1..end], file, out);
+            out.push_str("</ol>");
+        }
+        out.push_str("</li>");
+        ix = end;
+    }
+}
+
+fn nav_items(items: &[NavItem], headings: &dyn Fn(&str) -> Vec<Heading>, out: &mut String) {
+    for item in items {
+        let mut children = String::new();
+        if let Some(file) = &item.url {
+            // Down to `###`; a top heading that repeats the chapter's title is left out.
+            let all = headings(file);
+            let shown: Vec<&Heading> = all
+                .iter()
+                .filter(|heading| heading.level <= 3 && !(heading.level == 1 && heading.text == item.title))
+                .collect();
+            heading_items(&shown, file, &mut children);
+        }
+        nav_items(&item.children, headings, &mut children);
+
+        match &item.url {
+            Some(file) => out.push_str(&format!("<li><a href=\"{}\">{}</a>", escape_html(file), escape_html(&item.title))),
+            None => out.push_str(&format!("<li><span>{}</span>", escape_html(&item.title))),
+        }
+        if !children.is_empty() {
+            out.push_str(&format!("<ol>{}</ol>", children));
+        }
+        out.push_str("</li>");
+    }
+}
+
+/// The navigation document: the chapters as `nav` gives them, with the
+/// file of each as its url, and the headings `headings` finds in each.
+pub fn nav_document(language: &str, nav: &[NavItem], headings: impl Fn(&str) -> Vec<Heading>) -> String {
+    let mut items = String::new();
+    nav_items(nav, &headings, &mut items);
+    format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n",
+            "<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" ",
+            "lang=\"{0}\" xml:lang=\"{0}\">\n<head>\n<meta charset=\"UTF-8\"/>\n<title>Índice</title>\n",
+            "<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n",
+            "<nav epub:type=\"toc\" id=\"toc\" role=\"doc-toc\">\n<h1>Índice</h1>\n<ol>{1}</ol>\n</nav>\n</body>\n</html>\n"
+        ),
+        escape_html(language),
+        items
+    )
+}
+
+/// A file of the book, as the package document lists it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestItem {
+    pub id: String,
+    /// Relative to the package document.
+    pub href: String,
+    pub media_type: String,
+    pub properties: Option<&'static str>,
+}
+
+/// `dc:date` only takes dates like `2024`, `2024-05` or `2024-05-17`.
+fn book_date(date: &str) -> Option<&str> {
+    let date_re = Regex::new(r"^\d{4}(?:-\d{2}(?:-\d{2})?)?").unwrap();
+    date_re.find(date.trim()).map(|found| found.as_str())
+}
+
+/// The package document: the book's metadata, every file in it and the
+/// chapters in reading order. `modified` is a [`timestamp`].
+pub fn package_document(metadata: &BookMetadata, modified: &str, manifest: &[ManifestItem], spine: &[String]) -> String {
+    let mut xml = format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{}\">\n",
+            "<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+            "<dc:identifier id=\"book-id\">{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>{}</dc:language>\n"
+        ),
+        escape_html(&metadata.language),
+        escape_html(&metadata.identifier),
+        escape_html(&metadata.title),
+        escape_html(&metadata.language)
+    );
+    for (ix, author) in metadata.authors.iter().enumerate() {
+        xml.push_str(&format!("<dc:creator id=\"creator{}\">{}</dc:creator>\n", ix + 1, escape_html(author)));
+    }
+    if let Some(date) = book_date(&metadata.date) {
+        xml.push_str(&format!("<dc:date>{}</dc:date>\n", date));
+    }
+    if !metadata.description.is_empty() {
+        xml.push_str(&format!("<dc:description>{}</dc:description>\n", escape_html(&metadata.description)));
+    }
+    if !metadata.publisher.is_empty() {
+        xml.push_str(&format!("<dc:publisher>{}</dc:publisher>\n", escape_html(&metadata.publisher)));
+    }
+    xml.push_str(&format!("<meta property=\"dcterms:modified\">{}</meta>\n</metadata>\n<manifest>\n", modified));
+    for item in manifest {
+        let properties = item.properties.map(|p| format!(" properties=\"{}\"", p)).unwrap_or_default();
+        xml.push_str(&format!(
+            "<item id=\"{}\" href=\"{}\" media-type=\"{}\"{}/>\n",
+            escape_html(&item.id),
+            escape_html(&item.href),
+            escape_html(&item.media_type),
+            properties
+        ));
+    }
+    xml.push_str("</manifest>\n<spine>\n");
+    for id in spine {
+        xml.push_str(&format!("<itemref idref=\"{}\"/>\n", escape_html(id)));
+    }
+    xml.push_str("</spine>\n</package>\n");
+    xml
+}
+
+/// The stylesheet of every chapter.
+pub fn epub_css() -> String {
+    format!("{}\n{}", EPUB_CSS, highlight_css())
+}
+
+/// `seconds` since the Unix epoch as `dcterms:modified` wants it, in UTC:
+/// `2024-05-17T09:30:00Z`.
+pub fn timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+    // Civil date from days since 1970-01-01, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{extract_headings, render_markdown, RenderOptions};
+
+    const DOC: &str = concat!(
+        "# Capítulo & <uno>\n\n",
+        "Texto con **negrita**, `código`, un salto  \nde línea y una nota[^n].\n\n",
+        "- [x] hecha\n- [ ] pendiente\n\n",
+        "| a | b |\n|---|---|\n| 1 | 2 |\n\n",
+        "![Imagen](img.png \"Título\")\n\n",
+        "---\n\n",
+        "```rust\nfn main() { println!(\"<hola>\"); }\n```\n\n",
+        "<p>a&nbsp;b<br>c</p>\n\n",
+        "[^n]: La nota.\n",
+    );
+
+    fn parse(xml: &str) {
+        let options = roxmltree::ParsingOptions { allow_dtd: true, ..roxmltree::ParsingOptions::default() };
+        if let Err(e) = roxmltree::Document::parse_with_options(xml, options) {
+            panic!("{}\n{}", e, xml);
+        }
+    }
+
+    #[test]
+    fn chapters_are_well_formed_xml() {
+        let body = xhtml(&render_markdown(DOC, &RenderOptions::default()));
+        assert!(body.contains("<span class=\"task\">☒</span>"), "{}", body);
+        assert!(!body.contains("&nbsp;"), "{}", body);
+        parse(&chapter_page("Capítulo & <uno>", "es", &body));
+    }
+
+    #[test]
+    fn navigation_and_package_are_well_formed_xml() {
+        let nav = vec![NavItem {
+            title: "Capítulo & <uno>".to_string(),
+            url: Some("chapter-001.xhtml".to_string()),
+            children: Vec::new(),
+        }];
+        parse(&nav_document("es", &nav, |_| extract_headings(DOC)));
+
+        let metadata = BookMetadata {
+            title: "Libro \"de\" <prueba>".to_string(),
+            authors: vec!["A & B".to_string()],
+            language: "es".to_string(),
+            identifier: "urn:isbn:123".to_string(),
+            ..BookMetadata::default()
+        };
+        let manifest = [ManifestItem {
+            id: "chapter-1".to_string(),
+            href: "chapter-001.xhtml".to_string(),
+            media_type: "application/xhtml+xml".to_string(),
+            properties: None,
+        }];
+        parse(&package_document(&metadata, &timestamp(0), &manifest, &["chapter-1".to_string()]));
+        parse(CONTAINER_XML);
+    }
+}
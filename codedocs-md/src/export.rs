@@ -0,0 +1,160 @@
+//! Rendered documents as standalone HTML pages.
+
+use crate::frontmatter::parse_front_matter;
+use crate::highlight::highlight_css;
+use crate::toc::{toc_html, TocOptions};
+use crate::{escape_html, extract_headings, unescape_html, Heading};
+use regex_lite::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+const EXPORT_CSS: &str = include_str!("export.css");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HtmlExportOptions {
+    /// Inline local images as `data:` URIs instead of linking them.
+    pub embed_images: bool,
+    /// Show the table of contents in a sidebar.
+    pub toc: bool,
+    /// Use the dark theme.
+    pub dark: bool,
+    /// Math and diagrams pre-rendered by the webview, in the order
+    /// [`enhancements`] lists them. Empty entries keep their source.
+    pub prerendered: Vec<String>,
+}
+
+impl Default for HtmlExportOptions {
+    fn default() -> Self {
+        Self {
+            embed_images: true,
+            toc: true,
+            dark: false,
+            prerendered: Vec::new(),
+        }
+    }
+}
+
+/// What the preview renders in the webview with KaTeX and Mermaid.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Enhancement {
+    Math { tex: String, display: bool },
+    Diagram { code: String },
+}
+
+fn enhancement_re() -> Regex {
+    Regex::new(concat!(
+        r#"<span\b[^>]*\bclass="math-inline"[^>]*>([\s\S]*?)</span>"#,
+        r#"|<div\b[^>]*\bclass="math-display"[^>]*>([\s\S]*?)</div>"#,
+        r#"|<pre\b[^>]*\bclass="mermaid-block"[^>]*>[\s\S]*?</pre>"#,
+    ))
+    .unwrap()
+}
+
+/// Text of an HTML fragment, like the DOM's `textContent`.
+pub(crate) fn text_content(html: &str) -> String {
+    let tag_re = Regex::new(r"<[^>]*>").unwrap();
+    unescape_html(&tag_re.replace_all(html, ""))
+}
+
+fn enhancement(found: &Captures) -> Option<Enhancement> {
+    if let Some(tex) = found.get(1) {
+        return Some(Enhancement::Math { tex: text_content(tex.as_str()), display: false });
+    }
+    if let Some(tex) = found.get(2) {
+        return Some(Enhancement::Math { tex: text_content(tex.as_str()), display: true });
+    }
+    let code_re = Regex::new(r#"\bdata-mermaid="([^"]*)""#).unwrap();
+    let code = code_re.captures(found.get(0)?.as_str())?.get(1)?.as_str();
+    Some(Enhancement::Diagram { code: unescape_html(code) })
+}
+
+/// The math and Mermaid placeholders of rendered HTML, in document order.
+pub fn enhancements(html: &str) -> Vec<Enhancement> {
+    enhancement_re().captures_iter(html).filter_map(|found| enhancement(&found)).collect()
+}
+
+/// Replaces each placeholder with its pre-rendered HTML. Placeholders with
+/// nothing rendered are left alone; diagrams then show their source.
+pub fn apply_enhancements(html: &str, rendered: &[String]) -> String {
+    let mut index = 0;
+    enhancement_re()
+        .replace_all(html, |found: &Captures| {
+            let whole = found.get(0).map_or("", |m| m.as_str()).to_string();
+            let Some(kind) = enhancement(found) else { return whole };
+            let output = rendered.get(index).filter(|output| !output.trim().is_empty());
+            index += 1;
+            match (kind, output) {
+                (Enhancement::Math { display: true, .. }, Some(output)) => {
+                    format!("<div class=\"math-display\">{}</div>", output)
+                }
+                (Enhancement::Math { .. }, Some(output)) => format!("<span class=\"math-inline\">{}</span>", output),
+                (Enhancement::Diagram { .. }, Some(output)) => format!("<div class=\"mermaid-block\">{}</div>", output),
+                (Enhancement::Diagram { code }, None) => format!("<pre><code>{}</code></pre>", escape_html(&code)),
+                (Enhancement::Math { .. }, None) => whole,
+            }
+        })
+        .into_owned()
+}
+
+/// Rewrites the `src` of every `<img>` with `rewrite`; `None` keeps it.
+pub fn rewrite_image_sources(html: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    let img_re = Regex::new(r#"(<img\b[^>]*\bsrc=")([^"]*)(")"#).unwrap();
+    img_re
+        .replace_all(html, |found: &Captures| {
+            let src = unescape_html(&found[2]);
+            match rewrite(&src) {
+                Some(replacement) => format!("{}{}{}", &found[1], escape_html(&replacement), &found[3]),
+                None => found[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Title of an exported document: the front matter's, the first top-level
+/// heading's or the file name.
+pub fn document_title(content: &str, path: &str) -> String {
+    let from_front_matter = parse_front_matter(content).map(|fm| fm.title()).filter(|t| !t.trim().is_empty());
+    from_front_matter
+        .or_else(|| extract_headings(content).into_iter().find(|h| h.level == 1).map(|h| h.text))
+        .unwrap_or_else(|| {
+            let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+            name.rsplit_once('.').map_or(name, |(stem, _)| stem).to_string()
+        })
+}
+
+/// The stylesheet of exported pages, with code highlighting for both themes.
+pub fn export_css() -> String {
+    format!("{}\n{}", EXPORT_CSS, highlight_css())
+}
+
+/// The HTML page every export wraps its content in: `head` goes after the
+/// title, `sidebar` before the document and `aside` after it.
+pub(crate) fn html_page(title: &str, head: &str, sidebar: &str, body: &str, aside: &str, dark: bool) -> String {
+    format!(
+        concat!(
+            "<!DOCTYPE html>\n<html lang=\"es\"{}>\n<head>\n<meta charset=\"utf-8\">\n",
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n",
+            "<title>{}</title>\n{}</head>\n<body>\n<div class=\"page\">\n",
+            "{}<main class=\"prose\">\n{}\n</main>\n{}</div>\n</body>\n</html>\n"
+        ),
+        if dark { " class=\"dark\"" } else { "" },
+        escape_html(title),
+        head,
+        sidebar,
+        body,
+        aside
+    )
+}
+
+/// A complete HTML page around a rendered document, with its styles inlined
+/// and, if asked for, the table of contents in a sidebar.
+pub fn standalone_html(title: &str, body: &str, headings: &[Heading], options: &HtmlExportOptions) -> String {
+    let sidebar = if options.toc && !headings.is_empty() {
+        format!("<aside class=\"sidebar\">{}</aside>\n", toc_html(headings, &TocOptions::default()))
+    } else {
+        String::new()
+    };
+    let head = format!("<style>\n{}</style>\n", export_css());
+    html_page(title, &head, &sidebar, body, "", options.dark)
+}
@@ -0,0 +1,474 @@
+//! Normalizes the style of a document without changing what it renders.
+//!
+//! The parser locates each construct and only the markup that differs from
+//! the configured style is rewritten, so everything else (front matter, code,
+//! math, HTML) stays byte for byte as written.
+
+use crate::edit::{apply_edits, TextEdit};
+use crate::lint::ListMarkerStyle;
+use crate::options;
+use crate::table::{container_prefix, line_start, ColumnAlignment, GfmTable};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex_lite::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Words that would start a new block if wrapping left them at the start of a line.
+static BLOCK_START: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(#{1,6}|[-+*]|\d{1,9}[.)]|=+|-+|>.*|\|.*|<.*|```.*|~~~.*)$").unwrap());
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmphasisStyle {
+    #[default]
+    Asterisk,
+    Underscore,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderedNumbering {
+    /// 1. 2. 3. counting from the list's first number.
+    #[default]
+    Increment,
+    /// Every item repeats the list's first number.
+    One,
+}
+
+/// The `[format]` table of `.codedocs.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    pub list_marker: ListMarkerStyle,
+    pub emphasis: EmphasisStyle,
+    pub ordered_numbering: OrderedNumbering,
+    /// Paragraph width; `0` keeps the line breaks as written.
+    pub wrap_width: usize,
+    /// Pad table cells so the columns line up.
+    pub table_padding: bool,
+    /// Format the document on every explicit save.
+    pub format_on_save: bool,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            list_marker: ListMarkerStyle::Consistent,
+            emphasis: EmphasisStyle::Asterisk,
+            ordered_numbering: OrderedNumbering::Increment,
+            wrap_width: 0,
+            table_padding: true,
+            format_on_save: false,
+        }
+    }
+}
+
+fn edit(content: &str, range: Range<usize>, replacement: impl Into<String>) -> TextEdit {
+    TextEdit {
+        start: range.start,
+        end: range.end,
+        original: content[range].to_string(),
+        replacement: replacement.into(),
+    }
+}
+
+/// Blocks kept verbatim: code, front matter, HTML and `$$` math, plus any
+/// paragraph with inline `$` math, which the parser does not know about.
+fn is_verbatim(verbatim: &[Range<usize>], range: &Range<usize>) -> bool {
+    verbatim.iter().any(|block| block.start <= range.start && range.start < block.end.max(block.start + 1))
+}
+
+fn verbatim_blocks(content: &str) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_) | Tag::HtmlBlock) => blocks.push(range),
+            Event::Start(Tag::Paragraph) if content[range.clone()].contains('$') => blocks.push(range),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// Formats `content` with the project's style.
+pub fn format_markdown(content: &str, config: &FormatConfig) -> String {
+    let normalized = apply_edits(content, &markup_edits(content, config)).unwrap_or_else(|| content.to_string());
+    let wrapped = if config.wrap_width > 0 {
+        apply_edits(&normalized, &wrap_edits(&normalized, config.wrap_width)).unwrap_or(normalized)
+    } else {
+        normalized
+    };
+    tidy_lines(&wrapped)
+}
+
+struct ListState {
+    ordered: Option<u64>,
+    index: u64,
+}
+
+/// List markers, numbering, emphasis delimiters and tables.
+fn markup_edits(content: &str, config: &FormatConfig) -> Vec<TextEdit> {
+    let verbatim = verbatim_blocks(content);
+    let mut edits = Vec::new();
+    let mut bullet = match config.list_marker {
+        ListMarkerStyle::Consistent => None,
+        ListMarkerStyle::Dash => Some('-'),
+        ListMarkerStyle::Asterisk => Some('*'),
+        ListMarkerStyle::Plus => Some('+'),
+    };
+    let emphasis = match config.emphasis {
+        EmphasisStyle::Asterisk => '*',
+        EmphasisStyle::Underscore => '_',
+    };
+    let mut lists: Vec<ListState> = Vec::new();
+    let mut table: Option<GfmTable> = None;
+
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        if table.is_none() && is_verbatim(&verbatim, &range) {
+            continue;
+        }
+        match event {
+            Event::Start(Tag::List(first)) => lists.push(ListState { ordered: first, index: 0 }),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                let Some(list) = lists.last_mut() else { continue };
+                let source = &content[range.clone()];
+                let at = range.start + (source.len() - source.trim_start().len());
+                match list.ordered {
+                    None => {
+                        let Some(marker) = content[at..].chars().next().filter(|c| matches!(c, '-' | '*' | '+')) else {
+                            continue;
+                        };
+                        let expected = *bullet.get_or_insert(marker);
+                        if marker != expected {
+                            edits.push(edit(content, at..at + 1, expected.to_string()));
+                        }
+                    }
+                    Some(start) => {
+                        let digits = content[at..].bytes().take_while(u8::is_ascii_digit).count();
+                        let number = match config.ordered_numbering {
+                            OrderedNumbering::Increment => start + list.index,
+                            OrderedNumbering::One => start,
+                        };
+                        let written = &content[at..at + digits];
+                        let renumbered = number.to_string();
+                        // A wider number would push the item's content past
+                        // the indentation its continuation lines use.
+                        let single_line = !source.trim_end().contains('\n');
+                        if written != renumbered && (renumbered.len() == digits || single_line) {
+                            edits.push(edit(content, at..at + digits, renumbered));
+                        }
+                    }
+                }
+                list.index += 1;
+            }
+            Event::Start(Tag::Emphasis) => {
+                edits.extend(delimiter_edits(content, range, 1, emphasis));
+            }
+            Event::Start(Tag::Strong) => {
+                edits.extend(delimiter_edits(content, range, 2, emphasis));
+            }
+            Event::Start(Tag::Table(alignments)) => {
+                table = Some(GfmTable {
+                    alignments: alignments.into_iter().map(ColumnAlignment::from).collect(),
+                    rows: Vec::new(),
+                    range,
+                });
+            }
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                if let Some(table) = table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Event::Start(Tag::TableCell) => {
+                if let Some(row) = table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(content[range].trim().to_string());
+                }
+            }
+            Event::End(TagEnd::Table) => {
+                if let Some(table) = table.take() {
+                    // Inline edits inside the table are dropped: the table is
+                    // rewritten from the cells as written.
+                    edits.retain(|e| !(table.range.start <= e.start && e.end <= table.range.end));
+                    if let Some(replacement) = table.render(content, config.table_padding) {
+                        edits.push(edit(content, table.range.clone(), replacement));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    edits.sort_by_key(|e| e.start);
+    edits.dedup_by(|b, a| b.start < a.end);
+    edits
+}
+
+/// Swaps the `width` delimiter characters at both ends of an emphasis span.
+/// Underscores are only used where they can close the span (not inside a word).
+fn delimiter_edits(content: &str, range: Range<usize>, width: usize, wanted: char) -> Vec<TextEdit> {
+    let source = &content[range.clone()];
+    if source.len() < width * 2 {
+        return Vec::new();
+    }
+    let current = source.chars().next().unwrap_or(wanted);
+    if current == wanted || !matches!(current, '*' | '_') || !source.ends_with(current) {
+        return Vec::new();
+    }
+    if wanted == '_' {
+        let before = content[..range.start].chars().next_back();
+        let after = content[range.end..].chars().next();
+        if before.is_some_and(char::is_alphanumeric) || after.is_some_and(char::is_alphanumeric) {
+            return Vec::new();
+        }
+    }
+    let delimiter = wanted.to_string().repeat(width);
+    vec![
+        edit(content, range.start..range.start + width, delimiter.clone()),
+        edit(content, range.end - width..range.end, delimiter),
+    ]
+}
+
+/// Inline events: what a paragraph or a tight list item is made of.
+fn is_inline(event: &Event) -> bool {
+    match event {
+        Event::Text(_)
+        | Event::Code(_)
+        | Event::InlineHtml(_)
+        | Event::InlineMath(_)
+        | Event::SoftBreak
+        | Event::HardBreak
+        | Event::FootnoteReference(_)
+        | Event::TaskListMarker(_) => true,
+        Event::Start(tag) => matches!(
+            tag,
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Superscript | Tag::Subscript | Tag::Link { .. } | Tag::Image { .. }
+        ),
+        Event::End(tag) => matches!(
+            tag,
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Superscript | TagEnd::Subscript | TagEnd::Link | TagEnd::Image
+        ),
+        _ => false,
+    }
+}
+
+/// Re-flows paragraphs and tight list items to `width` columns.
+fn wrap_edits(content: &str, width: usize) -> Vec<TextEdit> {
+    let verbatim = verbatim_blocks(content);
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    let mut blocks: Vec<bool> = Vec::new();
+    let mut run: Option<Range<usize>> = None;
+
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        if is_inline(&event) {
+            if blocks.last() == Some(&true) {
+                run = Some(match run {
+                    Some(current) => current.start..range.end.max(current.end),
+                    None => range,
+                });
+            }
+            continue;
+        }
+        runs.extend(run.take());
+        match event {
+            Event::Start(tag) => blocks.push(matches!(tag, Tag::Paragraph | Tag::Item)),
+            Event::End(_) => {
+                blocks.pop();
+            }
+            _ => {}
+        }
+    }
+    runs.extend(run);
+
+    runs.into_iter()
+        .filter(|run| !is_verbatim(&verbatim, run))
+        .filter_map(|run| {
+            let wrapped = wrap_run(content, run.clone(), width)?;
+            (wrapped != content[run.clone()]).then(|| edit(content, run, wrapped))
+        })
+        .collect()
+}
+
+fn wrap_run(content: &str, run: Range<usize>, width: usize) -> Option<String> {
+    let source = &content[run.clone()];
+    // A task list marker sits on the item's first line; leave those items alone.
+    if source.starts_with('[') && source.get(2..3) == Some("]") {
+        return None;
+    }
+    let prefix = container_prefix(content, run.start);
+    let quotes = prefix.matches('>').count();
+
+    // Logical lines without their container prefix; a line ending in a hard
+    // break closes a segment that is wrapped on its own.
+    let mut segments: Vec<(Vec<&str>, &str)> = vec![(Vec::new(), "")];
+    for (ix, line) in source.split('\n').enumerate() {
+        let mut text = line.trim_end_matches('\r');
+        if ix > 0 {
+            text = text.trim_start();
+            for _ in 0..quotes {
+                match text.strip_prefix('>') {
+                    Some(rest) => text = rest.trim_start(),
+                    None => break,
+                }
+            }
+        }
+        let hard_break = if text.ends_with('\\') {
+            Some("\\")
+        } else if text.ends_with("  ") {
+            Some("  ")
+        } else {
+            None
+        };
+        let segment = segments.last_mut()?;
+        segment.0.extend(text.split_whitespace());
+        if let Some(marker) = hard_break {
+            segment.1 = marker;
+            segments.push((Vec::new(), ""));
+        }
+    }
+
+    let first_column = run.start - line_start(content, run.start);
+    let mut out = String::new();
+    let mut column = first_column;
+    let mut at_line_start = true;
+    for (words, hard_break) in segments.iter().filter(|(words, _)| !words.is_empty()) {
+        for (ix, word) in words.iter().enumerate() {
+            let word = if *hard_break == "\\" && ix + 1 == words.len() {
+                word.strip_suffix('\\').unwrap_or(word)
+            } else {
+                word
+            };
+            let length = word.chars().count();
+            let fits = column + 1 + length <= width;
+            if at_line_start {
+                out.push_str(word);
+                column += length;
+                at_line_start = false;
+            } else if fits || BLOCK_START.is_match(word) {
+                out.push(' ');
+                out.push_str(word);
+                column += 1 + length;
+            } else {
+                out.push('\n');
+                out.push_str(&prefix);
+                out.push_str(word);
+                column = prefix.chars().count() + length;
+            }
+        }
+        if !hard_break.is_empty() {
+            out.push_str(hard_break);
+            out.push('\n');
+            out.push_str(&prefix);
+            column = prefix.chars().count();
+            at_line_start = true;
+        }
+    }
+    if let Some(stripped) = out.strip_suffix(&format!("\n{}", prefix)) {
+        out.truncate(stripped.len());
+    }
+    Some(out).filter(|out| !out.is_empty())
+}
+
+/// Trailing whitespace, runs of blank lines and the final newline, outside
+/// verbatim blocks. Two or more trailing spaces before another line of text
+/// are a hard break and become exactly two.
+fn tidy_lines(content: &str) -> String {
+    let verbatim = verbatim_blocks(content);
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(content.len());
+    let mut offset = 0;
+    let mut blank_run = 0;
+
+    for (ix, raw) in lines.iter().enumerate() {
+        let start = offset;
+        offset += raw.len();
+        if is_verbatim(&verbatim, &(start..start + 1)) {
+            out.push_str(raw);
+            blank_run = 0;
+            continue;
+        }
+        let line = raw.trim_end_matches(['\r', '\n']);
+        let ending = &raw[line.len()..];
+        let text = line.trim_end_matches([' ', '\t']);
+        if text.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 || out.is_empty() {
+                continue;
+            }
+            out.push_str(ending);
+            continue;
+        }
+        blank_run = 0;
+        out.push_str(text);
+        let trailing = &line[text.len()..];
+        let continues = lines.get(ix + 1).is_some_and(|next| !next.trim().is_empty());
+        let is_heading = text.trim_start().starts_with('#');
+        if trailing.len() >= 2 && !trailing.contains('\t') && continues && !is_heading {
+            out.push_str("  ");
+        }
+        out.push_str(ending);
+    }
+
+    let trimmed = out.trim_end_matches(['\n', '\r']).len();
+    out.truncate(trimmed);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = concat!(
+        "---\ntitle: Prueba   \n---\n",
+        "# Título  \n\n\n\n",
+        "Un párrafo con _cursiva_, __negrita__ y $a_b * c_d$ en línea,\n",
+        "que sigue en otra línea.\n\n",
+        "Otro con _cursiva_ y __negrita__, bastante largo como para\npartirlo en varias líneas al formatear.\n\n",
+        "* uno\n* dos\n  - anidado\n\n",
+        "3. tres\n3. cuatro\n\n",
+        "| a | bb |\n|:-|--:|\n| ccc | d |\n\n",
+        "> cita con *énfasis*\n> - y lista\n\n",
+        "```rust\nlet x = 1;   \n\n\n```\n\n",
+        "<div>\n_html_\n</div>\n",
+        "Final sin salto",
+    );
+
+    fn configs() -> Vec<FormatConfig> {
+        vec![
+            FormatConfig::default(),
+            FormatConfig {
+                list_marker: ListMarkerStyle::Plus,
+                emphasis: EmphasisStyle::Underscore,
+                ordered_numbering: OrderedNumbering::One,
+                wrap_width: 40,
+                table_padding: false,
+                format_on_save: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn formatting_twice_changes_nothing() {
+        for config in configs() {
+            let once = format_markdown(DOC, &config);
+            assert_eq!(format_markdown(&once, &config), once, "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn verbatim_blocks_are_left_alone() {
+        let formatted = format_markdown(DOC, &configs()[1]);
+        assert!(formatted.starts_with("---\ntitle: Prueba   \n---\n"), "{}", formatted);
+        assert!(formatted.contains("Un párrafo con _cursiva_, __negrita__ y $a_b * c_d$"), "{}", formatted);
+        assert!(formatted.contains("Otro con _cursiva_ y __negrita__,\n"), "{}", formatted);
+        assert!(formatted.contains("```rust\nlet x = 1;   \n\n\n```\n"), "{}", formatted);
+        assert!(formatted.contains("<div>\n_html_\n</div>\n"), "{}", formatted);
+        assert!(formatted.ends_with("Final sin salto\n"), "{}", formatted);
+    }
+}
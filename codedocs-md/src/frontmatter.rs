@@ -0,0 +1,475 @@
+use crate::options;
+use pulldown_cmark::{Event, MetadataBlockKind, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetaValue {
+    Text(String),
+    Date(String),
+    Bool(bool),
+    Number(String),
+    List(Vec<String>),
+    Other(String),
+}
+
+impl MetaValue {
+    pub fn display(&self) -> String {
+        match self {
+            MetaValue::Text(s) | MetaValue::Date(s) | MetaValue::Number(s) | MetaValue::Other(s) => {
+                s.clone()
+            }
+            MetaValue::Bool(b) => b.to_string(),
+            MetaValue::List(items) => items.join(", "),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatter {
+    pub format: FrontMatterFormat,
+    pub fields: Vec<(String, MetaValue)>,
+    /// Byte range of the block body (without the delimiter lines) in the source.
+    pub body: Range<usize>,
+    pub error: Option<String>,
+}
+
+impl FrontMatter {
+    pub fn get(&self, key: &str) -> Option<&MetaValue> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn title(&self) -> String {
+        self.get("title").map(|v| v.display()).unwrap_or_default()
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        match self.get("tags") {
+            Some(MetaValue::List(items)) => items.clone(),
+            Some(MetaValue::Text(s)) => s
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn date(&self) -> String {
+        self.get("date").map(|v| v.display()).unwrap_or_default()
+    }
+
+    pub fn draft(&self) -> bool {
+        matches!(self.get("draft"), Some(MetaValue::Bool(true)))
+    }
+}
+
+pub fn parse_front_matter(content: &str) -> Option<FrontMatter> {
+    let parser = Parser::new_ext(content, options()).into_offset_iter();
+
+    let mut format = None;
+    let mut body: Option<Range<usize>> = None;
+    for (event, range) in parser {
+        match event {
+            Event::Start(Tag::MetadataBlock(kind)) if range.start == 0 => {
+                format = Some(match kind {
+                    MetadataBlockKind::YamlStyle => FrontMatterFormat::Yaml,
+                    MetadataBlockKind::PlusesStyle => FrontMatterFormat::Toml,
+                });
+            }
+            Event::Text(_) if format.is_some() => {
+                body = Some(match body {
+                    Some(b) => b.start..range.end,
+                    None => range,
+                });
+            }
+            Event::End(TagEnd::MetadataBlock(_)) => break,
+            _ => return None,
+        }
+    }
+
+    let format = format?;
+    let body = body?;
+    let source = &content[body.clone()];
+    let (fields, error) = match format {
+        FrontMatterFormat::Yaml => parse_yaml(source),
+        FrontMatterFormat::Toml => parse_toml(source),
+    };
+
+    Some(FrontMatter {
+        format,
+        fields,
+        body,
+        error,
+    })
+}
+
+fn looks_like_date(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() >= 10
+        && b[..4].iter().all(u8::is_ascii_digit)
+        && b[4] == b'-'
+        && b[5..7].iter().all(u8::is_ascii_digit)
+        && b[7] == b'-'
+        && b[8..10].iter().all(u8::is_ascii_digit)
+}
+
+fn text_or_date(s: String) -> MetaValue {
+    if looks_like_date(&s) {
+        MetaValue::Date(s)
+    } else {
+        MetaValue::Text(s)
+    }
+}
+
+fn parse_yaml(source: &str) -> (Vec<(String, MetaValue)>, Option<String>) {
+    let value = match serde_yaml::from_str::<serde_yaml::Value>(source) {
+        Ok(v) => v,
+        Err(e) => return (Vec::new(), Some(e.to_string())),
+    };
+    let serde_yaml::Value::Mapping(map) = value else {
+        return (Vec::new(), Some("El front matter no es un mapa clave: valor".to_string()));
+    };
+
+    let fields = map
+        .into_iter()
+        .filter_map(|(k, v)| Some((yaml_scalar(&k)?, yaml_value(v))))
+        .collect();
+    (fields, None)
+}
+
+fn yaml_scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Null => Some(String::new()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn yaml_value(value: serde_yaml::Value) -> MetaValue {
+    match value {
+        serde_yaml::Value::Null => MetaValue::Text(String::new()),
+        serde_yaml::Value::Bool(b) => MetaValue::Bool(b),
+        serde_yaml::Value::Number(n) => MetaValue::Number(n.to_string()),
+        serde_yaml::Value::String(s) => text_or_date(s),
+        serde_yaml::Value::Sequence(items) => MetaValue::List(
+            items
+                .iter()
+                .map(|item| {
+                    yaml_scalar(item).unwrap_or_else(|| {
+                        serde_yaml::to_string(item).unwrap_or_default().trim().to_string()
+                    })
+                })
+                .collect(),
+        ),
+        other => MetaValue::Other(serde_yaml::to_string(&other).unwrap_or_default().trim().to_string()),
+    }
+}
+
+fn parse_toml(source: &str) -> (Vec<(String, MetaValue)>, Option<String>) {
+    match source.parse::<toml::Table>() {
+        Ok(table) => (
+            table.into_iter().map(|(k, v)| (k, toml_value(v))).collect(),
+            None,
+        ),
+        Err(e) => (Vec::new(), Some(e.message().to_string())),
+    }
+}
+
+fn toml_scalar(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn toml_value(value: toml::Value) -> MetaValue {
+    match value {
+        toml::Value::String(s) => text_or_date(s),
+        toml::Value::Integer(i) => MetaValue::Number(i.to_string()),
+        toml::Value::Float(f) => MetaValue::Number(f.to_string()),
+        toml::Value::Boolean(b) => MetaValue::Bool(b),
+        toml::Value::Datetime(d) => MetaValue::Date(d.to_string()),
+        toml::Value::Array(items) => MetaValue::List(items.iter().map(toml_scalar).collect()),
+        toml::Value::Table(t) => MetaValue::Other(t.to_string().trim().to_string()),
+    }
+}
+
+/// Sets (or removes, with `None`) a top-level key, rewriting only the lines that
+/// belong to that key so the rest of the block keeps its original formatting.
+/// Written lines end like the document's own.
+pub fn update_front_matter(content: &str, key: &str, value: Option<MetaValue>) -> String {
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let Some(fm) = parse_front_matter(content) else {
+        let Some(value) = value else {
+            return content.to_string();
+        };
+        if let Some((format, body)) = empty_block(content) {
+            let line = render_entry(format, key, &value, None, newline);
+            return format!("{}{}{}{}", &content[..body.start], line, newline, &content[body.end..]);
+        }
+        let line = render_entry(FrontMatterFormat::Yaml, key, &value, None, newline);
+        return format!("---{1}{0}{1}---{1}{1}{2}", line, newline, content);
+    };
+
+    let body = &content[fm.body.clone()];
+    let mut lines: Vec<&str> = body.lines().collect();
+    let span = find_key_span(fm.format, &lines, key);
+
+    let rendered = value.map(|v| {
+        let block_indent = span.as_ref().and_then(|s| block_list_indent(fm.format, &lines[s.clone()]));
+        render_entry(fm.format, key, &v, block_indent, newline)
+    });
+
+    let mut new_lines: Vec<String>;
+    match (span, rendered) {
+        (Some(span), Some(rendered)) => {
+            new_lines = lines[..span.start].iter().map(|l| l.to_string()).collect();
+            new_lines.push(rendered);
+            new_lines.extend(lines[span.end..].iter().map(|l| l.to_string()));
+        }
+        (Some(span), None) => {
+            lines.drain(span);
+            new_lines = lines.iter().map(|l| l.to_string()).collect();
+        }
+        (None, Some(rendered)) => {
+            let insert_at = match fm.format {
+                // Keys after the first `[table]` header would belong to that table.
+                FrontMatterFormat::Toml => lines
+                    .iter()
+                    .position(|l| l.trim_start().starts_with('['))
+                    .unwrap_or(lines.len()),
+                FrontMatterFormat::Yaml => lines.len(),
+            };
+            let insert_at = trim_blank_tail(&lines[..insert_at]);
+            new_lines = lines.iter().map(|l| l.to_string()).collect();
+            new_lines.insert(insert_at, rendered);
+        }
+        (None, None) => return content.to_string(),
+    }
+
+    let mut new_body = new_lines.join(newline);
+    new_body.push_str(newline);
+
+    let mut result = String::with_capacity(content.len() + 32);
+    result.push_str(&content[..fm.body.start]);
+    result.push_str(&new_body);
+    result.push_str(&content[fm.body.end..]);
+    result
+}
+
+/// A front matter block with nothing but blank lines in it, which the parser
+/// does not report, and the range between its delimiters.
+fn empty_block(content: &str) -> Option<(FrontMatterFormat, Range<usize>)> {
+    let mut lines = content.split_inclusive('\n');
+    let opening = lines.next()?;
+    let (format, delimiter) = match opening.trim_end() {
+        "---" => (FrontMatterFormat::Yaml, "---"),
+        "+++" => (FrontMatterFormat::Toml, "+++"),
+        _ => return None,
+    };
+    let mut end = opening.len();
+    for line in lines {
+        if line.trim_end() == delimiter {
+            return Some((format, opening.len()..end));
+        }
+        if !line.trim().is_empty() {
+            return None;
+        }
+        end += line.len();
+    }
+    None
+}
+
+fn trim_blank_tail(lines: &[&str]) -> usize {
+    let mut end = lines.len();
+    while end > 0 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    end
+}
+
+fn line_key(format: FrontMatterFormat, line: &str) -> Option<String> {
+    if line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '-' || c == '[') {
+        return None;
+    }
+    let sep = match format {
+        FrontMatterFormat::Yaml => ':',
+        FrontMatterFormat::Toml => '=',
+    };
+    let (raw_key, _) = line.split_once(sep)?;
+    let key = raw_key.trim().trim_matches(|c| c == '"' || c == '\'');
+    if key.is_empty() {
+        None
+    } else {
+        Some(key.to_string())
+    }
+}
+
+fn is_continuation(format: FrontMatterFormat, line: &str) -> bool {
+    if line.trim().is_empty() || line.starts_with(|c: char| c.is_whitespace()) {
+        return true;
+    }
+    match format {
+        FrontMatterFormat::Yaml => line.starts_with('-') && !line.starts_with("---"),
+        FrontMatterFormat::Toml => line.starts_with(']'),
+    }
+}
+
+fn find_key_span(format: FrontMatterFormat, lines: &[&str], key: &str) -> Option<Range<usize>> {
+    let start = lines
+        .iter()
+        .take_while(|l| format == FrontMatterFormat::Yaml || !l.trim_start().starts_with('['))
+        .position(|l| line_key(format, l).as_deref() == Some(key))?;
+
+    let mut end = start + 1;
+    while end < lines.len() && is_continuation(format, lines[end]) {
+        end += 1;
+    }
+    Some(start..start + trim_blank_tail(&lines[start..end]).max(1))
+}
+
+/// Returns the list item prefix (e.g. `"  - "`) when a YAML key uses a block sequence.
+fn block_list_indent(format: FrontMatterFormat, span: &[&str]) -> Option<String> {
+    if format != FrontMatterFormat::Yaml || span.len() < 2 {
+        return None;
+    }
+    let (_, rest) = span[0].split_once(':')?;
+    if !rest.trim().is_empty() {
+        return None;
+    }
+    let item = span[1];
+    let trimmed = item.trim_start();
+    if !trimmed.starts_with('-') {
+        return None;
+    }
+    Some(format!("{}- ", &item[..item.len() - trimmed.len()]))
+}
+
+fn render_entry(
+    format: FrontMatterFormat,
+    key: &str,
+    value: &MetaValue,
+    block_indent: Option<String>,
+    newline: &str,
+) -> String {
+    match format {
+        FrontMatterFormat::Yaml => {
+            if let (MetaValue::List(items), Some(prefix)) = (value, block_indent) {
+                let mut out = format!("{}:", key);
+                for item in items {
+                    out.push_str(newline);
+                    out.push_str(&prefix);
+                    out.push_str(&yaml_quote(item, false));
+                }
+                return out;
+            }
+            format!("{}: {}", key, yaml_literal(value))
+        }
+        FrontMatterFormat::Toml => format!("{} = {}", key, toml_literal(value)),
+    }
+}
+
+fn yaml_literal(value: &MetaValue) -> String {
+    match value {
+        MetaValue::Text(s) => yaml_quote(s, false),
+        MetaValue::Date(s) | MetaValue::Number(s) | MetaValue::Other(s) => s.clone(),
+        MetaValue::Bool(b) => b.to_string(),
+        MetaValue::List(items) => format!(
+            "[{}]",
+            items.iter().map(|i| yaml_quote(i, true)).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+fn yaml_quote(s: &str, in_flow: bool) -> String {
+    let reserved = matches!(
+        s.to_ascii_lowercase().as_str(),
+        "" | "~" | "null" | "true" | "false" | "yes" | "no" | "on" | "off"
+    );
+    let needs_quotes = reserved
+        || s.parse::<f64>().is_ok()
+        || looks_like_date(s)
+        || s.trim() != s
+        || s.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.ends_with(':')
+        || s.contains('\n')
+        || (in_flow && s.contains(|c: char| ",[]{}".contains(c)));
+    if needs_quotes {
+        format!("\"{}\"", escape_quoted(s))
+    } else {
+        s.to_string()
+    }
+}
+
+fn toml_literal(value: &MetaValue) -> String {
+    match value {
+        MetaValue::Text(s) => format!("\"{}\"", escape_quoted(s)),
+        MetaValue::Date(s) if s.len() == 10 && looks_like_date(s) => s.clone(),
+        MetaValue::Date(s) => format!("\"{}\"", escape_quoted(s)),
+        MetaValue::Number(s) | MetaValue::Other(s) => s.clone(),
+        MetaValue::Bool(b) => b.to_string(),
+        MetaValue::List(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(|i| format!("\"{}\"", escape_quoted(i)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn escape_quoted(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crlf_documents_keep_their_line_endings() {
+        let content = "---\r\ntitle: Uno\r\ntags:\r\n  - a\r\n---\r\n\r\nTexto\r\n";
+        let tags = MetaValue::List(vec!["a".to_string(), "b".to_string()]);
+        let updated = update_front_matter(content, "tags", Some(tags));
+        assert_eq!(updated, "---\r\ntitle: Uno\r\ntags:\r\n  - a\r\n  - b\r\n---\r\n\r\nTexto\r\n");
+
+        let added = update_front_matter("Texto\r\n", "title", Some(MetaValue::Text("Uno".to_string())));
+        assert_eq!(added, "---\r\ntitle: Uno\r\n---\r\n\r\nTexto\r\n");
+    }
+
+    #[test]
+    fn empty_blocks_are_filled_in_place() {
+        let title = || Some(MetaValue::Text("Uno".to_string()));
+        assert_eq!(update_front_matter("---\n---\n\nTexto\n", "title", title()), "---\ntitle: Uno\n---\n\nTexto\n");
+        let toml = update_front_matter("+++\r\n\r\n+++\r\nTexto\r\n", "title", title());
+        assert_eq!(toml, "+++\r\ntitle = \"Uno\"\r\n+++\r\nTexto\r\n");
+    }
+
+    #[test]
+    fn untouched_keys_keep_their_formatting() {
+        let content = "---\ntitle:   \"Uno\"   # comentario\ndraft: true\n---\nTexto\n";
+        let updated = update_front_matter(content, "draft", None);
+        assert_eq!(updated, "---\ntitle:   \"Uno\"   # comentario\n---\nTexto\n");
+    }
+}
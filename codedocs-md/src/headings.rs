@@ -0,0 +1,136 @@
+use crate::{options, Slugger};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+    /// Byte range of the whole heading (markers included) in the source.
+    pub range: Range<usize>,
+    /// 1-based line where the heading starts.
+    pub line: usize,
+}
+
+/// Plain text of a run of inline events, ignoring formatting and raw HTML.
+pub(crate) fn inline_text<'a, 'b: 'a>(events: impl IntoIterator<Item = &'a Event<'b>>) -> String {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Fills in the `id` of every heading: `{#custom-id}` attributes are kept as
+/// written, the rest get a deduplicated slug of their text.
+pub(crate) fn assign_heading_ids<'a>(
+    events: impl Iterator<Item = (Event<'a>, Range<usize>)>,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut slugger = Slugger::default();
+    let mut out = Vec::new();
+    let mut heading_start = None;
+
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                heading_start = Some(out.len());
+                out.push((event, range));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(start) = heading_start.take() {
+                    let text = inline_text(out[start + 1..].iter().map(|(e, _)| e));
+                    if let (Event::Start(Tag::Heading { id, .. }), _) = &mut out[start] {
+                        let slug = match id {
+                            Some(custom) => slugger.reserve(custom),
+                            None => slugger.slug(&text),
+                        };
+                        *id = Some(slug.into());
+                    }
+                }
+                out.push((event, range));
+            }
+            _ => out.push((event, range)),
+        }
+    }
+
+    out
+}
+
+pub fn extract_headings(content: &str) -> Vec<Heading> {
+    let parser = Parser::new_ext(content, options()).into_offset_iter();
+    collect_headings(content, &assign_heading_ids(parser))
+}
+
+/// Builds the heading list from events already processed by [`assign_heading_ids`].
+pub(crate) fn collect_headings(content: &str, events: &[(Event, Range<usize>)]) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut heading_start = None;
+    let mut line = 1;
+    let mut line_offset = 0;
+    for (ix, (event, _)) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::Heading { .. }) => heading_start = Some(ix),
+            Event::End(TagEnd::Heading(_)) => {
+                let Some(start) = heading_start.take() else { continue };
+                let (Event::Start(Tag::Heading { level, id, .. }), range) = &events[start] else {
+                    continue;
+                };
+                line += content[line_offset..range.start].matches('\n').count();
+                line_offset = range.start;
+                headings.push(Heading {
+                    level: *level as u8,
+                    text: inline_text(events[start + 1..ix].iter().map(|(e, _)| e))
+                        .trim()
+                        .to_string(),
+                    slug: id.as_deref().unwrap_or_default().to_string(),
+                    range: range.clone(),
+                    line,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    headings
+}
+
+/// Strips a trailing `{#id .class}` attribute block from a heading line.
+fn strip_attributes(text: &str) -> &str {
+    let trimmed = text.trim_end();
+    match trimmed.rfind('{') {
+        Some(open) if trimmed.ends_with('}') => trimmed[..open].trim_end(),
+        _ => trimmed,
+    }
+}
+
+/// Byte range of the heading's own text in the source: without the `#`
+/// markers, closing hashes, attributes or setext underline.
+pub(crate) fn heading_text_range(content: &str, heading: &Heading) -> Range<usize> {
+    let source = &content[heading.range.clone()];
+    let leading = source.len() - source.trim_start().len();
+    let rest = &source[leading..];
+
+    if rest.starts_with('#') {
+        let line = rest.split('\n').next().unwrap_or_default().trim_end_matches('\r');
+        let after_marker = line.trim_start_matches('#');
+        let text_start = leading + (line.len() - after_marker.trim_start().len());
+        let mut text = strip_attributes(after_marker.trim_start());
+        let without_hashes = text.trim_end_matches('#');
+        if without_hashes.is_empty() || without_hashes.ends_with([' ', '\t']) {
+            text = without_hashes.trim_end();
+        }
+        let start = heading.range.start + text_start;
+        return start..start + text.len();
+    }
+
+    // Setext: every line but the underline.
+    let body = rest.trim_end().rsplit_once('\n').map(|(body, _)| body).unwrap_or(rest);
+    let text = strip_attributes(body.trim_end_matches('\r'));
+    let start = heading.range.start + leading;
+    start..start + text.len()
+}
@@ -0,0 +1,206 @@
+use crate::escape_html;
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, line_tokens_to_classed_spans, ClassStyle};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// What a fenced block's info string asks for, e.g.
+/// ```` ```rust title="main.rs" {1,3-5} showLineNumbers ````.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodeInfo {
+    pub lang: Option<String>,
+    pub title: Option<String>,
+    pub highlight_lines: BTreeSet<usize>,
+    pub line_numbers: bool,
+}
+
+impl CodeInfo {
+    pub fn parse(info: &str) -> Self {
+        let mut code_info = Self::default();
+        let mut rest = info.trim();
+
+        let lang_end = rest.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(rest.len());
+        if lang_end > 0 {
+            code_info.lang = Some(rest[..lang_end].to_string());
+        }
+        rest = &rest[lang_end..];
+
+        while let Some(token_start) = rest.find(|c: char| !c.is_whitespace()) {
+            rest = &rest[token_start..];
+            if let Some(ranges) = rest.strip_prefix('{') {
+                let end = ranges.find('}').unwrap_or(ranges.len());
+                code_info.highlight_lines.extend(parse_line_ranges(&ranges[..end]));
+                rest = ranges.get(end + 1..).unwrap_or_default();
+            } else if let Some((key, value)) = rest.split_once('=').filter(|(k, _)| !k.contains(char::is_whitespace)) {
+                let (value, remainder) = match value.strip_prefix(['"', '\'']) {
+                    Some(quoted) => {
+                        let quote = value.as_bytes()[0] as char;
+                        let end = quoted.find(quote).unwrap_or(quoted.len());
+                        (&quoted[..end], quoted.get(end + 1..).unwrap_or_default())
+                    }
+                    None => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+                if matches!(key, "title" | "filename" | "file") {
+                    code_info.title = Some(value.to_string());
+                }
+                rest = remainder;
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                if matches!(&rest[..end], "showLineNumbers" | "lineNumbers" | "linenos") {
+                    code_info.line_numbers = true;
+                }
+                rest = &rest[end..];
+            }
+        }
+
+        code_info
+    }
+}
+
+/// Parses `1,3-5` into the set `{1, 3, 4, 5}`.
+fn parse_line_ranges(spec: &str) -> BTreeSet<usize> {
+    let mut lines = BTreeSet::new();
+    for part in spec.split(',').map(str::trim) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.trim().parse::<usize>(), end.trim().parse::<usize>()) {
+                    lines.extend(start..=end.min(start + 10_000));
+                }
+            }
+            None => {
+                if let Ok(line) = part.parse() {
+                    lines.insert(line);
+                }
+            }
+        }
+    }
+    lines
+}
+
+fn scope_classes(scope: Scope) -> String {
+    scope
+        .build_string()
+        .split('.')
+        .map(|atom| format!("hl-{}", atom))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Highlights each line separately so every `<span class="line">` is balanced
+/// on its own and can carry line numbers and highlight classes.
+fn highlighted_lines(code: &str, lang: Option<&str>) -> Vec<String> {
+    let syntaxes = syntax_set();
+    let syntax = lang
+        .and_then(|l| syntaxes.find_syntax_by_token(l))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut lines = Vec::new();
+
+    for line in LinesWithEndings::from(code) {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let mut html: String = stack
+            .as_slice()
+            .iter()
+            .map(|scope| format!("<span class=\"{}\">", scope_classes(*scope)))
+            .collect();
+        let open_before = stack.len() as isize;
+
+        let rendered = state.parse_line(line, syntaxes).ok().and_then(|ops| {
+            let ops: Vec<_> = ops.into_iter().map(|(i, op)| (i.min(text.len()), op)).collect();
+            line_tokens_to_classed_spans(text, &ops, CLASS_STYLE, &mut stack).ok()
+        });
+        match rendered {
+            Some((spans, delta)) => {
+                html.push_str(&spans);
+                html.push_str(&"</span>".repeat((open_before + delta).max(0) as usize));
+            }
+            None => {
+                html = escape_html(text);
+                stack = ScopeStack::new();
+            }
+        }
+        lines.push(html);
+    }
+
+    lines
+}
+
+/// Renders a fenced code block as highlighted, classed HTML.
+pub fn highlight_code(code: &str, info: &str) -> String {
+    let info = CodeInfo::parse(info);
+    let lines = highlighted_lines(code, info.lang.as_deref());
+
+    let mut html = String::from("<div class=\"code-block\">");
+    if let Some(title) = &info.title {
+        html.push_str(&format!("<div class=\"code-title\">{}</div>", escape_html(title)));
+    }
+
+    let mut code_classes = Vec::new();
+    if let Some(lang) = &info.lang {
+        code_classes.push(format!("language-{}", escape_html(lang)));
+    }
+    if info.line_numbers {
+        code_classes.push("line-numbers".to_string());
+    }
+    if code_classes.is_empty() {
+        html.push_str("<pre class=\"hl-code\"><code>");
+    } else {
+        html.push_str(&format!("<pre class=\"hl-code\"><code class=\"{}\">", code_classes.join(" ")));
+    }
+
+    for (ix, line) in lines.iter().enumerate() {
+        let class = if info.highlight_lines.contains(&(ix + 1)) { "line highlighted" } else { "line" };
+        html.push_str(&format!("<span class=\"{}\">{}</span>\n", class, line));
+    }
+    html.push_str("</code></pre></div>");
+    html
+}
+
+fn scoped_css(css: &str, scope: &str) -> String {
+    css.lines()
+        .map(|line| match line.strip_suffix('{') {
+            Some(selectors) if !scope.is_empty() => format!(
+                "{} {{",
+                selectors
+                    .split(',')
+                    .map(|s| format!("{} {}", scope, s.trim()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Stylesheet for the `hl-*` classes: the light theme by default and the dark
+/// one under `.dark`, matching the app's theme toggle.
+pub fn highlight_css() -> String {
+    let themes = ThemeSet::load_defaults();
+    let mut css = String::new();
+    for (name, scope) in [(LIGHT_THEME, ""), (DARK_THEME, ".dark")] {
+        if let Some(theme) = themes.themes.get(name) {
+            if let Ok(theme_css) = css_for_theme_with_class_style(theme, CLASS_STYLE) {
+                css.push_str(&scoped_css(&theme_css, scope));
+                css.push('\n');
+            }
+        }
+    }
+    css
+}
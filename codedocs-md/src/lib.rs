@@ -0,0 +1,74 @@
+pub mod assets;
+pub mod check;
+pub mod config;
+pub mod edit;
+pub mod epub;
+pub mod export;
+pub mod format;
+pub mod frontmatter;
+mod headings;
+pub mod highlight;
+pub mod links;
+pub mod lint;
+pub mod live;
+pub mod pdf;
+pub mod refactor;
+mod render;
+mod sanitize;
+pub mod site;
+mod slug;
+pub mod table;
+pub mod tasks;
+pub mod toc;
+pub mod wikilinks;
+
+pub use headings::{extract_headings, Heading};
+pub use highlight::{highlight_code, highlight_css};
+pub use config::ProjectConfig;
+pub use render::{render_markdown, RenderOptions};
+pub use sanitize::sanitize_html;
+pub use slug::{slugify, Slugger};
+pub use toc::{update_toc_blocks, TocOptions};
+
+use pulldown_cmark::Options;
+
+pub fn options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_SMART_PUNCTUATION);
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+    options.insert(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS);
+    options.insert(Options::ENABLE_WIKILINKS);
+    options
+}
+
+/// Byte offset where each line of `content` starts; the 1-based line of an
+/// offset is `line_starts.partition_point(|&start| start <= offset)`.
+pub(crate) fn line_starts(content: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(ix, _)| ix + 1))
+        .collect()
+}
+
+/// Escapes text for HTML content and double-quoted attributes.
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Undoes the entities the HTML writer and [`escape_html`] produce.
+pub(crate) fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
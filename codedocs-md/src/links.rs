@@ -0,0 +1,410 @@
+use crate::edit::TextEdit;
+use crate::wikilinks::{resolve_wikilink, split_wikilink};
+use crate::{line_starts, options, RenderOptions};
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use std::ops::Range;
+
+/// Scheme the preview uses for links to other documents; the frontend intercepts
+/// clicks on it and opens the file in the editor instead of navigating.
+pub const DOC_LINK_SCHEME: &str = "codedocs-doc";
+
+fn has_scheme(url: &str) -> bool {
+    match url.find(':') {
+        Some(ix) if ix > 1 => {
+            let scheme = &url[..ix];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        _ => false,
+    }
+}
+
+/// True for document-relative targets such as `./img.png` or `../other.md#intro`.
+pub fn is_relative(url: &str) -> bool {
+    !url.is_empty() && !url.starts_with(['/', '\\', '#']) && !has_scheme(url)
+}
+
+pub fn parent_dir(path: &str) -> &str {
+    path.rfind(['/', '\\']).map(|ix| &path[..ix]).unwrap_or("")
+}
+
+/// Joins `relative` onto `base_dir`, resolving `.` and `..` and keeping the
+/// separator style of the base path. With an empty base, `relative` is only
+/// normalized and stays absolute or relative as it was.
+pub fn resolve_path(base_dir: &str, relative: &str) -> String {
+    let separator = if base_dir.contains('\\') && !base_dir.contains('/') { "\\" } else { "/" };
+    let path = if base_dir.is_empty() { relative.to_string() } else { format!("{}/{}", base_dir, relative) };
+    // "" before a leading separator, or a drive such as `C:`; never popped.
+    let root = path.split(['/', '\\']).next().filter(|first| first.is_empty() || first.ends_with(':'));
+
+    let mut parts: Vec<&str> = root.into_iter().collect();
+    for part in path.split(['/', '\\']).skip(parts.len()) {
+        match part {
+            "" | "." => {}
+            ".." => match parts.last() {
+                Some(&last) if last != ".." && (root.is_none() || parts.len() > 1) => {
+                    parts.pop();
+                }
+                _ if root.is_none() => parts.push(".."),
+                _ => {}
+            },
+            part => parts.push(part),
+        }
+    }
+    parts.join(separator)
+}
+
+/// Path of `to` relative to the folder `from_dir`, with `/` separators.
+pub fn relative_path(from_dir: &str, to: &str) -> String {
+    let from: Vec<&str> = from_dir.split(['/', '\\']).filter(|p| !p.is_empty()).collect();
+    let to: Vec<&str> = to.split(['/', '\\']).filter(|p| !p.is_empty()).collect();
+    let common = from.iter().zip(to.iter()).take_while(|(a, b)| a == b).count();
+
+    let mut parts = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+    parts.join("/")
+}
+
+/// [`relative_path`] written so it parses as the destination of an inline
+/// link: spaces and angle brackets are percent-encoded.
+pub fn link_path(from_dir: &str, to: &str) -> String {
+    relative_path(from_dir, to).replace(' ', "%20").replace('<', "%3C").replace('>', "%3E")
+}
+
+/// Whether `path` is `root` itself or lies somewhere below it, once `.` and
+/// `..` are resolved in both. Symbolic links are not followed.
+pub fn is_within(path: &str, root: &str) -> bool {
+    let path = resolve_path("", path);
+    let root = resolve_path("", root);
+    let root = root.trim_end_matches('/');
+    path == root || path.strip_prefix(root).is_some_and(|rest| rest.starts_with('/'))
+}
+
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut ix = 0;
+    while ix < bytes.len() {
+        if bytes[ix] == b'%' {
+            if let Some(byte) = text.get(ix + 1..ix + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(byte);
+                ix += 3;
+                continue;
+            }
+        }
+        out.push(bytes[ix]);
+        ix += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Same character set as JavaScript's `encodeURIComponent`.
+pub fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'()".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Splits `path?query#fragment` into the decoded path and fragment.
+fn split_target(url: &str) -> (String, Option<String>) {
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(percent_decode(fragment))),
+        None => (url, None),
+    };
+    let path = rest.split_once('?').map(|(path, _)| path).unwrap_or(rest);
+    (percent_decode(path), fragment.filter(|f| !f.is_empty()))
+}
+
+pub(crate) fn is_markdown_path(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".markdown")
+}
+
+/// Rewrites a relative image source to the asset protocol, as long as it stays
+/// inside the project.
+pub fn resolve_image_url(url: &str, options: &RenderOptions) -> Option<String> {
+    let document_path = options.document_path.as_deref()?;
+    let asset_prefix = options.asset_prefix.as_deref()?;
+    if !is_relative(url) {
+        return None;
+    }
+    let (path, _) = split_target(url);
+    let resolved = resolve_path(parent_dir(document_path), &path);
+    if let Some(root) = options.project_root.as_deref() {
+        if !is_within(&resolved, root) {
+            return None;
+        }
+    }
+    Some(format!("{}{}", asset_prefix, percent_encode(&resolved)))
+}
+
+/// Rewrites a relative link to a markdown file into a `codedocs-doc:` link.
+pub fn resolve_link_url(url: &str, options: &RenderOptions) -> Option<String> {
+    let document_path = options.document_path.as_deref()?;
+    if !is_relative(url) {
+        return None;
+    }
+    let (path, fragment) = split_target(url);
+    if !is_markdown_path(&path) {
+        return None;
+    }
+    let resolved = resolve_path(parent_dir(document_path), &path);
+    Some(doc_link_url(&resolved, fragment.as_deref()))
+}
+
+pub fn doc_link_url(path: &str, anchor: Option<&str>) -> String {
+    match anchor {
+        Some(anchor) => format!("{}:{}#{}", DOC_LINK_SCHEME, percent_encode(path), percent_encode(anchor)),
+        None => format!("{}:{}", DOC_LINK_SCHEME, percent_encode(path)),
+    }
+}
+
+/// Inverse of [`doc_link_url`]: the document path and optional heading anchor.
+pub fn parse_doc_link(href: &str) -> Option<(String, Option<String>)> {
+    let target = href.strip_prefix(DOC_LINK_SCHEME)?.strip_prefix(':')?;
+    let (path, anchor) = split_target(target);
+    (!path.is_empty()).then_some((path, anchor))
+}
+
+/// A link or image as written in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkRef {
+    /// Destination exactly as the parser reports it (for wiki-links, the part before `|`).
+    pub target: String,
+    pub image: bool,
+    pub wiki: bool,
+    /// Byte range of the whole link in the source.
+    pub range: Range<usize>,
+    /// Byte range of the destination as written, inside the link or in the
+    /// reference definition it uses. `None` if it cannot be told apart.
+    pub target_range: Option<Range<usize>>,
+    /// 1-based line where the link starts.
+    pub line: usize,
+}
+
+impl LinkRef {
+    /// An edit that swaps just the destination of this link, leaving the text,
+    /// title and wiki alias alone. For reference links the definition changes,
+    /// so every link using it follows. `None` if the destination was not found.
+    pub fn target_edit(&self, content: &str, replacement: String) -> Option<TextEdit> {
+        let range = self.target_range.clone()?;
+        Some(TextEdit {
+            start: range.start,
+            end: range.end,
+            original: content.get(range)?.to_string(),
+            replacement,
+        })
+    }
+}
+
+/// Byte range of the link destination starting at `start` (after any
+/// whitespace): the inside of `<...>`, or a run without spaces and with
+/// balanced parentheses. Backslash escapes are skipped over.
+fn destination_range(content: &str, start: usize) -> Option<Range<usize>> {
+    let rest = content.get(start..)?;
+    let start = start + (rest.len() - rest.trim_start().len());
+    let rest = &content[start..];
+
+    let mut escaped = false;
+    if let Some(inner) = rest.strip_prefix('<') {
+        for (ix, c) in inner.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '>' => return Some(start + 1..start + 1 + ix),
+                '<' | '\n' => return None,
+                _ => {}
+            }
+        }
+        return None;
+    }
+
+    let mut depth = 0usize;
+    let mut end = rest.len();
+    for (ix, c) in rest.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            c if c == ')' || c.is_whitespace() || c.is_control() => {
+                end = ix;
+                break;
+            }
+            _ => {}
+        }
+    }
+    (end > 0).then_some(start..start + end)
+}
+
+/// Byte range of the destination of the reference definition at `span`.
+fn definition_destination(content: &str, span: &Range<usize>) -> Option<Range<usize>> {
+    let source = content.get(span.clone())?;
+    let mut escaped = false;
+    for (ix, c) in source.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ']' if source[ix + 1..].starts_with(':') => {
+                let range = destination_range(content, span.start + ix + 2)?;
+                return (range.end <= span.end).then_some(range);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn extract_links(content: &str) -> Vec<LinkRef> {
+    let line_starts = line_starts(content);
+
+    let mut parser = Parser::new_ext(content, options()).into_offset_iter();
+    let events: Vec<(Event, Range<usize>)> = parser.by_ref().collect();
+    let definitions = parser.reference_definitions();
+
+    let mut links = Vec::new();
+    for (ix, (event, range)) in events.iter().enumerate() {
+        let (link_type, dest_url, id, image) = match event {
+            Event::Start(Tag::Link { link_type, dest_url, id, .. }) => (link_type, dest_url, id, false),
+            Event::Start(Tag::Image { link_type, dest_url, id, .. }) => (link_type, dest_url, id, true),
+            _ => continue,
+        };
+        let target_range = match link_type {
+            LinkType::Autolink | LinkType::Email => continue,
+            LinkType::WikiLink { .. } => content[range.clone()]
+                .find(dest_url.as_ref())
+                .map(|offset| range.start + offset..range.start + offset + dest_url.len()),
+            LinkType::Inline => {
+                // The text ends where its last event does; `](` follows it.
+                let mut depth = 0usize;
+                let mut text_end = range.start + if image { 2 } else { 1 };
+                for (event, child) in &events[ix + 1..] {
+                    match event {
+                        Event::Start(_) => depth += 1,
+                        Event::End(_) if depth == 0 => break,
+                        Event::End(_) => depth -= 1,
+                        _ => {}
+                    }
+                    text_end = text_end.max(child.end);
+                }
+                content[text_end..range.end]
+                    .find("](")
+                    .and_then(|offset| destination_range(content, text_end + offset + 2))
+                    .filter(|destination| destination.end <= range.end)
+            }
+            LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut => definitions
+                .get(id.as_ref())
+                .and_then(|definition| definition_destination(content, &definition.span)),
+            _ => None,
+        };
+        links.push(LinkRef {
+            target: dest_url.to_string(),
+            image,
+            wiki: matches!(link_type, LinkType::WikiLink { .. }),
+            line: line_starts.partition_point(|&start| start <= range.start),
+            range: range.clone(),
+            target_range,
+        });
+    }
+    links
+}
+
+/// The file a link points at (the document itself for `#anchor` links) and the
+/// anchor, if any. `None` for external URLs and wiki-links that resolve to nothing.
+pub fn link_destination(link: &LinkRef, document_path: &str, files: &[String]) -> Option<(String, Option<String>)> {
+    if link.wiki {
+        let (page, heading) = split_wikilink(&link.target);
+        let path = if page.is_empty() {
+            document_path.to_string()
+        } else {
+            resolve_wikilink(page, files)?.to_string()
+        };
+        return Some((path, heading.map(str::to_string)));
+    }
+
+    if let Some(anchor) = link.target.strip_prefix('#') {
+        return Some((document_path.to_string(), Some(percent_decode(anchor)).filter(|a| !a.is_empty())));
+    }
+    if !is_relative(&link.target) {
+        return None;
+    }
+    let (path, anchor) = split_target(&link.target);
+    Some((resolve_path(parent_dir(document_path), &path), anchor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The document with the destination of every link replaced by `new.md`.
+    fn retarget(content: &str) -> String {
+        let mut edits: Vec<TextEdit> = Vec::new();
+        for link in extract_links(content) {
+            let edit = link.target_edit(content, "new.md".to_string()).expect("destination found");
+            if !edits.iter().any(|other| other.start == edit.start) {
+                edits.push(edit);
+            }
+        }
+        crate::edit::apply_edits(content, &edits).unwrap()
+    }
+
+    #[test]
+    fn inline_destinations_are_found_in_every_form() {
+        assert_eq!(retarget("[a](b.md)"), "[a](new.md)");
+        assert_eq!(retarget("[a]( b.md#x \"b.md\" )"), "[a]( new.md \"b.md\" )");
+        assert_eq!(retarget("[b.md](b.md 'b')"), "[b.md](new.md 'b')");
+        assert_eq!(retarget("[a](<b c.md>)"), "[a](<new.md>)");
+        assert_eq!(retarget("[a](b(1).md)"), "[a](new.md)");
+        assert_eq!(retarget("[a](b\\.md)"), "[a](new.md)");
+        assert_eq!(retarget("![b](b.png)"), "![b](new.md)");
+        assert_eq!(retarget("[![i](i.png)](b.md)"), "[![i](new.md)](new.md)");
+    }
+
+    #[test]
+    fn reference_links_edit_their_definition() {
+        let content = "[a][r] [r][] [r] [x][R]\n\n[r]: <b c.md> \"Título\"\n";
+        assert_eq!(retarget(content), "[a][r] [r][] [r] [x][R]\n\n[r]: <new.md> \"Título\"\n");
+        let content = "[a][r]\n\n[r]:\n  b.md\n";
+        assert_eq!(retarget(content), "[a][r]\n\n[r]:\n  new.md\n");
+    }
+
+    #[test]
+    fn wiki_links_keep_alias_and_heading_text() {
+        let content = "[[b]] [[b|otro]] [[b#Sección]]";
+        let links = extract_links(content);
+        let originals: Vec<String> =
+            links.iter().map(|link| link.target_edit(content, String::new()).unwrap().original).collect();
+        assert_eq!(originals, ["b", "b", "b#Sección"]);
+    }
+
+    #[test]
+    fn links_report_their_line() {
+        let lines: Vec<usize> = extract_links("[a](a.md)\n\ntexto [b](b.md)\n").iter().map(|link| link.line).collect();
+        assert_eq!(lines, [1, 3]);
+    }
+
+    #[test]
+    fn paths_resolve_against_their_base() {
+        assert_eq!(resolve_path("/docs/guia", "../img/./a.png"), "/docs/img/a.png");
+        assert_eq!(resolve_path("/docs", "../../a.md"), "/a.md");
+        assert_eq!(resolve_path("C:\\docs", "img\\a.png"), "C:\\docs\\img\\a.png");
+        assert_eq!(resolve_path("", "a"), "a");
+        assert_eq!(resolve_path("", "a/../../b"), "../b");
+        assert_eq!(resolve_path("", "/docs/./a.md"), "/docs/a.md");
+    }
+
+    #[test]
+    fn is_within_compares_resolved_paths() {
+        assert!(is_within("/docs/a/../b.md", "/docs/"));
+        assert!(is_within("docs/b.md", "docs"));
+        assert!(!is_within("/docs/../secreto.md", "/docs"));
+        assert!(!is_within("/docs2/a.md", "/docs"));
+        assert!(!is_within("../docs/a.md", "docs"));
+    }
+}
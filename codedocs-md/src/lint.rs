@@ -0,0 +1,467 @@
+//! Style rules over the parsed document, in the spirit of markdownlint.
+
+use crate::edit::TextEdit;
+use crate::{extract_headings, line_starts, options};
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use regex_lite::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+static URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"https?://[^\s<>()\[\]]+[^\s<>()\[\].,;:!?'\x22]").unwrap());
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Off,
+    #[default]
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListMarkerStyle {
+    /// Whatever the first list of the document uses.
+    #[default]
+    Consistent,
+    Dash,
+    Asterisk,
+    Plus,
+}
+
+/// The `[lint]` table of `.codedocs.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LintConfig {
+    pub heading_increment: Severity,
+    pub duplicate_headings: Severity,
+    pub trailing_spaces: Severity,
+    pub bare_urls: Severity,
+    pub image_alt: Severity,
+    pub list_marker: Severity,
+    pub line_length: Severity,
+    pub code_language: Severity,
+    pub list_marker_style: ListMarkerStyle,
+    pub max_line_length: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            heading_increment: Severity::Warning,
+            duplicate_headings: Severity::Warning,
+            trailing_spaces: Severity::Warning,
+            bare_urls: Severity::Warning,
+            image_alt: Severity::Warning,
+            list_marker: Severity::Warning,
+            line_length: Severity::Off,
+            code_language: Severity::Warning,
+            list_marker_style: ListMarkerStyle::Consistent,
+            max_line_length: 120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LintDiagnostic {
+    pub rule: String,
+    pub message: String,
+    pub severity: Severity,
+    /// 1-based line where the problem starts.
+    pub line: usize,
+    /// Byte range in the source.
+    pub start: usize,
+    pub end: usize,
+    pub fix: Option<TextEdit>,
+}
+
+struct Linter<'a> {
+    content: &'a str,
+    config: &'a LintConfig,
+    line_starts: Vec<usize>,
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+impl Linter<'_> {
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+
+    fn report(&mut self, rule: &str, severity: Severity, range: Range<usize>, message: String, fix: Option<TextEdit>) {
+        if severity == Severity::Off {
+            return;
+        }
+        self.diagnostics.push(LintDiagnostic {
+            rule: rule.to_string(),
+            message,
+            severity,
+            line: self.line_of(range.start),
+            start: range.start,
+            end: range.end,
+            fix,
+        });
+    }
+
+    fn edit(&self, range: Range<usize>, replacement: String) -> TextEdit {
+        TextEdit {
+            start: range.start,
+            end: range.end,
+            original: self.content[range].to_string(),
+            replacement,
+        }
+    }
+}
+
+fn marker_char(style: ListMarkerStyle) -> Option<char> {
+    match style {
+        ListMarkerStyle::Consistent => None,
+        ListMarkerStyle::Dash => Some('-'),
+        ListMarkerStyle::Asterisk => Some('*'),
+        ListMarkerStyle::Plus => Some('+'),
+    }
+}
+
+/// Runs every enabled rule over one document, in source order.
+pub fn lint_document(content: &str, config: &LintConfig) -> Vec<LintDiagnostic> {
+    let mut linter = Linter {
+        content,
+        config,
+        line_starts: line_starts(content),
+        diagnostics: Vec::new(),
+    };
+
+    let mut verbatim: Vec<Range<usize>> = Vec::new();
+    let mut in_verbatim = false;
+    let mut link_depth = 0;
+    let mut image: Option<(Range<usize>, bool)> = None;
+    let mut list_marker: Option<char> = marker_char(config.list_marker_style);
+    let mut unordered_lists: Vec<bool> = Vec::new();
+
+    let mut text_run: Option<Range<usize>> = None;
+
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        if !matches!(event, Event::Text(_)) {
+            if let Some(run) = text_run.take() {
+                lint_bare_urls(&mut linter, run);
+            }
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                in_verbatim = true;
+                verbatim.push(range.clone());
+                if let CodeBlockKind::Fenced(info) = kind {
+                    if info.trim().is_empty() {
+                        let fence_line = content[range.clone()].lines().next().unwrap_or_default();
+                        let fence_end = range.start + fence_line.trim_end().len();
+                        let fix = linter.edit(fence_end..fence_end, "text".to_string());
+                        linter.report(
+                            "code-language",
+                            config.code_language,
+                            range.start..fence_end,
+                            "El bloque de código no indica el lenguaje".to_string(),
+                            Some(fix),
+                        );
+                    }
+                }
+            }
+            Event::Start(Tag::MetadataBlock(_)) => {
+                in_verbatim = true;
+                verbatim.push(range);
+            }
+            Event::End(TagEnd::CodeBlock) | Event::End(TagEnd::MetadataBlock(_)) => in_verbatim = false,
+            Event::Start(Tag::HtmlBlock) => verbatim.push(range),
+            Event::Start(Tag::Link { .. }) => link_depth += 1,
+            Event::End(TagEnd::Link) => link_depth -= 1,
+            Event::Start(Tag::Image { .. }) => {
+                link_depth += 1;
+                image = Some((range, false));
+            }
+            Event::End(TagEnd::Image) => {
+                link_depth -= 1;
+                if let Some((range, false)) = image.take() {
+                    linter.report(
+                        "image-alt",
+                        config.image_alt,
+                        range,
+                        "La imagen no tiene texto alternativo".to_string(),
+                        None,
+                    );
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, has_alt)) = image.as_mut() {
+                    *has_alt |= !text.trim().is_empty();
+                }
+                if link_depth == 0 && !in_verbatim {
+                    // The parser splits text at entities and some punctuation; a URL
+                    // may span several adjacent text events.
+                    match text_run.as_mut() {
+                        Some(run) if run.end == range.start => run.end = range.end,
+                        _ => {
+                            if let Some(run) = text_run.replace(range) {
+                                lint_bare_urls(&mut linter, run);
+                            }
+                        }
+                    }
+                }
+            }
+            Event::Code(_) => {
+                if let Some((_, has_alt)) = image.as_mut() {
+                    *has_alt = true;
+                }
+            }
+            Event::Start(Tag::List(first)) => unordered_lists.push(first.is_none()),
+            Event::End(TagEnd::List(_)) => {
+                unordered_lists.pop();
+            }
+            Event::Start(Tag::Item) if unordered_lists.last() == Some(&true) => {
+                let indent = content[range.clone()].len() - content[range.clone()].trim_start().len();
+                let at = range.start + indent;
+                let Some(marker) = content[at..].chars().next().filter(|c| matches!(c, '-' | '*' | '+')) else {
+                    continue;
+                };
+                match list_marker {
+                    None => list_marker = Some(marker),
+                    Some(expected) if expected != marker => {
+                        let fix = linter.edit(at..at + 1, expected.to_string());
+                        linter.report(
+                            "list-marker",
+                            config.list_marker,
+                            at..at + 1,
+                            format!("Marcador de lista «{}» en lugar de «{}»", marker, expected),
+                            Some(fix),
+                        );
+                    }
+                    Some(_) => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(run) = text_run.take() {
+        lint_bare_urls(&mut linter, run);
+    }
+    lint_headings(&mut linter);
+    lint_lines(&mut linter, &verbatim);
+
+    linter.diagnostics.sort_by_key(|d| (d.start, d.end));
+    linter.diagnostics
+}
+
+fn lint_bare_urls(linter: &mut Linter, run: Range<usize>) {
+    let config = linter.config;
+    let source = &linter.content[run.clone()];
+    for found in URL_RE.find_iter(source) {
+        let url = run.start + found.start()..run.start + found.end();
+        let fix = linter.edit(url.clone(), format!("<{}>", found.as_str()));
+        linter.report("bare-urls", config.bare_urls, url, "URL sin formato de enlace".to_string(), Some(fix));
+    }
+}
+
+fn lint_headings(linter: &mut Linter) {
+    let config = linter.config;
+    let headings = extract_headings(linter.content);
+    let mut previous_level: Option<u8> = None;
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for heading in &headings {
+        if let Some(previous) = previous_level {
+            if heading.level > previous + 1 {
+                let source = &linter.content[heading.range.clone()];
+                let hashes = source.len() - source.trim_start_matches('#').len();
+                let fix = (hashes == heading.level as usize).then(|| {
+                    let start = heading.range.start;
+                    linter.edit(start..start + hashes, "#".repeat(previous as usize + 1))
+                });
+                linter.report(
+                    "heading-increment",
+                    config.heading_increment,
+                    heading.range.clone(),
+                    format!("El encabezado salta de h{} a h{}", previous, heading.level),
+                    fix,
+                );
+            }
+        }
+        previous_level = Some(heading.level);
+
+        let key = heading.text.to_lowercase();
+        if let Some(line) = seen.get(&key) {
+            linter.report(
+                "duplicate-headings",
+                config.duplicate_headings,
+                heading.range.clone(),
+                format!("Encabezado duplicado: «{}» ya aparece en la línea {}", heading.text, line),
+                None,
+            );
+        } else {
+            seen.insert(key, heading.line);
+        }
+    }
+}
+
+/// Rules that look at raw lines. Code blocks, front matter and HTML blocks
+/// are left alone.
+fn lint_lines(linter: &mut Linter, verbatim: &[Range<usize>]) {
+    let config = linter.config;
+    let mut offset = 0;
+    for raw in linter.content.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        if verbatim.iter().any(|block| block.contains(&start)) {
+            continue;
+        }
+        let line = raw.trim_end_matches(['\r', '\n']);
+
+        let trimmed = line.trim_end_matches([' ', '\t']);
+        let trailing = &line[trimmed.len()..];
+        // Exactly two spaces is a hard line break.
+        if !trailing.is_empty() && (trailing != "  " || trimmed.is_empty()) {
+            let range = start + trimmed.len()..start + line.len();
+            let fix = linter.edit(range.clone(), String::new());
+            linter.report(
+                "trailing-spaces",
+                config.trailing_spaces,
+                range,
+                "Espacios al final de la línea".to_string(),
+                Some(fix),
+            );
+        }
+
+        let length = line.chars().count();
+        // Lines that cannot be wrapped (a long URL, a table row) are not flagged.
+        let wrappable = line.trim().contains(' ') && !line.trim_start().starts_with('|');
+        if length > config.max_line_length && wrappable {
+            let cut = line.char_indices().nth(config.max_line_length).map_or(line.len(), |(ix, _)| ix);
+            linter.report(
+                "line-length",
+                config.line_length,
+                start + cut..start + line.len(),
+                format!("Línea de {} caracteres (máximo {})", length, config.max_line_length),
+                None,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit::apply_edits;
+
+    /// Rule and line of every problem found.
+    fn found(content: &str, config: &LintConfig) -> Vec<(String, usize)> {
+        lint_document(content, config).into_iter().map(|d| (d.rule, d.line)).collect()
+    }
+
+    fn fixed(content: &str, config: &LintConfig) -> String {
+        let fixes: Vec<TextEdit> = lint_document(content, config).into_iter().filter_map(|d| d.fix).collect();
+        apply_edits(content, &fixes).unwrap()
+    }
+
+    fn only(rule: &str, line: usize) -> Vec<(String, usize)> {
+        vec![(rule.to_string(), line)]
+    }
+
+    #[test]
+    fn headings_must_not_skip_levels() {
+        let config = LintConfig::default();
+        assert_eq!(found("# A\n\n### B\n", &config), only("heading-increment", 3));
+        assert_eq!(fixed("# A\n\n### B\n", &config), "# A\n\n## B\n");
+        assert!(found("# A\n\n## B\n\n### C\n\n# D\n", &config).is_empty());
+    }
+
+    #[test]
+    fn headings_must_not_repeat() {
+        let config = LintConfig::default();
+        assert_eq!(found("# Uso\n\n## uso\n", &config), only("duplicate-headings", 3));
+        assert!(found("# Uso\n\n## Usos\n", &config).is_empty());
+    }
+
+    #[test]
+    fn trailing_spaces_except_hard_breaks() {
+        let config = LintConfig::default();
+        let content = "uno \t\ndos  \ntres\n  \n\n```text\ncódigo   \n```\n";
+        assert_eq!(found(content, &config), [("trailing-spaces".to_string(), 1), ("trailing-spaces".to_string(), 4)]);
+        assert_eq!(fixed(content, &config), "uno\ndos  \ntres\n\n\n```text\ncódigo   \n```\n");
+    }
+
+    #[test]
+    fn images_need_alt_text() {
+        let config = LintConfig::default();
+        assert_eq!(found("![](a.png) ![x](b.png) ![`c`](c.png)\n", &config), only("image-alt", 1));
+    }
+
+    #[test]
+    fn list_markers_follow_the_configured_style() {
+        let mut config = LintConfig::default();
+        let content = "- a\n- b\n\n* c\n\n1. d\n";
+        assert_eq!(found(content, &config), only("list-marker", 4));
+        assert_eq!(fixed(content, &config), "- a\n- b\n\n- c\n\n1. d\n");
+
+        config.list_marker_style = ListMarkerStyle::Plus;
+        assert_eq!(fixed(content, &config), "+ a\n+ b\n\n+ c\n\n1. d\n");
+        assert!(found("+ a\n", &config).is_empty());
+    }
+
+    #[test]
+    fn long_lines_are_reported_only_when_enabled_and_wrappable() {
+        let content = "una línea larga\nhttps://ejemplo.com/larga\n| a | b | c |\ncorta\n";
+        assert!(found(content, &LintConfig::default()).iter().all(|(rule, _)| rule != "line-length"));
+
+        let config = LintConfig {
+            line_length: Severity::Error,
+            max_line_length: 10,
+            bare_urls: Severity::Off,
+            ..Default::default()
+        };
+        let diagnostics = lint_document(content, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].severity), (1, Severity::Error));
+        assert_eq!(&content[diagnostics[0].start..diagnostics[0].end], "larga");
+    }
+
+    #[test]
+    fn fenced_code_needs_a_language() {
+        let config = LintConfig::default();
+        let content = "```\nx\n```\n\n```rust\ny\n```\n\n    z\n";
+        assert_eq!(found(content, &config), only("code-language", 1));
+        assert_eq!(fixed(content, &config), content.replacen("```\n", "```text\n", 1));
+    }
+
+    #[test]
+    fn rules_set_to_off_report_nothing() {
+        let config = LintConfig {
+            heading_increment: Severity::Off,
+            trailing_spaces: Severity::Off,
+            bare_urls: Severity::Off,
+            code_language: Severity::Off,
+            ..Default::default()
+        };
+        assert!(found("# A\n\n### B \n\nhttps://x.org\n\n```\nx\n```\n", &config).is_empty());
+    }
+
+    fn bare_urls(content: &str) -> Vec<&str> {
+        lint_document(content, &LintConfig::default())
+            .into_iter()
+            .filter(|d| d.rule == "bare-urls")
+            .map(|d| &content[d.start..d.end])
+            .collect()
+    }
+
+    #[test]
+    fn bare_urls_split_across_text_events_are_reported_whole() {
+        assert_eq!(bare_urls("Ver https://a.com/x?a=1&amp;b=2 hoy\n"), ["https://a.com/x?a=1&amp;b=2"]);
+        let urls = bare_urls("Ver https://a.com/mi_guia_v2 y http://b.org.\n");
+        assert_eq!(urls, ["https://a.com/mi_guia_v2", "http://b.org"]);
+    }
+
+    #[test]
+    fn urls_in_links_and_code_are_not_bare() {
+        let content = "[a](https://a.com) <https://b.com> `https://c.com`\n\n```text\nhttps://d.com\n```\n";
+        assert!(bare_urls(content).is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+//! What the editor's live preview draws in place of the markdown source on
+//! lines away from the cursor.
+
+use crate::headings::{heading_text_range, inline_text};
+use crate::links::resolve_image_url;
+use crate::{extract_headings, options, RenderOptions};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex_lite::Regex;
+use serde::Serialize;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+// The same `$` and `$$` delimiters the preview renders.
+static DISPLAY_MATH_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\$([\s\S]*?)\$\$").unwrap());
+static INLINE_MATH_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$([^\$\n]+?)\$").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LiveKind {
+    Heading { level: u8 },
+    Emphasis,
+    Strong,
+    Strikethrough,
+    Code,
+    Link { url: String },
+    /// Replaced by the image itself.
+    Image { src: String, alt: String },
+    /// Replaced by the formula rendered with KaTeX.
+    Math { tex: String, display: bool },
+    /// Replaced by a checkbox that toggles the `[ ]` marker.
+    Task { checked: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveSpan {
+    pub kind: LiveKind,
+    /// Byte range of the whole construct.
+    pub range: Range<usize>,
+    /// Markup hidden while rendered: delimiters, heading markers, link targets.
+    pub hidden: Vec<Range<usize>>,
+}
+
+fn delimiter_width(source: &str, delimiter: char) -> usize {
+    source.chars().take_while(|&c| c == delimiter).count()
+}
+
+fn span(kind: LiveKind, range: Range<usize>, hidden: Vec<Range<usize>>) -> LiveSpan {
+    LiveSpan { kind, range, hidden }
+}
+
+/// The constructs of `content` the live preview renders, in source order.
+/// Images resolve like in the preview.
+pub fn live_spans(content: &str, render_options: &RenderOptions) -> Vec<LiveSpan> {
+    let mut spans = Vec::new();
+    // Code and raw HTML, where `$` is not math.
+    let mut verbatim: Vec<Range<usize>> = Vec::new();
+    // Open links with the extent of their text, and the open image with its alt text.
+    let mut links: Vec<(Range<usize>, String, Option<Range<usize>>)> = Vec::new();
+    let mut image: Option<(Range<usize>, String, Vec<Event>)> = None;
+
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        if let Some((_, _, events)) = image.as_mut() {
+            if !matches!(event, Event::End(TagEnd::Image)) {
+                events.push(event);
+                continue;
+            }
+        }
+        if let Some((_, _, inner)) = links.last_mut() {
+            if !matches!(event, Event::End(TagEnd::Link)) {
+                let inner = inner.get_or_insert(range.clone());
+                inner.end = inner.end.max(range.end);
+            }
+        }
+
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_)) => verbatim.push(range),
+            Event::InlineHtml(_) => verbatim.push(range),
+            Event::Start(Tag::Emphasis | Tag::Strong | Tag::Strikethrough) => {
+                let source = &content[range.clone()];
+                let (kind, width) = match event {
+                    Event::Start(Tag::Emphasis) => (LiveKind::Emphasis, 1),
+                    Event::Start(Tag::Strong) => (LiveKind::Strong, 2),
+                    _ => (LiveKind::Strikethrough, delimiter_width(source, '~')),
+                };
+                if source.len() >= width * 2 {
+                    let hidden = vec![range.start..range.start + width, range.end - width..range.end];
+                    spans.push(span(kind, range, hidden));
+                }
+            }
+            Event::Code(_) => {
+                verbatim.push(range.clone());
+                let source = &content[range.clone()];
+                let width = delimiter_width(source, '`');
+                if source.len() > width * 2 {
+                    let hidden = vec![range.start..range.start + width, range.end - width..range.end];
+                    spans.push(span(LiveKind::Code, range, hidden));
+                }
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                links.push((range, dest_url.to_string(), None));
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((range, url, Some(inner))) = links.pop() {
+                    let hidden = vec![range.start..inner.start, inner.end..range.end];
+                    spans.push(span(LiveKind::Link { url }, range, hidden));
+                }
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                let src = resolve_image_url(&dest_url, render_options).unwrap_or_else(|| dest_url.to_string());
+                image = Some((range, src, Vec::new()));
+            }
+            Event::End(TagEnd::Image) => {
+                if let Some((range, src, events)) = image.take() {
+                    let alt = inline_text(events.iter());
+                    spans.push(span(LiveKind::Image { src, alt }, range, Vec::new()));
+                }
+            }
+            Event::TaskListMarker(checked) => {
+                spans.push(span(LiveKind::Task { checked }, range, Vec::new()));
+            }
+            _ => {}
+        }
+    }
+
+    for heading in extract_headings(content) {
+        let source = &content[heading.range.clone()];
+        let line_end = heading.range.start + source.find('\n').unwrap_or(source.len());
+        let text = heading_text_range(content, &heading);
+        let mut hidden = Vec::new();
+        if source.trim_start().starts_with('#') {
+            hidden.push(heading.range.start..text.start);
+            if text.end < line_end {
+                hidden.push(text.end..line_end);
+            }
+        }
+        spans.push(span(LiveKind::Heading { level: heading.level }, heading.range.clone(), hidden));
+    }
+
+    let mut math: Vec<Range<usize>> = Vec::new();
+    for (re, display) in [(&*DISPLAY_MATH_RE, true), (&*INLINE_MATH_RE, false)] {
+        for found in re.captures_iter(content) {
+            let (Some(whole), Some(tex)) = (found.get(0), found.get(1)) else { continue };
+            let range = whole.range();
+            let overlaps = |other: &Range<usize>| other.start < range.end && range.start < other.end;
+            if tex.as_str().trim().is_empty() || verbatim.iter().any(overlaps) || math.iter().any(overlaps) {
+                continue;
+            }
+            math.push(range.clone());
+            spans.push(span(LiveKind::Math { tex: tex.as_str().trim().to_string(), display }, range, Vec::new()));
+        }
+    }
+    // Underscores and asterisks inside a formula are not emphasis.
+    spans.retain(|s| {
+        matches!(s.kind, LiveKind::Math { .. } | LiveKind::Heading { .. })
+            || !math.iter().any(|m| m.start < s.range.end && s.range.start < m.end)
+    });
+
+    spans.sort_by_key(|s| (s.range.start, std::cmp::Reverse(s.range.end)));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each span's kind, source and hidden markup.
+    fn spans(content: &str) -> Vec<(LiveKind, &str, Vec<&str>)> {
+        live_spans(content, &RenderOptions::default())
+            .into_iter()
+            .map(|s| (s.kind, &content[s.range], s.hidden.into_iter().map(|h| &content[h]).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn inline_markup_hides_its_delimiters() {
+        assert_eq!(
+            spans("*a* **b** ~~c~~ ``d`e``\n"),
+            [
+                (LiveKind::Emphasis, "*a*", vec!["*", "*"]),
+                (LiveKind::Strong, "**b**", vec!["**", "**"]),
+                (LiveKind::Strikethrough, "~~c~~", vec!["~~", "~~"]),
+                (LiveKind::Code, "``d`e``", vec!["``", "``"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn headings_links_images_and_tasks() {
+        let content = "## Título ##\n\n- [x] ver [la guía](guia.md \"t\") ![un *logo*](https://x.org/l.png)\n";
+        let url = LiveKind::Link { url: "guia.md".to_string() };
+        let image = LiveKind::Image { src: "https://x.org/l.png".to_string(), alt: "un logo".to_string() };
+        assert_eq!(
+            spans(content),
+            [
+                (LiveKind::Heading { level: 2 }, "## Título ##\n", vec!["## ", " ##"]),
+                (LiveKind::Task { checked: true }, "[x]", vec![]),
+                (url, "[la guía](guia.md \"t\")", vec!["[", "](guia.md \"t\")"]),
+                (image, "![un *logo*](https://x.org/l.png)", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn math_outside_code_replaces_the_emphasis_inside_it() {
+        let content = "$a_1 + b_1$ y `$no$`\n\n$$\nx^2\n$$\n";
+        let inline = LiveKind::Math { tex: "a_1 + b_1".to_string(), display: false };
+        let display = LiveKind::Math { tex: "x^2".to_string(), display: true };
+        assert_eq!(
+            spans(content),
+            [
+                (inline, "$a_1 + b_1$", vec![]),
+                (LiveKind::Code, "`$no$`", vec!["`", "`"]),
+                (display, "$$\nx^2\n$$", vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Settings of the PDF export and the front matter its cover page shows.
+
+use crate::export::document_title;
+use crate::frontmatter::{parse_front_matter, MetaValue};
+use serde::{Deserialize, Serialize};
+
+/// Points per millimetre.
+const MM: f32 = 72.0 / 25.4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    #[default]
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    /// Width and height in points.
+    pub fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (210.0 * MM, 297.0 * MM),
+            PageSize::A5 => (148.0 * MM, 210.0 * MM),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Legal => (612.0, 1008.0),
+        }
+    }
+}
+
+/// Page margins in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Margins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Self { top: 25.0, right: 20.0, bottom: 25.0, left: 20.0 }
+    }
+}
+
+impl Margins {
+    /// Top, right, bottom and left in points.
+    pub fn points(&self) -> (f32, f32, f32, f32) {
+        (self.top * MM, self.right * MM, self.bottom * MM, self.left * MM)
+    }
+}
+
+/// The `[pdf]` table of `.codedocs.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfConfig {
+    pub page_size: PageSize,
+    pub margins: Margins,
+    /// Size of the body text in points.
+    pub font_size: f32,
+    /// Text at the top of every page but the cover; see [`page_text`].
+    pub header: String,
+    /// Text at the bottom of every page but the cover; see [`page_text`].
+    pub footer: String,
+    /// Start with a cover page when the document has front matter.
+    pub cover: bool,
+    /// Start every top-level heading on a new page.
+    pub break_before_h1: bool,
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::A4,
+            margins: Margins::default(),
+            font_size: 11.0,
+            header: "{title}".to_string(),
+            footer: "{page} / {pages}".to_string(),
+            cover: true,
+            break_before_h1: true,
+        }
+    }
+}
+
+/// A header or footer with its placeholders filled in: `{title}`, `{page}`
+/// and `{pages}`. Pages are counted without the cover.
+pub fn page_text(template: &str, title: &str, page: usize, pages: usize) -> String {
+    template
+        .replace("{title}", title)
+        .replace("{page}", &page.to_string())
+        .replace("{pages}", &pages.to_string())
+}
+
+/// What the cover page shows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cover {
+    pub title: String,
+    pub subtitle: String,
+    pub author: String,
+    pub date: String,
+}
+
+/// The cover of a document with front matter: its title, `subtitle` (or
+/// `description`), `author` (or `authors`) and `date`.
+pub fn cover(content: &str, path: &str) -> Option<Cover> {
+    let front_matter = parse_front_matter(content)?;
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| front_matter.get(key))
+            .map(MetaValue::display)
+            .unwrap_or_default()
+    };
+    Some(Cover {
+        title: document_title(content, path),
+        subtitle: field(&["subtitle", "description"]),
+        author: field(&["author", "authors"]),
+        date: front_matter.date(),
+    })
+}
@@ -0,0 +1,295 @@
+//! Project-wide edits that keep links working when documents move or headings change.
+
+use crate::edit::{FileEdits, TextEdit};
+use crate::headings::heading_text_range;
+use crate::links::{extract_links, is_relative, link_destination, link_path, parent_dir, LinkRef};
+use crate::toc::{toc_blocks, toc_markdown, TocOptions};
+use crate::wikilinks::{resolve_wikilink, shortest_wikilink, split_wikilink};
+use crate::{extract_headings, slugify};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A link a refactoring should update but whose destination could not be
+/// located in the source, so it is left as it is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedLink {
+    pub path: String,
+    pub line: usize,
+    pub target: String,
+}
+
+/// The edits of a refactoring and the links it has to leave alone.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RefactorEdits {
+    pub edits: Vec<FileEdits>,
+    pub skipped: Vec<SkippedLink>,
+}
+
+/// Adds the edit that points `link` at `replacement`, or records the link as
+/// skipped. Links sharing a reference definition share its edit.
+fn push_target_edit(
+    edits: &mut Vec<TextEdit>,
+    skipped: &mut Vec<SkippedLink>,
+    path: &str,
+    content: &str,
+    link: &LinkRef,
+    replacement: String,
+) {
+    match link.target_edit(content, replacement) {
+        Some(edit) if edits.iter().any(|other| other.start == edit.start) => {}
+        Some(edit) => edits.push(edit),
+        None => skipped.push(SkippedLink {
+            path: path.to_string(),
+            line: link.line,
+            target: link.target.clone(),
+        }),
+    }
+}
+
+/// Edits that keep every link working once `old_path` becomes `new_path`:
+/// relative links and wiki-links pointing at the document, the moved
+/// document's own relative links, and wiki-links whose resolution the move
+/// would change. Edits are expressed against each file's current content.
+pub fn rename_edits(old_path: &str, new_path: &str, documents: &[(String, String)]) -> RefactorEdits {
+    let old_files: Vec<String> = documents.iter().map(|(path, _)| path.clone()).collect();
+    let new_files: Vec<String> = old_files
+        .iter()
+        .map(|path| if path == old_path { new_path.to_string() } else { path.clone() })
+        .collect();
+    let after_move = |path: &str| if path == old_path { new_path.to_string() } else { path.to_string() };
+
+    let mut result = RefactorEdits::default();
+    for (path, content) in documents {
+        let is_moved = path == old_path;
+        let new_dir = parent_dir(if is_moved { new_path } else { path }).to_string();
+        let mut edits = Vec::new();
+
+        for link in extract_links(content) {
+            let replacement = if link.wiki {
+                let (page, heading) = split_wikilink(&link.target);
+                if page.is_empty() {
+                    continue;
+                }
+                let Some(before) = resolve_wikilink(page, &old_files).map(after_move) else { continue };
+                if resolve_wikilink(page, &new_files) == Some(before.as_str()) {
+                    continue;
+                }
+                let page = shortest_wikilink(&before, &new_files);
+                match heading {
+                    Some(heading) => format!("{}#{}", page, heading),
+                    None => page,
+                }
+            } else {
+                if !is_relative(&link.target) {
+                    continue;
+                }
+                let Some((target, _)) = link_destination(&link, path, &old_files) else { continue };
+                if !is_moved && target != old_path {
+                    continue;
+                }
+                let mut relative = link_path(&new_dir, &after_move(&target));
+                if link.target.starts_with("./") && !relative.starts_with("../") {
+                    relative = format!("./{}", relative);
+                }
+                match link.target.split_once('#') {
+                    Some((_, fragment)) => format!("{}#{}", relative, fragment),
+                    None => relative,
+                }
+            };
+
+            if replacement != link.target {
+                push_target_edit(&mut edits, &mut result.skipped, path, content, &link, replacement);
+            }
+        }
+
+        if !edits.is_empty() {
+            result.edits.push(FileEdits { path: path.clone(), edits });
+        }
+    }
+    result
+}
+
+/// Edits that rename the heading starting at `line` of `path` to `new_text`
+/// and keep every `#anchor` and `[[page#Heading]]` pointing at it (or at a
+/// heading whose deduplicated slug shifts because of it) working, plus the
+/// regenerated TOC blocks of that document, whose markers fill in what they
+/// leave out from `toc_defaults`. `None` if there is no heading on
+/// that line or the new text would turn it into something else.
+pub fn rename_heading_edits(
+    path: &str,
+    line: usize,
+    new_text: &str,
+    documents: &[(String, String)],
+    toc_defaults: &TocOptions,
+) -> Option<RefactorEdits> {
+    let files: Vec<String> = documents.iter().map(|(path, _)| path.clone()).collect();
+    let (_, content) = documents.iter().find(|(doc, _)| doc == path)?;
+
+    let before = extract_headings(content);
+    let index = before.iter().position(|heading| heading.line == line)?;
+    let text_range = heading_text_range(content, &before[index]);
+    let heading_edit = TextEdit {
+        start: text_range.start,
+        end: text_range.end,
+        original: content[text_range].to_string(),
+        replacement: new_text.trim().to_string(),
+    };
+    let after = extract_headings(&heading_edit.apply(content)?);
+    if after.len() != before.len() || after[index].level != before[index].level {
+        return None;
+    }
+
+    let old_slug = before[index].slug.as_str();
+    let renamed = &after[index];
+    let slugs: HashMap<&str, &str> = before
+        .iter()
+        .zip(&after)
+        .filter(|(old, new)| old.slug != new.slug)
+        .map(|(old, new)| (old.slug.as_str(), new.slug.as_str()))
+        .collect();
+
+    let mut result = RefactorEdits::default();
+    for (doc_path, doc) in documents {
+        let own = doc_path == path;
+        let toc = if own { toc_blocks(doc, toc_defaults) } else { Vec::new() };
+        let mut edits = Vec::new();
+        if own && heading_edit.original != heading_edit.replacement {
+            edits.push(heading_edit.clone());
+        }
+
+        for link in extract_links(doc) {
+            // Links inside a TOC block are rewritten with the block itself.
+            if toc.iter().any(|block| block.body.contains(&link.range.start)) {
+                continue;
+            }
+            let Some((target, Some(anchor))) = link_destination(&link, doc_path, &files) else { continue };
+            if target != path {
+                continue;
+            }
+            let replacement = if link.wiki {
+                if slugify(&anchor) != old_slug {
+                    continue;
+                }
+                let (page, _) = split_wikilink(&link.target);
+                format!("{}#{}", page, renamed.text)
+            } else {
+                let Some(new_slug) = slugs.get(anchor.as_str()) else { continue };
+                let base = link.target.split_once('#').map(|(base, _)| base).unwrap_or_default();
+                format!("{}#{}", base, new_slug)
+            };
+            if replacement != link.target {
+                push_target_edit(&mut edits, &mut result.skipped, doc_path, doc, &link, replacement);
+            }
+        }
+
+        for block in toc.iter().filter(|block| block.closed) {
+            let list = toc_markdown(&after, &block.options);
+            if doc[block.body.clone()] != list {
+                edits.push(TextEdit {
+                    start: block.body.start,
+                    end: block.body.end,
+                    original: doc[block.body.clone()].to_string(),
+                    replacement: list,
+                });
+            }
+        }
+
+        if !edits.is_empty() {
+            result.edits.push(FileEdits { path: doc_path.clone(), edits });
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit::apply_edits;
+
+    fn documents(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        files.iter().map(|(path, content)| (path.to_string(), content.to_string())).collect()
+    }
+
+    /// The content of every file the refactoring changes, once its edits are applied.
+    fn applied(result: &RefactorEdits, documents: &[(String, String)]) -> Vec<(String, String)> {
+        result
+            .edits
+            .iter()
+            .map(|file| {
+                let (_, content) = documents.iter().find(|(path, _)| *path == file.path).unwrap();
+                (file.path.clone(), apply_edits(content, &file.edits).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn moving_a_document_updates_links_to_it_and_its_own_links() {
+        let documents = documents(&[
+            ("/p/index.md", "[a](notas/a%20b.md#uso) [[a b]] [o](notas/otro.md) [x](#x)\n"),
+            ("/p/notas/a b.md", "[i](../index.md) ![img](img/x.png) [u](#uso) [w](https://x.org)\n"),
+            ("/p/notas/otro.md", "[a](./a%20b.md) [[a b#Uso]]\n"),
+        ]);
+        let result = rename_edits("/p/notas/a b.md", "/p/archivo/2024/a b.md", &documents);
+
+        assert!(result.skipped.is_empty());
+        assert_eq!(
+            applied(&result, &documents),
+            [
+                (
+                    "/p/index.md".to_string(),
+                    "[a](archivo/2024/a%20b.md#uso) [[a b]] [o](notas/otro.md) [x](#x)\n".to_string()
+                ),
+                (
+                    "/p/notas/a b.md".to_string(),
+                    "[i](../../index.md) ![img](../../notas/img/x.png) [u](#uso) [w](https://x.org)\n".to_string()
+                ),
+                ("/p/notas/otro.md".to_string(), "[a](../archivo/2024/a%20b.md) [[a b#Uso]]\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn wiki_links_change_only_when_their_resolution_would() {
+        let documents = documents(&[
+            ("/p/index.md", "[[b/todo#Uso]] [[todo]] [t](./b/todo.md)\n"),
+            ("/p/a/todo.md", ""),
+            ("/p/b/todo.md", ""),
+        ]);
+        let result = rename_edits("/p/b/todo.md", "/p/c/todo.md", &documents);
+        assert_eq!(
+            applied(&result, &documents),
+            [("/p/index.md".to_string(), "[[c/todo#Uso]] [[todo]] [t](./c/todo.md)\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn renaming_a_heading_updates_anchors_shifted_slugs_and_the_toc() {
+        let own = "<!-- toc -->\n- [Uso](#uso)\n  - [Uso](#uso-1)\n<!-- /toc -->\n\n# Uso\n\n## Uso\n\n\
+                   Ver [arriba](#uso) y [abajo](#uso-1).\n";
+        let documents = documents(&[("/p/a.md", own), ("/p/b.md", "[x](a.md#uso) [[a#Uso]] [[a#uso]] [y](a.md)\n")]);
+        let result = rename_heading_edits("/p/a.md", 6, "Empleo", &documents, &TocOptions::default()).unwrap();
+
+        assert!(result.skipped.is_empty());
+        assert_eq!(
+            applied(&result, &documents),
+            [
+                (
+                    "/p/a.md".to_string(),
+                    "<!-- toc -->\n- [Empleo](#empleo)\n  - [Uso](#uso)\n<!-- /toc -->\n\n# Empleo\n\n## Uso\n\n\
+                     Ver [arriba](#empleo) y [abajo](#uso).\n"
+                        .to_string()
+                ),
+                ("/p/b.md".to_string(), "[x](a.md#empleo) [[a#Empleo]] [[a#Empleo]] [y](a.md)\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn heading_renames_that_would_change_the_structure_are_refused() {
+        let documents = documents(&[("/p/a.md", "# Uno\n\nTexto\n")]);
+        let rename = |line, text| rename_heading_edits("/p/a.md", line, text, &documents, &TocOptions::default());
+        assert!(rename(3, "Dos").is_none());
+        assert!(rename(1, "Dos\n\n# Tres").is_none());
+        assert_eq!(rename(1, "Uno").unwrap(), RefactorEdits::default());
+    }
+}
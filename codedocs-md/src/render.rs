@@ -0,0 +1,332 @@
+use crate::headings::{assign_heading_ids, collect_headings, inline_text};
+use crate::highlight::highlight_code;
+use crate::links::{doc_link_url, percent_encode, resolve_image_url, resolve_link_url};
+use crate::slug::slugify;
+use crate::wikilinks::{new_page_path, resolve_wikilink, split_wikilink};
+use crate::toc::{toc_blocks, toc_html, TocOptions};
+use crate::{line_starts, options};
+use crate::sanitize::sanitize_html;
+use pulldown_cmark::{html, CodeBlockKind, Event, LinkType, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+fn preprocess_math(content: &str) -> String {
+    let mut result = content.to_string();
+
+    result = result.replace("[!NOTE]", "**NOTE**");
+    result = result.replace("[!TIP]", "**TIP**");
+    result = result.replace("[!IMPORTANT]", "**IMPORTANT**");
+    result = result.replace("[!WARNING]", "**WARNING**");
+    result = result.replace("[!CAUTION]", "**CAUTION**");
+
+    let display_re = regex_lite::Regex::new(r"\$\$([\s\S]*?)\$\$").unwrap();
+    result = display_re
+        .replace_all(&result, r#"<div class="math-display">$1</div>"#)
+        .to_string();
+
+    let inline_re = regex_lite::Regex::new(r"\$([^\$\n]+?)\$").unwrap();
+    result = inline_re
+        .replace_all(&result, r#"<span class="math-inline">$1</span>"#)
+        .to_string();
+
+    result
+}
+
+fn preprocess_mermaid(content: &str) -> String {
+    let re = regex_lite::Regex::new(r"```mermaid[ \t]*\n([\s\S]*?)\n```").unwrap();
+    re.replace_all(content, |caps: &regex_lite::Captures| {
+        let code = &caps[1];
+        let escaped = code.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+        // The line breaks of the attribute are encoded and the tags get lines of
+        // their own, so the block spans as many lines as the fenced source.
+        format!(
+            "<pre class=\"mermaid-block\" data-mermaid=\"{}\">\n{}\n</pre>",
+            escaped.replace('\n', "&#10;"),
+            escaped
+        )
+    })
+    .to_string()
+}
+
+/// Swaps paragraphs consisting only of `[TOC]` for a live list of the document
+/// headings, with the options of the document's first `<!-- toc -->` block,
+/// or else the project's.
+fn replace_toc_placeholders<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    content: &str,
+    defaults: &TocOptions,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut toc = None;
+    let mut out = Vec::with_capacity(events.len());
+    let mut paragraph_start = None;
+
+    for (ix, (event, range)) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::Paragraph) => paragraph_start = Some(ix),
+            Event::End(TagEnd::Paragraph) => {
+                if let Some(start) = paragraph_start.take() {
+                    let text = inline_text(events[start + 1..ix].iter().map(|(e, _)| e));
+                    if text.trim() == "[TOC]" {
+                        let html = toc.get_or_insert_with(|| {
+                            let options = match toc_blocks(content, defaults).into_iter().next() {
+                                Some(block) => block.options,
+                                None => defaults.clone(),
+                            };
+                            toc_html(&collect_headings(content, &events), &options)
+                        });
+                        out.truncate(out.len() - (ix - start));
+                        out.push((Event::Html(html.clone().into()), events[start].1.start..range.end));
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        out.push((event.clone(), range.clone()));
+    }
+
+    out
+}
+
+/// Replaces each code block with its syntax-highlighted HTML.
+fn highlight_code_blocks<'a>(events: Vec<(Event<'a>, Range<usize>)>) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut block: Option<(String, String, usize)> = None;
+
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                block = Some((info, String::new(), range.start));
+            }
+            Event::Text(text) if block.is_some() => {
+                if let Some((_, code, _)) = block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((info, code, start)) = block.take() {
+                    out.push((Event::Html(highlight_code(&code, &info).into()), start..range.end));
+                }
+            }
+            _ => out.push((event, range)),
+        }
+    }
+
+    out
+}
+
+/// Task checkboxes carry the line of their marker, so clicking one in the
+/// preview can toggle it in the source.
+fn task_checkboxes<'a>(events: Vec<(Event<'a>, Range<usize>)>, content: &str) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut line = 1;
+    let mut counted = 0;
+    events
+        .into_iter()
+        .map(|(event, range)| match event {
+            Event::TaskListMarker(checked) => {
+                line += content[counted..range.start].matches('\n').count();
+                counted = range.start;
+                let checked = if checked { " checked=\"\"" } else { "" };
+                let html = format!("<input type=\"checkbox\" data-task-line=\"{}\"{}>", line, checked);
+                (Event::InlineHtml(html.into()), range)
+            }
+            event => (event, range),
+        })
+        .collect()
+}
+
+fn wikilink_open_tag(target: &str, render_options: &RenderOptions) -> String {
+    let (page, heading) = split_wikilink(target);
+    let anchor = heading.map(slugify);
+
+    if page.is_empty() {
+        return format!("<a class=\"wikilink\" href=\"#{}\">", percent_encode(&anchor.unwrap_or_default()));
+    }
+    if let Some(path) = resolve_wikilink(page, &render_options.project_files) {
+        return format!("<a class=\"wikilink\" href=\"{}\">", doc_link_url(path, anchor.as_deref()));
+    }
+    match render_options.project_root.as_deref() {
+        Some(root) => format!(
+            "<a class=\"wikilink unresolved\" href=\"{}\">",
+            doc_link_url(&new_page_path(page, root), None)
+        ),
+        None => "<a class=\"wikilink unresolved\">".to_string(),
+    }
+}
+
+/// Points relative images at the asset protocol, relative `.md` links at the
+/// documents they name and `[[wiki-links]]` at the project file they resolve to.
+fn resolve_links<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    render_options: &RenderOptions,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut in_wikilink = false;
+
+    events
+        .into_iter()
+        .map(|(event, range)| {
+            let event = match event {
+                Event::Start(Tag::Link { link_type: LinkType::WikiLink { .. }, dest_url, .. }) => {
+                    in_wikilink = true;
+                    Event::InlineHtml(wikilink_open_tag(&dest_url, render_options).into())
+                }
+                Event::End(TagEnd::Link) if in_wikilink => {
+                    in_wikilink = false;
+                    Event::InlineHtml("</a>".into())
+                }
+                Event::Start(Tag::Image { link_type, dest_url, title, id }) => Event::Start(Tag::Image {
+                    link_type,
+                    dest_url: resolve_image_url(&dest_url, render_options).map(Into::into).unwrap_or(dest_url),
+                    title,
+                    id,
+                }),
+                Event::Start(Tag::Link { link_type, dest_url, title, id }) => Event::Start(Tag::Link {
+                    link_type,
+                    dest_url: resolve_link_url(&dest_url, render_options).map(Into::into).unwrap_or(dest_url),
+                    title,
+                    id,
+                }),
+                event => event,
+            };
+            (event, range)
+        })
+        .collect()
+}
+
+/// Adds `data-source-line` to the opening tag of a rendered block, unless it
+/// does not start with an element (a comment, text in an HTML block).
+fn tag_source_line(html: &str, line: usize) -> String {
+    let is_element = html.starts_with('<') && html[1..].starts_with(|c: char| c.is_ascii_alphabetic());
+    let Some(name_end) = html.find([' ', '>', '/']).filter(|_| is_element) else {
+        return html.to_string();
+    };
+    format!("{} data-source-line=\"{}\"{}", &html[..name_end], line, &html[name_end..])
+}
+
+/// Opens the comment that carries a block's source line through the HTML writer.
+const SOURCE_LINE_MARK: &str = "<!--codedocs-source-line:";
+
+/// Renders the events, marking each top-level block with the 1-based source
+/// line it starts on so the editor and the preview can scroll together.
+/// The preprocessing keeps line numbers, so `content` is the preprocessed text.
+///
+/// A single writer renders the whole document, so footnotes keep one
+/// numbering; each block is preceded by a comment with its line, which is
+/// then moved into the block's opening tag.
+fn push_html_with_source_lines(content: &str, events: Vec<(Event, Range<usize>)>) -> String {
+    let line_starts = line_starts(content);
+    let mut marked = Vec::with_capacity(events.len());
+    let mut depth = 0usize;
+
+    for (event, range) in events {
+        if depth == 0 {
+            let line = line_starts.partition_point(|&start| start <= range.start);
+            // The trailing newline keeps the writer from adding one before the block.
+            marked.push(Event::Html(format!("{}{}-->\n", SOURCE_LINE_MARK, line).into()));
+        }
+        match &event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        marked.push(event);
+    }
+
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, marked.into_iter());
+
+    let mut parts = rendered.split(SOURCE_LINE_MARK);
+    let mut html_output = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        match part.split_once("-->\n") {
+            Some((line, block)) => html_output.push_str(&tag_source_line(block, line.parse().unwrap_or(1))),
+            None => html_output.push_str(part),
+        }
+    }
+    html_output
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    /// Skip the sanitizer and pass raw HTML through untouched.
+    pub trusted_html: bool,
+    /// Path of the document being rendered; relative URLs resolve against its folder.
+    pub document_path: Option<String>,
+    /// Images outside this folder are left as they are.
+    pub project_root: Option<String>,
+    /// What the webview's `convertFileSrc("", "project")` returns, e.g. `project://localhost/`.
+    pub asset_prefix: Option<String>,
+    /// Markdown files of the project, used to resolve `[[wiki-links]]`.
+    pub project_files: Vec<String>,
+    /// Options of `[TOC]` when the document has no `<!-- toc -->` block.
+    pub toc: TocOptions,
+}
+
+pub fn render_markdown(content: &str, render_options: &RenderOptions) -> String {
+    let preprocessed = preprocess_mermaid(&preprocess_math(content));
+
+    let parser = Parser::new_ext(&preprocessed, options()).into_offset_iter();
+    let events = replace_toc_placeholders(assign_heading_ids(parser), &preprocessed, &render_options.toc);
+    let events = highlight_code_blocks(events);
+    let events = resolve_links(events, render_options);
+    let events = task_checkboxes(events, &preprocessed);
+    let html_output = push_html_with_source_lines(&preprocessed, events);
+
+    if render_options.trusted_html {
+        html_output
+    } else {
+        sanitize_html(&html_output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footnotes_are_numbered_across_blocks() {
+        let html = render_markdown("Uno[^a]\n\nDos[^b]\n\n[^a]: A\n[^b]: B\n", &RenderOptions::default());
+        assert!(html.contains(">1</a></sup>"), "{}", html);
+        assert!(html.contains(">2</a></sup>"), "{}", html);
+        assert!(html.contains("<sup class=\"footnote-definition-label\">2</sup>"), "{}", html);
+    }
+
+    #[test]
+    fn blocks_carry_their_source_line() {
+        let html = render_markdown("# Uno\n\ntexto\n\n```mermaid\n\ngraph TD\n```\n\n## Dos\n", &RenderOptions::default());
+        assert!(html.contains("<h1 data-source-line=\"1\""), "{}", html);
+        assert!(html.contains("<p data-source-line=\"3\">"), "{}", html);
+        assert!(html.contains("<h2 data-source-line=\"10\""), "{}", html);
+        assert!(!html.contains(SOURCE_LINE_MARK));
+    }
+
+    #[test]
+    fn relative_images_keep_their_project_url() {
+        for prefix in ["project://localhost/", "http://project.localhost/"] {
+            let options = RenderOptions {
+                document_path: Some("/docs/guia.md".to_string()),
+                project_root: Some("/docs".to_string()),
+                asset_prefix: Some(prefix.to_string()),
+                ..RenderOptions::default()
+            };
+            let html = render_markdown("![x](img/a.png)\n", &options);
+            let src = format!("src=\"{}%2Fdocs%2Fimg%2Fa.png\"", prefix);
+            assert!(html.contains(&src), "{}", html);
+        }
+    }
+
+    #[test]
+    fn toc_placeholder_uses_the_toc_block_options() {
+        let content = "# Uno\n\n[TOC]\n\n## Dos\n\n<!-- toc depth=2-6 -->\n<!-- /toc -->\n";
+        let html = render_markdown(content, &RenderOptions::default());
+        assert!(html.contains("href=\"#dos\""), "{}", html);
+        assert!(!html.contains("href=\"#uno\""), "{}", html);
+
+        let options = RenderOptions { toc: TocOptions { ordered: true, ..TocOptions::default() }, ..RenderOptions::default() };
+        let html = render_markdown("# Uno\n\n[TOC]\n", &options);
+        assert!(html.contains("<ol>"), "{}", html);
+    }
+}
@@ -0,0 +1,27 @@
+use std::collections::HashSet;
+
+/// Scheme the app serves the open project's images through, on platforms
+/// where `convertFileSrc` does not map it to `http://project.localhost/`.
+const PROJECT_SCHEME: &str = "project";
+
+/// Allow-list sanitizer for the rendered preview. Keeps the markup the pipeline
+/// itself produces (heading ids, source lines, task checkboxes, table alignment,
+/// math and Mermaid placeholders, callouts) and drops scripts, event handlers
+/// and dangerous URL schemes coming from raw HTML in the document.
+pub fn sanitize_html(html: &str) -> String {
+    ammonia::Builder::default()
+        .add_tags(["input"])
+        .add_generic_attributes(["id", "class", "data-source-line"])
+        .add_url_schemes([PROJECT_SCHEME, crate::links::DOC_LINK_SCHEME])
+        .add_tag_attributes("input", ["type", "checked", "disabled", "data-task-line"])
+        .add_tag_attributes("pre", ["data-mermaid"])
+        .add_tag_attributes("th", ["style"])
+        .add_tag_attributes("td", ["style"])
+        .filter_style_properties(HashSet::from(["text-align"]))
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("input", "type") if value != "checkbox" => None,
+            _ => Some(value.into()),
+        })
+        .clean(html)
+        .to_string()
+}
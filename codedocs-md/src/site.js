@@ -0,0 +1,108 @@
+(function () {
+  var root = document.currentScript.getAttribute("data-root") || "";
+
+  if (window.matchMedia && window.matchMedia("(prefers-color-scheme: dark)").matches) {
+    document.documentElement.classList.add("dark");
+  }
+
+  function renderMath() {
+    if (typeof katex === "undefined") return;
+    document.querySelectorAll(".math-inline, .math-display").forEach(function (el) {
+      var tex = el.textContent;
+      try {
+        katex.render(tex, el, { throwOnError: false, displayMode: el.classList.contains("math-display") });
+      } catch (e) {
+        el.textContent = tex;
+      }
+    });
+  }
+
+  function renderDiagrams() {
+    if (typeof mermaid === "undefined") return;
+    var dark = document.documentElement.classList.contains("dark");
+    mermaid.initialize({ startOnLoad: false, theme: dark ? "dark" : "default" });
+    document.querySelectorAll(".mermaid-block").forEach(function (el, i) {
+      var code = el.getAttribute("data-mermaid");
+      if (!code) return;
+      mermaid.render("mermaid-" + i, code).then(function (svg) {
+        el.innerHTML = svg.svg;
+        el.removeAttribute("data-mermaid");
+      }).catch(function () {});
+    });
+  }
+
+  function escape(text) {
+    return text.replace(/[&<>"]/g, function (c) {
+      return { "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;" }[c];
+    });
+  }
+
+  function normalize(text) {
+    return text.toLowerCase().normalize("NFD").replace(/[\u0300-\u036f]/g, "");
+  }
+
+  // Every word has to appear; matches in titles and headings rank first.
+  function search(query) {
+    var words = normalize(query).split(/\s+/).filter(Boolean);
+    if (!words.length) return [];
+    return (window.__SITE_SEARCH_INDEX || []).map(function (page) {
+      var title = normalize(page.title);
+      var headings = normalize(page.headings.join(" "));
+      var text = normalize(page.text);
+      var score = 0;
+      for (var i = 0; i < words.length; i++) {
+        var word = words[i];
+        if (title.indexOf(word) >= 0) score += 10;
+        else if (headings.indexOf(word) >= 0) score += 5;
+        else if (text.indexOf(word) >= 0) score += 1;
+        else return null;
+      }
+      return { page: page, score: score };
+    }).filter(Boolean).sort(function (a, b) {
+      return b.score - a.score;
+    }).slice(0, 20);
+  }
+
+  function snippet(text, query) {
+    var word = normalize(query).split(/\s+/).filter(Boolean)[0] || "";
+    var at = Math.max(0, normalize(text).indexOf(word));
+    var start = Math.max(0, at - 40);
+    var before = escape(text.slice(start, at));
+    var match = escape(text.slice(at, at + word.length));
+    var after = escape(text.slice(at + word.length, at + word.length + 80));
+    return (start > 0 ? "…" : "") + before + "<mark>" + match + "</mark>" + after + "…";
+  }
+
+  function setupSearch() {
+    var input = document.querySelector(".site-search");
+    var results = document.querySelector(".site-results");
+    var nav = document.querySelector(".site-nav");
+    if (!input || !results) return;
+
+    input.addEventListener("input", function () {
+      var query = input.value.trim();
+      var found = search(query);
+      results.hidden = !query;
+      if (nav) nav.hidden = !!query;
+      results.innerHTML = query && !found.length
+        ? "<li><small>Sin resultados</small></li>"
+        : found.map(function (result) {
+          var page = result.page;
+          return "<li><a href=\"" + escape(root + encodeURI(page.url)) + "\">" + escape(page.title) + "</a>"
+            + "<small>" + snippet(page.text, query) + "</small></li>";
+        }).join("");
+    });
+
+    input.addEventListener("keydown", function (event) {
+      if (event.key !== "Enter") return;
+      var first = results.querySelector("a");
+      if (first) window.location.href = first.href;
+    });
+  }
+
+  window.addEventListener("DOMContentLoaded", function () {
+    renderMath();
+    renderDiagrams();
+    setupSearch();
+  });
+})();
@@ -0,0 +1,351 @@
+//! A project rendered as a static website: one page per document, the
+//! navigation built from the file tree and a search index for the browser.
+
+use crate::export::{document_title, enhancements, export_css, html_page, text_content, Enhancement};
+use crate::frontmatter::{parse_front_matter, MetaValue};
+use crate::links::{is_markdown_path, parent_dir, parse_doc_link, percent_encode, relative_path};
+use crate::toc::{toc_html, TocOptions};
+use crate::{escape_html, extract_headings, unescape_html, Heading};
+use regex_lite::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+const SITE_CSS: &str = include_str!("site.css");
+
+/// Loaded by every page: renders math and diagrams and runs the search box.
+pub const SITE_JS: &str = include_str!("site.js");
+
+const KATEX_CSS: &str = "https://cdn.jsdelivr.net/npm/katex@0.17/dist/katex.min.css";
+const KATEX_JS: &str = "https://cdn.jsdelivr.net/npm/katex@0.17/dist/katex.min.js";
+const MERMAID_JS: &str = "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.min.js";
+
+/// A document or folder of the project tree, in the order the sidebar shows them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SiteEntry {
+    Page { path: String, content: String },
+    Folder { name: String, children: Vec<SiteEntry> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavItem {
+    pub title: String,
+    /// Page the item links to, relative to the site root. Folders without an
+    /// index page have none.
+    pub url: Option<String>,
+    pub children: Vec<NavItem>,
+}
+
+/// What the search box looks through for one page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchEntry {
+    pub title: String,
+    pub url: String,
+    pub headings: Vec<String>,
+    pub text: String,
+}
+
+/// Where the page for the document at `path` goes, relative to the site root:
+/// the same place with `.html` in place of `.md`.
+pub fn page_output(root: &str, path: &str) -> String {
+    let relative = relative_path(root, path);
+    match relative.rsplit_once('.') {
+        Some((stem, extension)) if is_markdown_path(&format!(".{}", extension)) => format!("{}.html", stem),
+        _ => format!("{}.html", relative),
+    }
+}
+
+/// The `order` (or `weight`) of a document's front matter. Pages without one
+/// go after those with one, in the tree's order.
+pub fn page_order(content: &str) -> Option<f64> {
+    let front_matter = parse_front_matter(content)?;
+    let value = front_matter.get("order").or_else(|| front_matter.get("weight"))?;
+    match value {
+        MetaValue::Number(n) | MetaValue::Text(n) => n.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Drafts stay out of the site.
+pub fn is_draft(content: &str) -> bool {
+    parse_front_matter(content).is_some_and(|fm| fm.draft())
+}
+
+fn is_index(path: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path).to_ascii_lowercase();
+    matches!(name.as_str(), "index.md" | "readme.md")
+}
+
+/// The sidebar of the site. A folder's `index.md` or `README.md` gives the
+/// folder its link, title and order instead of appearing as a child.
+pub fn navigation(root: &str, entries: &[SiteEntry]) -> Vec<NavItem> {
+    let mut items: Vec<(Option<f64>, NavItem)> = Vec::new();
+    for entry in entries {
+        match entry {
+            SiteEntry::Page { path, content } => {
+                if is_draft(content) {
+                    continue;
+                }
+                let item = NavItem {
+                    title: document_title(content, path),
+                    url: Some(page_output(root, path)),
+                    children: Vec::new(),
+                };
+                items.push((page_order(content), item));
+            }
+            SiteEntry::Folder { name, children } => {
+                let index = children.iter().find_map(|child| match child {
+                    SiteEntry::Page { path, content } if is_index(path) && !is_draft(content) => Some((path, content)),
+                    _ => None,
+                });
+                let is_index_page =
+                    |child: &SiteEntry| matches!(child, SiteEntry::Page { path, .. } if index.is_some_and(|(i, _)| i == path));
+                let rest: Vec<SiteEntry> = children.iter().filter(|child| !is_index_page(child)).cloned().collect();
+                let children = navigation(root, &rest);
+                if children.is_empty() && index.is_none() {
+                    continue;
+                }
+                let item = NavItem {
+                    title: index.map_or_else(|| name.clone(), |(path, content)| document_title(content, path)),
+                    url: index.map(|(path, _)| page_output(root, path)),
+                    children,
+                };
+                items.push((index.and_then(|(_, content)| page_order(content)), item));
+            }
+        }
+    }
+    // Stable, so pages without an order keep the tree's.
+    items.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Every page of the navigation, depth first: the order the site is read in.
+pub fn reading_order(items: &[NavItem]) -> Vec<String> {
+    let mut urls = Vec::new();
+    for item in items {
+        urls.extend(item.url.clone());
+        urls.extend(reading_order(&item.children));
+    }
+    urls
+}
+
+/// A link from the page at `current` to the page at `url`, both relative to the site root.
+fn link_from(current: &str, url: &str) -> String {
+    relative_path(parent_dir(current), url).split('/').map(percent_encode).collect::<Vec<_>>().join("/")
+}
+
+fn push_nav(items: &[NavItem], current: &str, out: &mut String) {
+    out.push_str("<ul>");
+    for item in items {
+        out.push_str("<li>");
+        match &item.url {
+            Some(url) => {
+                let active = if url == current { " class=\"active\"" } else { "" };
+                out.push_str(&format!(
+                    "<a href=\"{}\"{}>{}</a>",
+                    escape_html(&link_from(current, url)),
+                    active,
+                    escape_html(&item.title)
+                ));
+            }
+            None => out.push_str(&format!("<span>{}</span>", escape_html(&item.title))),
+        }
+        if !item.children.is_empty() {
+            push_nav(&item.children, current, out);
+        }
+        out.push_str("</li>");
+    }
+    out.push_str("</ul>");
+}
+
+/// The navigation as HTML for the page at `current`, with that page marked.
+pub fn nav_html(items: &[NavItem], current: &str) -> String {
+    let mut out = String::from("<nav class=\"site-nav\">");
+    push_nav(items, current, &mut out);
+    out.push_str("</nav>");
+    out
+}
+
+/// Points the `codedocs-doc:` links of a page rendered with a document path
+/// at the pages of the site. `page_for` maps a document path to its page;
+/// links to documents that are not on the site lead nowhere.
+pub fn rewrite_doc_links(html: &str, current: &str, page_for: impl Fn(&str) -> Option<String>) -> String {
+    let href_re = Regex::new(r#"\bhref="(codedocs-doc:[^"]*)""#).unwrap();
+    href_re
+        .replace_all(html, |found: &Captures| {
+            let Some((path, anchor)) = parse_doc_link(&unescape_html(&found[1])) else {
+                return found[0].to_string();
+            };
+            let anchor = anchor.map(|a| format!("#{}", percent_encode(&a))).unwrap_or_default();
+            let href = match page_for(&path) {
+                Some(url) if url == current && !anchor.is_empty() => anchor,
+                Some(url) => format!("{}{}", link_from(current, &url), anchor),
+                None => "#".to_string(),
+            };
+            format!("href=\"{}\"", escape_html(&href))
+        })
+        .into_owned()
+}
+
+/// The search entry of a page from its source and rendered body.
+pub fn search_entry(title: &str, url: &str, content: &str, body: &str) -> SearchEntry {
+    let text = text_content(body).split_whitespace().collect::<Vec<_>>().join(" ");
+    SearchEntry {
+        title: title.to_string(),
+        url: url.to_string(),
+        headings: extract_headings(content).into_iter().map(|h| h.text).collect(),
+        text,
+    }
+}
+
+/// The stylesheet every page links.
+pub fn site_css() -> String {
+    format!("{}\n{}", export_css(), SITE_CSS)
+}
+
+/// A page of the site at `current`: the navigation and search box on the
+/// left, the document and its table of contents on the right. KaTeX and
+/// Mermaid are only loaded by pages that need them.
+pub fn site_page(site_title: &str, title: &str, body: &str, headings: &[Heading], nav: &[NavItem], current: &str) -> String {
+    let root = relative_path(parent_dir(current), "");
+    let root = if root.is_empty() { String::new() } else { format!("{}/", root) };
+
+    let mut head = format!("<link rel=\"stylesheet\" href=\"{}style.css\">\n", root);
+    let found = enhancements(body);
+    if found.iter().any(|e| matches!(e, Enhancement::Math { .. })) {
+        head.push_str(&format!("<link rel=\"stylesheet\" href=\"{}\">\n", KATEX_CSS));
+        head.push_str(&format!("<script defer src=\"{}\"></script>\n", KATEX_JS));
+    }
+    if found.iter().any(|e| matches!(e, Enhancement::Diagram { .. })) {
+        head.push_str(&format!("<script defer src=\"{}\"></script>\n", MERMAID_JS));
+    }
+    head.push_str(&format!(
+        concat!(
+            "<script defer src=\"{0}search-index.js\"></script>\n",
+            "<script defer src=\"{0}site.js\" data-root=\"{0}\"></script>\n"
+        ),
+        root
+    ));
+
+    let toc = if headings.len() > 1 {
+        format!("<aside class=\"site-toc\">{}</aside>\n", toc_html(headings, &TocOptions::default()))
+    } else {
+        String::new()
+    };
+    let sidebar = format!(
+        concat!(
+            "<aside class=\"sidebar\">\n<a class=\"site-title\" href=\"{}index.html\">{}</a>\n",
+            "<input type=\"search\" class=\"site-search\" placeholder=\"Buscar...\" aria-label=\"Buscar\">\n",
+            "<ul class=\"site-results\" hidden></ul>\n{}\n</aside>\n"
+        ),
+        root,
+        escape_html(site_title),
+        nav_html(nav, current)
+    );
+    let page_title = if title == site_title { title.to_string() } else { format!("{} · {}", title, site_title) };
+    html_page(&page_title, &head, &sidebar, body, &toc, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::doc_link_url;
+
+    fn page(path: &str, content: &str) -> SiteEntry {
+        SiteEntry::Page { path: path.to_string(), content: content.to_string() }
+    }
+
+    fn tree() -> Vec<SiteEntry> {
+        vec![
+            page("/p/index.md", "# Inicio\n"),
+            SiteEntry::Folder {
+                name: "guia".to_string(),
+                children: vec![
+                    page("/p/guia/README.md", "---\ntitle: Guía\norder: 1\n---\n"),
+                    page("/p/guia/mas pasos.md", "# Más pasos\n"),
+                    page("/p/guia/pasos.md", "---\nweight: '0.5'\n---\n# Pasos\n"),
+                ],
+            },
+            SiteEntry::Folder {
+                name: "vacia".to_string(),
+                children: vec![page("/p/vacia/b.md", "---\ndraft: true\n---\n")],
+            },
+            page("/p/notas.markdown", "Sin título\n"),
+        ]
+    }
+
+    fn item(title: &str, url: &str, children: Vec<NavItem>) -> NavItem {
+        NavItem { title: title.to_string(), url: Some(url.to_string()), children }
+    }
+
+    #[test]
+    fn pages_go_where_their_documents_are() {
+        assert_eq!(page_output("/p", "/p/guia/Más pasos.md"), "guia/Más pasos.html");
+        assert_eq!(page_output("/p", "/p/notas.MARKDOWN"), "notas.html");
+        assert_eq!(page_output("/p", "/p/LICENSE"), "LICENSE.html");
+    }
+
+    #[test]
+    fn navigation_follows_order_skips_drafts_and_uses_folder_indexes() {
+        let nav = navigation("/p", &tree());
+        assert_eq!(
+            nav,
+            [
+                item(
+                    "Guía",
+                    "guia/README.html",
+                    vec![item("Pasos", "guia/pasos.html", vec![]), item("Más pasos", "guia/mas pasos.html", vec![])]
+                ),
+                item("Inicio", "index.html", vec![]),
+                item("notas", "notas.html", vec![]),
+            ]
+        );
+        assert_eq!(
+            reading_order(&nav),
+            ["guia/README.html", "guia/pasos.html", "guia/mas pasos.html", "index.html", "notas.html"]
+        );
+    }
+
+    #[test]
+    fn navigation_links_are_relative_to_the_current_page() {
+        let html = nav_html(&navigation("/p", &tree()), "guia/pasos.html");
+        assert!(html.contains("<a href=\"mas%20pasos.html\">Más pasos</a>"));
+        assert!(html.contains("<a href=\"pasos.html\" class=\"active\">Pasos</a>"));
+        assert!(html.contains("<a href=\"../index.html\">Inicio</a>"));
+    }
+
+    #[test]
+    fn document_links_point_at_site_pages() {
+        let html = format!(
+            "<a href=\"{}\">a</a> <a href=\"{}\">b</a> <a href=\"{}\">c</a> <a href=\"https://x.org\">d</a>",
+            escape_html(&doc_link_url("/p/index.md", Some("uso"))),
+            escape_html(&doc_link_url("/p/guia/pasos.md", Some("paso 1"))),
+            escape_html(&doc_link_url("/p/fuera.md", None)),
+        );
+        let page_for = |path: &str| (path != "/p/fuera.md").then(|| page_output("/p", path));
+        assert_eq!(
+            rewrite_doc_links(&html, "guia/pasos.html", page_for),
+            concat!(
+                "<a href=\"../index.html#uso\">a</a> <a href=\"#paso%201\">b</a> ",
+                "<a href=\"#\">c</a> <a href=\"https://x.org\">d</a>"
+            )
+        );
+    }
+
+    #[test]
+    fn pages_load_only_what_they_use_from_the_site_root() {
+        let nav = navigation("/p", &tree());
+        let plain = site_page("Docs", "Pasos", "<p>Hola</p>", &[], &nav, "guia/pasos.html");
+        assert!(plain.contains("href=\"../style.css\"") && plain.contains("src=\"../site.js\" data-root=\"../\""));
+        assert!(plain.contains("<title>Pasos · Docs</title>"));
+        assert!(!plain.contains("katex") && !plain.contains("mermaid"));
+
+        let body = "<h1>Pasos</h1>\n<p>Texto  <b>a</b></p>";
+        let search = search_entry("Pasos", "guia/pasos.html", "# Pasos\n\n## Uno\n", body);
+        assert_eq!(search.headings, ["Pasos", "Uno"]);
+        assert_eq!(search.text, "Pasos Texto a");
+    }
+}
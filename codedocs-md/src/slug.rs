@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+/// GitHub-style slug: lowercase, drop punctuation, spaces become `-`.
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Hands out unique heading ids for one document, deduplicating repeated
+/// headings as `slug`, `slug-1`, `slug-2`... the same way GitHub does.
+#[derive(Debug, Default)]
+pub struct Slugger {
+    occurrences: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slugify(text);
+        let mut result = base.clone();
+        while self.occurrences.contains_key(&result) {
+            let count = self.occurrences.entry(base.clone()).or_insert(0);
+            *count += 1;
+            result = format!("{}-{}", base, count);
+        }
+        self.occurrences.insert(result.clone(), 0);
+        result
+    }
+
+    /// Registers an explicit `{#custom-id}` so later generated slugs avoid it.
+    pub fn reserve(&mut self, id: &str) -> String {
+        self.occurrences.entry(id.to_string()).or_insert(0);
+        id.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_headings_get_numbered_slugs() {
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.slug("Uso básico"), "uso-básico");
+        assert_eq!(slugger.slug("Uso básico"), "uso-básico-1");
+        assert_eq!(slugger.slug("Uso básico!"), "uso-básico-2");
+        // A heading whose own slug is a generated one does not collide either.
+        assert_eq!(slugger.slug("Uso básico 1"), "uso-básico-1-1");
+    }
+
+    #[test]
+    fn reserved_ids_are_skipped() {
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.reserve("intro"), "intro");
+        assert_eq!(slugger.slug("Intro"), "intro-1");
+        assert_eq!(slugger.slug("Intro"), "intro-2");
+    }
+}
@@ -0,0 +1,440 @@
+//! GFM tables as a grid of cells that can be edited and written back.
+
+use crate::edit::TextEdit;
+use crate::options;
+use pulldown_cmark::{Alignment, Event, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnAlignment {
+    #[default]
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+impl From<Alignment> for ColumnAlignment {
+    fn from(alignment: Alignment) -> Self {
+        match alignment {
+            Alignment::None => Self::None,
+            Alignment::Left => Self::Left,
+            Alignment::Center => Self::Center,
+            Alignment::Right => Self::Right,
+        }
+    }
+}
+
+/// A table with its header as the first row. Cells keep their inline
+/// markdown as written.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GfmTable {
+    pub alignments: Vec<ColumnAlignment>,
+    pub rows: Vec<Vec<String>>,
+    /// Where the table sits in the source it was read from.
+    pub range: Range<usize>,
+}
+
+pub(crate) fn line_start(content: &str, offset: usize) -> usize {
+    content[..offset].rfind('\n').map_or(0, |ix| ix + 1)
+}
+
+/// What continuation lines of a block starting at `offset` are prefixed
+/// with: the blockquote markers before it, with list markers blanked out.
+pub(crate) fn container_prefix(content: &str, offset: usize) -> String {
+    content[line_start(content, offset)..offset]
+        .chars()
+        .map(|c| if c == '>' || c == '\t' { c } else { ' ' })
+        .collect()
+}
+
+/// Every table in the document, in source order.
+pub fn extract_tables(content: &str) -> Vec<GfmTable> {
+    let mut tables = Vec::new();
+    let mut current: Option<GfmTable> = None;
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Table(alignments)) => {
+                current = Some(GfmTable {
+                    alignments: alignments.into_iter().map(ColumnAlignment::from).collect(),
+                    rows: Vec::new(),
+                    range,
+                });
+            }
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                if let Some(table) = current.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Event::Start(Tag::TableCell) => {
+                if let Some(row) = current.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(content[range].trim().to_string());
+                }
+            }
+            Event::End(TagEnd::Table) => {
+                if let Some(mut table) = current.take() {
+                    table.normalize();
+                    tables.push(table);
+                }
+            }
+            _ => {}
+        }
+    }
+    tables
+}
+
+/// The table on the given 1-based line, if any.
+pub fn table_at_line(content: &str, line: usize) -> Option<GfmTable> {
+    let start = content.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum::<usize>();
+    table_at(content, start)
+}
+
+/// The table covering the byte `offset`, if any.
+pub fn table_at(content: &str, offset: usize) -> Option<GfmTable> {
+    extract_tables(content).into_iter().find(|table| {
+        let end = content[..table.range.end].trim_end_matches(['\n', '\r']).len();
+        line_start(content, table.range.start) <= offset && offset <= end
+    })
+}
+
+/// Splits CSV or TSV text into rows; the delimiter is a tab if the first line
+/// has one, otherwise whichever of `;` and `,` it uses more.
+pub fn parse_delimited(text: &str) -> Vec<Vec<String>> {
+    let first = text.lines().next().unwrap_or_default();
+    let delimiter = if first.contains('\t') {
+        '\t'
+    } else if first.matches(';').count() > first.matches(',').count() {
+        ';'
+    } else {
+        ','
+    };
+
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    rows.into_iter()
+        .filter(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|row| {
+            row.into_iter()
+                .map(|cell| cell.trim().replace(['\n', '\r'], " ").replace('|', "\\|"))
+                .collect()
+        })
+        .collect()
+}
+
+impl GfmTable {
+    /// A table from parsed CSV/TSV rows, the first one used as the header.
+    pub fn from_rows(rows: Vec<Vec<String>>) -> Option<Self> {
+        let columns = rows.iter().map(Vec::len).max().filter(|&n| n > 0)?;
+        let mut table = GfmTable {
+            alignments: vec![ColumnAlignment::None; columns],
+            rows,
+            range: 0..0,
+        };
+        table.normalize();
+        Some(table)
+    }
+
+    /// An empty table with a header and one row.
+    pub fn empty(columns: usize) -> Self {
+        let columns = columns.max(1);
+        GfmTable {
+            alignments: vec![ColumnAlignment::None; columns],
+            rows: vec![
+                (1..=columns).map(|n| format!("Columna {}", n)).collect(),
+                vec![String::new(); columns],
+            ],
+            range: 0..0,
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.alignments.len()
+    }
+
+    /// Pads short rows and drops cells past the last column.
+    fn normalize(&mut self) {
+        let columns = self.columns();
+        for row in &mut self.rows {
+            row.resize(columns, String::new());
+        }
+    }
+
+    /// Sets a cell from what the user typed; a `|` that is not escaped yet
+    /// is, so it does not split the cell.
+    pub fn set_cell(&mut self, row: usize, column: usize, text: &str) {
+        if let Some(cell) = self.rows.get_mut(row).and_then(|r| r.get_mut(column)) {
+            let mut escaped = String::with_capacity(text.len());
+            let mut backslash = false;
+            for c in text.trim().chars() {
+                match c {
+                    '\n' | '\r' => escaped.push(' '),
+                    '|' if !backslash => escaped.push_str("\\|"),
+                    c => escaped.push(c),
+                }
+                backslash = c == '\\' && !backslash;
+            }
+            *cell = escaped;
+        }
+    }
+
+    /// Inserts an empty body row at `at` (the header is row 0 and stays first).
+    pub fn insert_row(&mut self, at: usize) {
+        let at = at.clamp(1, self.rows.len());
+        self.rows.insert(at, vec![String::new(); self.columns()]);
+    }
+
+    pub fn remove_row(&mut self, row: usize) {
+        if row > 0 && row < self.rows.len() && self.rows.len() > 2 {
+            self.rows.remove(row);
+        }
+    }
+
+    pub fn move_row(&mut self, from: usize, to: usize) {
+        if from > 0 && to > 0 && from < self.rows.len() && to < self.rows.len() {
+            let row = self.rows.remove(from);
+            self.rows.insert(to, row);
+        }
+    }
+
+    pub fn insert_column(&mut self, at: usize) {
+        let at = at.min(self.columns());
+        self.alignments.insert(at, ColumnAlignment::None);
+        for row in &mut self.rows {
+            row.insert(at, String::new());
+        }
+    }
+
+    pub fn remove_column(&mut self, column: usize) {
+        if column < self.columns() && self.columns() > 1 {
+            self.alignments.remove(column);
+            for row in &mut self.rows {
+                row.remove(column);
+            }
+        }
+    }
+
+    pub fn move_column(&mut self, from: usize, to: usize) {
+        if from < self.columns() && to < self.columns() {
+            let alignment = self.alignments.remove(from);
+            self.alignments.insert(to, alignment);
+            for row in &mut self.rows {
+                let cell = row.remove(from);
+                row.insert(to, cell);
+            }
+        }
+    }
+
+    pub fn set_alignment(&mut self, column: usize, alignment: ColumnAlignment) {
+        if let Some(current) = self.alignments.get_mut(column) {
+            *current = alignment;
+        }
+    }
+
+    /// The table's source lines; with `padding` the columns line up.
+    pub fn render_lines(&self, padding: bool) -> Vec<String> {
+        let columns = self.columns();
+        let width = |text: &str| text.chars().count();
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                let cells = self.rows.iter().map(|row| row.get(col).map_or(0, |cell| width(cell)));
+                cells.max().unwrap_or(0).max(3)
+            })
+            .collect();
+
+        let pad = |text: &str, col: usize| -> String {
+            if !padding {
+                return text.to_string();
+            }
+            let fill = widths[col].saturating_sub(width(text));
+            match self.alignments[col] {
+                ColumnAlignment::Right => format!("{}{}", " ".repeat(fill), text),
+                ColumnAlignment::Center => format!("{}{}{}", " ".repeat(fill / 2), text, " ".repeat(fill - fill / 2)),
+                _ => format!("{}{}", text, " ".repeat(fill)),
+            }
+        };
+        let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+
+        let mut lines = Vec::new();
+        for (ix, row) in self.rows.iter().enumerate() {
+            let cells = (0..columns).map(|col| pad(row.get(col).map_or("", String::as_str), col)).collect();
+            lines.push(line(cells));
+            if ix == 0 {
+                let rule = (0..columns)
+                    .map(|col| {
+                        let dashes = if padding { widths[col] } else { 3 };
+                        match self.alignments[col] {
+                            ColumnAlignment::Left => format!(":{}", "-".repeat(dashes - 1)),
+                            ColumnAlignment::Right => format!("{}:", "-".repeat(dashes - 1)),
+                            ColumnAlignment::Center => format!(":{}:", "-".repeat(dashes.max(3) - 2)),
+                            ColumnAlignment::None => "-".repeat(dashes),
+                        }
+                    })
+                    .collect();
+                lines.push(line(rule));
+            }
+        }
+        lines
+    }
+
+    /// The table as it should replace its range in `content`, inside the
+    /// same blockquote or list item.
+    pub(crate) fn render(&self, content: &str, padding: bool) -> Option<String> {
+        if self.columns() == 0 || self.rows.is_empty() {
+            return None;
+        }
+        let prefix = container_prefix(content, self.range.start);
+        let mut out = self.render_lines(padding).join(&format!("\n{}", prefix));
+        if content.get(self.range.clone())?.ends_with('\n') {
+            out.push('\n');
+        }
+        Some(out)
+    }
+
+    /// Inserts the table after the given 1-based line, set off by blank
+    /// lines. Returns the edit and the line the table's header ends up on.
+    pub fn insert_edit(&self, content: &str, line: usize) -> (TextEdit, usize) {
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let line = line.clamp(1, lines.len().max(1));
+        let at: usize = lines.iter().take(line).map(|l| l.len()).sum();
+        let current_blank = lines.get(line - 1).is_none_or(|l| l.trim().is_empty());
+        let next_blank = lines.get(line).is_none_or(|l| l.trim().is_empty());
+
+        let mut replacement = String::new();
+        if at > 0 && !content[..at].ends_with('\n') {
+            replacement.push('\n');
+        }
+        if !current_blank {
+            replacement.push('\n');
+        }
+        replacement.push_str(&self.render_lines(true).join("\n"));
+        replacement.push('\n');
+        if !next_blank {
+            replacement.push('\n');
+        }
+
+        let header_line = if content.is_empty() { 1 } else { line + 1 + usize::from(!current_blank) };
+        let edit = TextEdit {
+            start: at,
+            end: at,
+            original: String::new(),
+            replacement,
+        };
+        (edit, header_line)
+    }
+
+    /// Replaces the table in `content` (the text it was read from) with its
+    /// current cells, padded.
+    pub fn edit(&self, content: &str) -> Option<TextEdit> {
+        Some(TextEdit {
+            start: self.range.start,
+            end: self.range.end,
+            original: content.get(self.range.clone())?.to_string(),
+            replacement: self.render(content, true)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(table: &GfmTable) -> Vec<Vec<&str>> {
+        table.rows.iter().map(|row| row.iter().map(String::as_str).collect()).collect()
+    }
+
+    #[test]
+    fn tables_are_found_by_line_with_their_cells() {
+        let content = "Texto\n\n| a | b |\n|:--|--:|\n| 1 | 2 \\| x |\n| 3 |\n\nFin\n";
+        assert!(table_at_line(content, 1).is_none());
+        assert!(table_at_line(content, 8).is_none());
+
+        let table = table_at_line(content, 6).unwrap();
+        assert_eq!(table, table_at_line(content, 3).unwrap());
+        assert_eq!(table.alignments, [ColumnAlignment::Left, ColumnAlignment::Right]);
+        assert_eq!(rows(&table), [vec!["a", "b"], vec!["1", "2 \\| x"], vec!["3", ""]]);
+    }
+
+    #[test]
+    fn edits_write_the_table_back_padded_inside_its_container() {
+        let content = "> | a | b |\n> |---|:-:|\n> | 1 | 2 |\n\nFin\n";
+        let mut table = table_at_line(content, 1).unwrap();
+        table.set_cell(1, 0, "10 | 20");
+        let updated = table.edit(content).unwrap().apply(content).unwrap();
+        assert_eq!(updated, "> | a        |  b  |\n> | -------- | :-: |\n> | 10 \\| 20 |  2  |\n\nFin\n");
+    }
+
+    #[test]
+    fn rows_and_columns_keep_the_header_and_at_least_one_cell() {
+        let mut table = GfmTable::empty(2);
+        table.insert_row(0);
+        assert_eq!(table.rows.len(), 3);
+        table.remove_row(0);
+        table.remove_row(1);
+        table.remove_row(1);
+        assert_eq!(table.rows.len(), 2);
+
+        table.set_alignment(1, ColumnAlignment::Center);
+        table.move_column(1, 0);
+        assert_eq!(table.alignments, [ColumnAlignment::Center, ColumnAlignment::None]);
+        assert_eq!(table.rows[0], ["Columna 2", "Columna 1"]);
+        table.remove_column(0);
+        table.remove_column(0);
+        assert_eq!(table.columns(), 1);
+        table.set_cell(0, 0, "ya \\| escapado");
+        assert_eq!(table.rows[0][0], "ya \\| escapado");
+    }
+
+    #[test]
+    fn delimited_text_becomes_rows() {
+        assert_eq!(
+            parse_delimited("nombre,nota\n\"Pérez, Ana\",\"dice \"\"hola\"\"\"\n,\nb|c,1\n"),
+            [vec!["nombre", "nota"], vec!["Pérez, Ana", "dice \"hola\""], vec!["b\\|c", "1"]]
+        );
+        assert_eq!(parse_delimited("a;b,c;d\r\n1;2;3"), [vec!["a", "b,c", "d"], vec!["1", "2", "3"]]);
+        assert_eq!(parse_delimited("a\tb;c\n1\t2"), [vec!["a", "b;c"], vec!["1", "2"]]);
+
+        let table = GfmTable::from_rows(parse_delimited("a,b,c\n1\n")).unwrap();
+        assert_eq!(rows(&table), [vec!["a", "b", "c"], vec!["1", "", ""]]);
+        assert!(GfmTable::from_rows(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn new_tables_are_set_off_by_blank_lines() {
+        let content = "# T\nTexto\n";
+        let (edit, header_line) = GfmTable::empty(1).insert_edit(content, 1);
+        assert_eq!(edit.apply(content).unwrap(), "# T\n\n| Columna 1 |\n| --------- |\n|           |\n\nTexto\n");
+        assert_eq!(header_line, 3);
+
+        let (edit, header_line) = GfmTable::empty(1).insert_edit("", 1);
+        assert_eq!(edit.replacement, "| Columna 1 |\n| --------- |\n|           |\n");
+        assert_eq!(header_line, 1);
+    }
+}
@@ -0,0 +1,107 @@
+//! Task list items (`- [ ] ...`) and toggling them in the source.
+
+use crate::edit::TextEdit;
+use crate::headings::inline_text;
+use crate::{extract_headings, line_starts, options};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    /// Document the task is in; empty for tasks of a single document.
+    pub file: String,
+    /// 1-based line of the `[ ]` marker.
+    pub line: usize,
+    pub text: String,
+    pub checked: bool,
+    /// Text of the closest heading above the task.
+    pub heading: Option<String>,
+}
+
+/// Every task item of the document, in source order.
+pub fn extract_tasks(content: &str) -> Vec<Task> {
+    let starts = line_starts(content);
+    let headings = extract_headings(content);
+    let mut tasks = Vec::new();
+    // The task being read and the events of its text so far.
+    let mut current: Option<(Task, Vec<Event>)> = None;
+
+    for (event, range) in Parser::new_ext(content, options()).into_offset_iter() {
+        match event {
+            Event::TaskListMarker(checked) => {
+                let line = starts.partition_point(|&start| start <= range.start);
+                let heading = headings.iter().rev().find(|h| h.line < line).map(|h| h.text.clone());
+                let task = Task { file: String::new(), line, text: String::new(), checked, heading };
+                current = Some((task, Vec::new()));
+            }
+            // The text ends with its paragraph, a nested list or the item.
+            Event::End(TagEnd::Paragraph | TagEnd::Item) | Event::Start(Tag::List(_)) => {
+                if let Some((mut task, events)) = current.take() {
+                    task.text = inline_text(events.iter()).trim().to_string();
+                    tasks.push(task);
+                }
+            }
+            event => {
+                if let Some((_, events)) = current.as_mut() {
+                    events.push(event);
+                }
+            }
+        }
+    }
+    tasks
+}
+
+/// Flips the `[ ]`/`[x]` marker of the task on the given 1-based line.
+pub fn toggle_task(content: &str, line: usize) -> Option<TextEdit> {
+    let starts = line_starts(content);
+    Parser::new_ext(content, options()).into_offset_iter().find_map(|(event, range)| {
+        let Event::TaskListMarker(checked) = event else { return None };
+        if starts.partition_point(|&start| start <= range.start) != line {
+            return None;
+        }
+        // The marker is `[`, the state character and `]`.
+        let at = range.start + 1;
+        Some(TextEdit {
+            start: at,
+            end: at + 1,
+            original: content.get(at..at + 1)?.to_string(),
+            replacement: if checked { " " } else { "x" }.to_string(),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Lista\n\n- [ ] uno\n- [x] dos\n  - [X] anidada\n- tres\n\n```\n- [ ] en código\n```\n";
+
+    fn toggled(content: &str, line: usize) -> Option<String> {
+        toggle_task(content, line)?.apply(content)
+    }
+
+    #[test]
+    fn toggling_flips_only_the_marker() {
+        assert_eq!(toggled(DOC, 3).unwrap(), DOC.replace("- [ ] uno", "- [x] uno"));
+        assert_eq!(toggled(DOC, 4).unwrap(), DOC.replace("- [x] dos", "- [ ] dos"));
+        assert_eq!(toggled(DOC, 5).unwrap(), DOC.replace("[X] anidada", "[ ] anidada"));
+        let twice = toggled(&toggled(DOC, 3).unwrap(), 3).unwrap();
+        assert_eq!(twice, DOC);
+    }
+
+    #[test]
+    fn lines_without_a_task_are_left_alone() {
+        assert_eq!(toggle_task(DOC, 1), None);
+        assert_eq!(toggle_task(DOC, 6), None);
+        assert_eq!(toggle_task(DOC, 9), None);
+        assert_eq!(toggle_task(DOC, 40), None);
+    }
+
+    #[test]
+    fn tasks_know_their_heading() {
+        let tasks = extract_tasks(DOC);
+        assert_eq!(tasks.len(), 3);
+        assert!(tasks.iter().all(|task| task.heading.as_deref() == Some("Lista")));
+        assert_eq!(tasks.iter().map(|task| task.checked).collect::<Vec<_>>(), [false, true, true]);
+    }
+}
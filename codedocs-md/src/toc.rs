@@ -0,0 +1,275 @@
+use crate::{escape_html, extract_headings, Heading};
+use regex_lite::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::LazyLock;
+
+static ATTR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(\w+)\s*=\s*(?:"([^"]*)"|'([^']*)'|(\S+))"#).unwrap());
+static OPEN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^\s*<!--\s*toc\b(.*?)-->\s*$").unwrap());
+static CLOSE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^\s*<!--\s*/toc\s*-->\s*$").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TocOptions {
+    pub min_level: u8,
+    pub max_level: u8,
+    pub ordered: bool,
+    /// Heading texts (case-insensitive) left out of the list.
+    pub exclude: Vec<String>,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            min_level: 1,
+            max_level: 6,
+            ordered: false,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl TocOptions {
+    /// These options with the attributes of a `<!-- toc depth=2-4 style=ordered exclude="A, B" -->`
+    /// marker applied on top.
+    pub fn with_attrs(&self, attrs: &str) -> Self {
+        let mut options = self.clone();
+        for caps in ATTR_RE.captures_iter(attrs) {
+            let value = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .or_else(|| caps.get(4))
+                .map(|m| m.as_str())
+                .unwrap_or_default();
+            match caps[1].to_ascii_lowercase().as_str() {
+                "depth" => {
+                    let (min, max) = value.split_once('-').unwrap_or(("1", value));
+                    if let (Ok(min), Ok(max)) = (min.trim().parse::<u8>(), max.trim().parse::<u8>()) {
+                        options.min_level = min.clamp(1, 6);
+                        options.max_level = max.clamp(options.min_level, 6);
+                    }
+                }
+                "style" => options.ordered = value.eq_ignore_ascii_case("ordered"),
+                "exclude" => {
+                    options.exclude = value
+                        .split([',', '|'])
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                }
+                _ => {}
+            }
+        }
+        options
+    }
+
+    fn includes(&self, heading: &Heading) -> bool {
+        heading.level >= self.min_level
+            && heading.level <= self.max_level
+            && !self.exclude.iter().any(|e| e.eq_ignore_ascii_case(heading.text.trim()))
+    }
+}
+
+fn selected<'a>(headings: &'a [Heading], options: &TocOptions) -> (Vec<&'a Heading>, u8) {
+    let items: Vec<&Heading> = headings.iter().filter(|h| options.includes(h)).collect();
+    let base = items.iter().map(|h| h.level).min().unwrap_or(1);
+    (items, base)
+}
+
+/// Markdown list of links to the headings, indented relative to the shallowest one.
+pub fn toc_markdown(headings: &[Heading], options: &TocOptions) -> String {
+    let (items, base) = selected(headings, options);
+    let (marker, indent) = if options.ordered { ("1.", "   ") } else { ("-", "  ") };
+
+    let mut out = String::new();
+    for heading in items {
+        let text = heading.text.replace('[', "\\[").replace(']', "\\]");
+        out.push_str(&indent.repeat((heading.level - base) as usize));
+        out.push_str(&format!("{} [{}](#{})\n", marker, text, heading.slug));
+    }
+    out
+}
+
+/// Nested HTML list used to render `[TOC]` placeholders in the preview.
+pub fn toc_html(headings: &[Heading], options: &TocOptions) -> String {
+    let (items, base) = selected(headings, options);
+    let tag = if options.ordered { "ol" } else { "ul" };
+
+    let mut html = String::from("<nav class=\"toc\">");
+    let mut depth = 0;
+    for heading in items {
+        // Skipped levels (h2 -> h4) nest just one step deeper, as markdown lists do.
+        let level = ((heading.level - base) as usize + 1).min(depth + 1);
+        if level > depth {
+            for _ in depth..level {
+                html.push_str(&format!("<{}>", tag));
+            }
+            depth = level;
+        } else {
+            html.push_str("</li>");
+            while depth > level {
+                html.push_str(&format!("</{}></li>", tag));
+                depth -= 1;
+            }
+        }
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            escape_html(&heading.slug),
+            escape_html(&heading.text)
+        ));
+    }
+    if depth > 0 {
+        html.push_str("</li>");
+    }
+    while depth > 0 {
+        html.push_str(&format!("</{}>", tag));
+        depth -= 1;
+        if depth > 0 {
+            html.push_str("</li>");
+        }
+    }
+    html.push_str("</nav>");
+    html
+}
+
+/// A `<!-- toc -->` marker and the generated list after it.
+pub(crate) struct TocBlock {
+    pub options: TocOptions,
+    /// Between the end of the opening marker line and the start of the
+    /// closing one; empty when the marker has no closing comment.
+    pub body: Range<usize>,
+    pub closed: bool,
+}
+
+/// Every TOC block outside fenced code, in document order. Attributes the
+/// marker leaves out take their value from `defaults`.
+pub(crate) fn toc_blocks(content: &str, defaults: &TocOptions) -> Vec<TocBlock> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        lines.push((offset, line));
+        offset += line.len();
+    }
+
+    let mut blocks = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut ix = 0;
+    while ix < lines.len() {
+        let (start, line) = lines[ix];
+        let trimmed = line.trim_start();
+        ix += 1;
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        let Some(caps) = OPEN_RE.captures(line.trim_end_matches(['\r', '\n'])) else { continue };
+        let body_start = start + line.len();
+        let close = lines[ix..]
+            .iter()
+            .position(|(_, l)| CLOSE_RE.is_match(l.trim_end_matches(['\r', '\n'])));
+        let (body, closed) = match close {
+            Some(offset) => {
+                ix += offset + 1;
+                (body_start..lines[ix - 1].0, true)
+            }
+            None => (body_start..body_start, false),
+        };
+        blocks.push(TocBlock {
+            options: defaults.with_attrs(&caps[1]),
+            body,
+            closed,
+        });
+    }
+    blocks
+}
+
+/// Regenerates the contents of every `<!-- toc -->...<!-- /toc -->` block in place.
+/// A marker without a closing comment gets one inserted after the generated list.
+pub fn update_toc_blocks(content: &str, defaults: &TocOptions) -> String {
+    let blocks = toc_blocks(content, defaults);
+    if blocks.is_empty() {
+        return content.to_string();
+    }
+
+    let headings = extract_headings(content);
+    let mut out = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for block in blocks {
+        out.push_str(&content[cursor..block.body.start]);
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&toc_markdown(&headings, &block.options));
+        if !block.closed {
+            out.push_str("<!-- /toc -->\n");
+        }
+        cursor = block.body.end;
+    }
+    out.push_str(&content[cursor..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str =
+        "# Guia\n\n<!-- toc depth=2-3 -->\n<!-- /toc -->\n\n## Instalar\n\n### En Linux\n\n#### Detalle\n\n## Uso\n";
+
+    #[test]
+    fn marker_attributes_override_the_defaults() {
+        let defaults = TocOptions { max_level: 3, ..Default::default() };
+        let options = defaults.with_attrs(r#" depth=2-9 style=ordered exclude="Notas, Ver también" "#);
+        assert_eq!((options.min_level, options.max_level, options.ordered), (2, 6, true));
+        assert_eq!(options.exclude, ["Notas", "Ver también"]);
+
+        let options = defaults.with_attrs("depth=2 style='bullets' other=1");
+        assert_eq!((options.min_level, options.max_level, options.ordered), (1, 2, false));
+        assert_eq!(defaults.with_attrs("depth=x"), defaults);
+    }
+
+    #[test]
+    fn blocks_inside_fenced_code_are_ignored() {
+        let content = "```md\n<!-- toc -->\n```\n\n~~~\n<!-- toc -->\n<!-- /toc -->\n~~~\n<!-- TOC -->\n";
+        let blocks = toc_blocks(content, &TocOptions::default());
+        assert_eq!(blocks.len(), 1);
+        assert!(!blocks[0].closed);
+        assert_eq!(blocks[0].body, content.len()..content.len());
+    }
+
+    #[test]
+    fn blocks_are_filled_and_regenerating_changes_nothing() {
+        let updated = update_toc_blocks(DOC, &TocOptions::default());
+        let list = "- [Instalar](#instalar)\n  - [En Linux](#en-linux)\n- [Uso](#uso)\n";
+        assert_eq!(updated, DOC.replace("-->\n<!-- /toc", &format!("-->\n{}<!-- /toc", list)));
+        assert_eq!(update_toc_blocks(&updated, &TocOptions::default()), updated);
+    }
+
+    #[test]
+    fn unclosed_markers_get_a_closing_comment() {
+        let content = "<!-- toc style=ordered exclude=Uso -->\n## Instalar\n## Uso\n";
+        let updated = update_toc_blocks(content, &TocOptions::default());
+        let list = "1. [Instalar](#instalar)\n<!-- /toc -->\n";
+        assert_eq!(updated, content.replace("-->\n", &format!("-->\n{}", list)));
+        assert_eq!(update_toc_blocks(&updated, &TocOptions::default()), updated);
+    }
+
+    #[test]
+    fn html_lists_nest_skipped_levels_one_step() {
+        let headings = extract_headings("## A\n#### B\n## C `<x>`\n");
+        assert_eq!(
+            toc_html(&headings, &TocOptions::default()),
+            "<nav class=\"toc\"><ul><li><a href=\"#a\">A</a><ul><li><a href=\"#b\">B</a></li></ul></li>\
+             <li><a href=\"#c-x\">C &lt;x&gt;</a></li></ul></nav>"
+        );
+    }
+}
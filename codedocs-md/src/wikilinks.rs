@@ -0,0 +1,117 @@
+//! `[[page]]`, `[[page#heading]]` and `[[page|alias]]` links, Obsidian style.
+
+use crate::links::is_markdown_path;
+
+fn normalize(path: &str) -> String {
+    path.trim().replace('\\', "/").to_lowercase()
+}
+
+fn strip_markdown_ext(path: &str) -> &str {
+    [".md", ".markdown"]
+        .iter()
+        .find_map(|ext| {
+            let cut = path.len().checked_sub(ext.len())?;
+            path.get(cut..).filter(|tail| tail.eq_ignore_ascii_case(ext)).map(|_| &path[..cut])
+        })
+        .unwrap_or(path)
+}
+
+/// Splits a wiki-link target into the page and the optional heading.
+pub fn split_wikilink(target: &str) -> (&str, Option<&str>) {
+    match target.split_once('#') {
+        Some((page, heading)) => (page.trim(), Some(heading.trim()).filter(|h| !h.is_empty())),
+        None => (target.trim(), None),
+    }
+}
+
+/// Finds the project file a wiki-link names: the one whose path without the
+/// extension ends with the target at a folder boundary. When several match,
+/// the shallowest (then shortest) path wins.
+pub fn resolve_wikilink<'a>(page: &str, files: &'a [String]) -> Option<&'a str> {
+    let target = normalize(page);
+    let target = strip_markdown_ext(target.trim_start_matches("./").trim_start_matches('/'));
+    if target.is_empty() {
+        return None;
+    }
+
+    files
+        .iter()
+        .filter(|file| {
+            let file = normalize(file);
+            let stem = strip_markdown_ext(&file);
+            stem == target || stem.ends_with(&format!("/{}", target))
+        })
+        .min_by_key(|file| (file.matches(['/', '\\']).count(), file.len()))
+        .map(String::as_str)
+}
+
+/// The shortest `[[link]]` that still resolves to `path`, e.g. `notes/todo`
+/// when there is more than one `todo.md` in the project.
+pub fn shortest_wikilink(path: &str, files: &[String]) -> String {
+    let normalized = path.replace('\\', "/");
+    let stem = strip_markdown_ext(&normalized);
+    let parts: Vec<&str> = stem.split('/').filter(|p| !p.is_empty()).collect();
+
+    for take in 1..=parts.len() {
+        let candidate = parts[parts.len() - take..].join("/");
+        if resolve_wikilink(&candidate, files).is_some_and(|found| normalize(found) == normalize(path)) {
+            return candidate;
+        }
+    }
+    parts.join("/")
+}
+
+/// Where a new page for an unresolved `[[page]]` is created: under the project root.
+pub fn new_page_path(page: &str, project_root: &str) -> String {
+    let separator = if project_root.contains('\\') && !project_root.contains('/') { "\\" } else { "/" };
+    let relative = page
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|part| !matches!(*part, "" | "." | ".."))
+        .collect::<Vec<_>>()
+        .join(separator);
+    let file_name = if is_markdown_path(&relative) { relative } else { format!("{}.md", relative) };
+    format!("{}{}{}", project_root.trim_end_matches(['/', '\\']), separator, file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<String> {
+        ["/p/todo.md", "/p/notas/todo.md", "/p/notas/Diario 2024.markdown"].iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn targets_split_into_page_and_heading() {
+        assert_eq!(split_wikilink(" notas/todo # Uso "), ("notas/todo", Some("Uso")));
+        assert_eq!(split_wikilink("todo#"), ("todo", None));
+        assert_eq!(split_wikilink("todo"), ("todo", None));
+    }
+
+    #[test]
+    fn pages_resolve_at_folder_boundaries_preferring_the_shallowest() {
+        let files = files();
+        assert_eq!(resolve_wikilink("todo", &files), Some("/p/todo.md"));
+        assert_eq!(resolve_wikilink("Notas/TODO.md", &files), Some("/p/notas/todo.md"));
+        assert_eq!(resolve_wikilink("./diario 2024", &files), Some("/p/notas/Diario 2024.markdown"));
+        assert_eq!(resolve_wikilink("tas/todo", &files), None);
+        assert_eq!(resolve_wikilink(" ", &files), None);
+    }
+
+    #[test]
+    fn new_pages_are_created_under_the_root() {
+        assert_eq!(new_page_path("ideas/nueva", "/p/"), "/p/ideas/nueva.md");
+        assert_eq!(new_page_path("../fuera/Plan.MD", "C:\\p"), "C:\\p\\fuera\\Plan.MD");
+    }
+
+    #[test]
+    fn shortest_links_ignore_extension_and_path_case() {
+        let files: Vec<String> =
+            ["/p/Guia.MD", "/p/notas/todo.md", "/p/viejo/Todo.markdown"].iter().map(|f| f.to_string()).collect();
+        assert_eq!(shortest_wikilink("/p/Guia.MD", &files), "Guia");
+        assert_eq!(shortest_wikilink("/p/notas/todo.md", &files), "todo");
+        assert_eq!(shortest_wikilink("/p/viejo/Todo.markdown", &files), "viejo/Todo");
+        assert_eq!(shortest_wikilink("\\p\\GUIA.md", &files), "GUIA");
+    }
+}
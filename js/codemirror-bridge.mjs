@@ -2,24 +2,289 @@ import { EditorView, basicSetup } from "codemirror";
 import { markdown, markdownLanguage } from "@codemirror/lang-markdown";
 import { oneDark } from "@codemirror/theme-one-dark";
 import { languages } from "@codemirror/language-data";
-import { EditorState, Compartment } from "@codemirror/state";
-import { keymap } from "@codemirror/view";
+import { EditorState, Compartment, StateEffect, StateField } from "@codemirror/state";
+import { keymap, Decoration, WidgetType } from "@codemirror/view";
+import { setDiagnostics, lintGutter } from "@codemirror/lint";
 
 const themeCompartment = new Compartment();
+const liveCompartment = new Compartment();
 
 let currentView = null;
 let onChangeCallback = null;
+let onCursorLineCallback = null;
+let onScrollLineCallback = null;
+let wikiTargets = [];
+let pendingGotoLine = null;
+let lastDiagnostics = [];
+let livePreviewEnabled = false;
+// Spans sent by Rust with the document length they were computed for.
+let lastLiveSpans = { length: -1, spans: [] };
+
+// Inserts the completion and closes the link, reusing a "]]" auto-inserted by closeBrackets.
+function applyWikiCompletion(text) {
+  return (view, completion, from, to) => {
+    const closing = view.state.sliceDoc(to, to + 2) === "]]" ? "" : "]]";
+    view.dispatch({
+      changes: { from, to, insert: text + closing },
+      selection: { anchor: from + text.length + 2 },
+    });
+  };
+}
+
+// `[[` completes page names; `[[page#` completes that page's headings.
+function wikiLinkCompletions(context) {
+  const match = context.matchBefore(/\[\[[^\[\]|]*$/);
+  if (!match) return null;
+  const query = match.text.slice(2);
+  const hash = query.indexOf("#");
+
+  if (hash >= 0) {
+    const page = query.slice(0, hash).trim().toLowerCase();
+    const target = wikiTargets.find((t) => t.link.toLowerCase() === page);
+    if (!target) return null;
+    return {
+      from: match.from + 2 + hash + 1,
+      options: target.headings.map((heading) => ({
+        label: heading,
+        type: "property",
+        apply: applyWikiCompletion(heading),
+      })),
+      validFor: /^[^\]#|]*$/,
+    };
+  }
+
+  return {
+    from: match.from + 2,
+    options: wikiTargets.map((target) => ({
+      label: target.link,
+      detail: target.path,
+      type: "text",
+      apply: applyWikiCompletion(target.link),
+    })),
+    validFor: /^[^\]#|]*$/,
+  };
+}
+
+// ---------------------------------------------------------------------------
+// Live preview: Rust parses the document and sends the constructs to render
+// (see `codedocs_md::live`); here they become decorations, except on the lines
+// touched by the selection, which keep showing the markdown source.
+
+const setLiveSpans = StateEffect.define();
+
+class ImageWidget extends WidgetType {
+  constructor(src, alt) {
+    super();
+    this.src = src;
+    this.alt = alt;
+  }
+  eq(other) {
+    return other.src === this.src && other.alt === this.alt;
+  }
+  toDOM() {
+    const img = document.createElement("img");
+    img.className = "cm-live-image";
+    img.src = this.src;
+    img.alt = this.alt;
+    img.title = this.alt;
+    return img;
+  }
+}
+
+class MathWidget extends WidgetType {
+  constructor(tex, display) {
+    super();
+    this.tex = tex;
+    this.display = display;
+  }
+  eq(other) {
+    return other.tex === this.tex && other.display === this.display;
+  }
+  toDOM() {
+    const el = document.createElement(this.display ? "div" : "span");
+    el.className = this.display ? "cm-live-math-display" : "cm-live-math";
+    if (window.katex) {
+      window.katex.render(this.tex, el, { displayMode: this.display, throwOnError: false });
+    } else {
+      el.textContent = this.tex;
+    }
+    return el;
+  }
+}
+
+class TaskWidget extends WidgetType {
+  constructor(checked) {
+    super();
+    this.checked = checked;
+  }
+  eq(other) {
+    return other.checked === this.checked;
+  }
+  toDOM(view) {
+    const box = document.createElement("input");
+    box.type = "checkbox";
+    box.className = "cm-live-task";
+    box.checked = this.checked;
+    box.addEventListener("mousedown", (event) => {
+      event.preventDefault();
+      // The marker is "[ ]" or "[x]"; the widget sits where it starts.
+      const pos = view.posAtDOM(box);
+      view.dispatch({ changes: { from: pos + 1, to: pos + 2, insert: this.checked ? " " : "x" } });
+    });
+    return box;
+  }
+}
+
+const markClasses = {
+  emphasis: "cm-live-em",
+  strong: "cm-live-strong",
+  strikethrough: "cm-live-strike",
+  code: "cm-live-code",
+  link: "cm-live-link",
+};
+
+function buildLiveDecorations(state, spans) {
+  const length = state.doc.length;
+  const activeLines = state.selection.ranges.map((range) => [
+    state.doc.lineAt(range.from).number,
+    state.doc.lineAt(range.to).number,
+  ]);
+  const isActive = (from, to) => {
+    const first = state.doc.lineAt(from).number;
+    const last = state.doc.lineAt(to).number;
+    return activeLines.some(([start, end]) => first <= end && start <= last);
+  };
+
+  const decorations = [];
+  for (const span of spans) {
+    if (span.to > length || span.from >= span.to) continue;
+    // A heading's range ends after its newline; only its own lines count.
+    const to = span.kind === "heading" ? Math.max(span.from, span.to - 1) : span.to;
+    if (isActive(span.from, to)) continue;
+
+    switch (span.kind) {
+      case "heading":
+        decorations.push(
+          Decoration.line({ class: `cm-live-h${span.level}` }).range(state.doc.lineAt(span.from).from),
+        );
+        break;
+      case "image":
+        decorations.push(Decoration.replace({ widget: new ImageWidget(span.src, span.alt) }).range(span.from, span.to));
+        continue;
+      case "math":
+        decorations.push(
+          Decoration.replace({ widget: new MathWidget(span.tex, span.display) }).range(span.from, span.to),
+        );
+        continue;
+      case "task":
+        decorations.push(Decoration.replace({ widget: new TaskWidget(span.checked) }).range(span.from, span.to));
+        continue;
+      default: {
+        const attributes = span.kind === "link" ? { title: span.url } : undefined;
+        decorations.push(Decoration.mark({ class: markClasses[span.kind], attributes }).range(span.from, span.to));
+      }
+    }
+    for (const [from, to] of span.hidden) {
+      if (from < to && to <= length) decorations.push(Decoration.replace({}).range(from, to));
+    }
+  }
+  return Decoration.set(decorations, true);
+}
+
+function mapLiveSpans(spans, changes) {
+  return spans.map((span) => ({
+    ...span,
+    from: changes.mapPos(span.from, 1),
+    to: changes.mapPos(span.to, -1),
+    hidden: span.hidden.map(([from, to]) => [changes.mapPos(from, 1), changes.mapPos(to, -1)]),
+  }));
+}
+
+const liveSpansField = StateField.define({
+  create(state) {
+    const spans = lastLiveSpans.length === state.doc.length ? lastLiveSpans.spans : [];
+    return { spans, decorations: buildLiveDecorations(state, spans) };
+  },
+  update(value, tr) {
+    let spans = tr.docChanged ? mapLiveSpans(value.spans, tr.changes) : value.spans;
+    for (const effect of tr.effects) {
+      if (effect.is(setLiveSpans)) spans = effect.value;
+    }
+    if (spans === value.spans && !tr.selection) return value;
+    return { spans, decorations: buildLiveDecorations(tr.state, spans) };
+  },
+  provide: (field) => EditorView.decorations.from(field, (value) => value.decorations),
+});
+
+function liveExtensions() {
+  return livePreviewEnabled ? [liveSpansField] : [];
+}
+
+window.__codedocs_set_live_preview = function (enabled) {
+  livePreviewEnabled = enabled;
+  if (!currentView) return;
+  currentView.dispatch({ effects: liveCompartment.reconfigure(liveExtensions()) });
+};
+
+// Spans computed for a different document (the user kept typing) are dropped;
+// the ones already shown are mapped through the edits until the next batch.
+window.__codedocs_set_live_spans = function (length, spans) {
+  lastLiveSpans = { length, spans };
+  if (!currentView || !livePreviewEnabled || currentView.state.doc.length !== length) return;
+  currentView.dispatch({ effects: setLiveSpans.of(spans) });
+};
+
+function imageFiles(dataTransfer) {
+  if (!dataTransfer) return [];
+  return Array.from(dataTransfer.files || []).filter((file) => file.type.startsWith("image/"));
+}
+
+// The app saves each image as an asset and inserts its link at the cursor.
+async function sendImages(files) {
+  for (const file of files) {
+    const name = file.name || `image.${file.type.split("/")[1] || "png"}`;
+    const bytes = new Uint8Array(await file.arrayBuffer());
+    window.__codedocs_paste_image(name, bytes);
+  }
+}
+
+const imageDropHandlers = EditorView.domEventHandlers({
+  paste(event) {
+    const files = imageFiles(event.clipboardData);
+    if (!window.__codedocs_paste_image || files.length === 0) return false;
+    event.preventDefault();
+    sendImages(files);
+    return true;
+  },
+  drop(event, view) {
+    const files = imageFiles(event.dataTransfer);
+    if (!window.__codedocs_paste_image || files.length === 0) return false;
+    event.preventDefault();
+    const pos = view.posAtCoords({ x: event.clientX, y: event.clientY });
+    if (pos !== null) view.dispatch({ selection: { anchor: pos } });
+    sendImages(files);
+    return true;
+  },
+});
 
 function getExtensions(isDark) {
   return [
     basicSetup,
     markdown({ base: markdownLanguage, codeLanguages: languages }),
+    markdownLanguage.data.of({ autocomplete: wikiLinkCompletions }),
     themeCompartment.of(isDark ? oneDark : []),
+    liveCompartment.of(liveExtensions()),
+    lintGutter(),
+    imageDropHandlers,
     EditorView.lineWrapping,
     EditorView.updateListener.of((update) => {
       if (update.docChanged && onChangeCallback) {
         onChangeCallback(update.state.doc.toString());
       }
+      if ((update.docChanged || update.selectionSet) && onCursorLineCallback) {
+        const state = update.state;
+        onCursorLineCallback(state.doc.lineAt(state.selection.main.head).number);
+      }
     }),
     keymap.of([
       {
@@ -50,6 +315,15 @@ function getExtensions(isDark) {
           return true;
         },
       },
+      {
+        key: "F2",
+        run: (view) => {
+          if (!window.__codedocs_rename_heading) return false;
+          const state = view.state;
+          window.__codedocs_rename_heading(state.doc.lineAt(state.selection.main.head).number);
+          return true;
+        },
+      },
     ]),
   ];
 }
@@ -68,6 +342,18 @@ window.__codedocs_createEditor = function (parentEl, initialContent, isDark) {
     state,
     parent: parentEl,
   });
+  const view = currentView;
+  view.scrollDOM.addEventListener("scroll", () => {
+    if (onScrollLineCallback && view === currentView) onScrollLineCallback(topLine(view));
+  });
+
+  applyDiagnostics();
+
+  if (pendingGotoLine !== null) {
+    const lineNumber = pendingGotoLine;
+    pendingGotoLine = null;
+    window.__codedocs_goto_line(lineNumber);
+  }
 
   return currentView;
 };
@@ -77,13 +363,27 @@ window.__codedocs_getContent = function () {
   return currentView.state.doc.toString();
 };
 
+// Only the span that differs is replaced, so the cursor stays put when the
+// document is rewritten from outside (formatter, metadata form, table grid).
 window.__codedocs_setContent = function (content) {
   if (!currentView) return;
+  const current = currentView.state.doc.toString();
+  if (current === content) return;
+  let from = 0;
+  const max = Math.min(current.length, content.length);
+  while (from < max && current.charCodeAt(from) === content.charCodeAt(from)) from++;
+  let end = 0;
+  while (
+    end < max - from &&
+    current.charCodeAt(current.length - 1 - end) === content.charCodeAt(content.length - 1 - end)
+  ) {
+    end++;
+  }
   currentView.dispatch({
     changes: {
-      from: 0,
-      to: currentView.state.doc.length,
-      insert: content,
+      from,
+      to: current.length - end,
+      insert: content.slice(from, content.length - end),
     },
   });
 };
@@ -99,6 +399,89 @@ window.__codedocs_setOnChange = function (callback) {
   onChangeCallback = callback;
 };
 
+window.__codedocs_setOnCursorLine = function (callback) {
+  onCursorLineCallback = callback;
+};
+
+window.__codedocs_setOnScrollLine = function (callback) {
+  onScrollLineCallback = callback;
+};
+
+// Offset of the document's top within the scroller's content.
+function documentOffset(view) {
+  return view.documentTop - view.scrollDOM.getBoundingClientRect().top + view.scrollDOM.scrollTop;
+}
+
+// The line at the top of the viewport, with the fraction of it scrolled past,
+// e.g. 12.5 when half of line 12 is hidden.
+function topLine(view) {
+  const height = Math.max(0, view.scrollDOM.scrollTop - documentOffset(view));
+  const block = view.lineBlockAtHeight(height);
+  const line = view.state.doc.lineAt(block.from).number;
+  return line + Math.min(1, Math.max(0, (height - block.top) / Math.max(block.height, 1)));
+}
+
+// Scrolls (without moving the cursor) so the fractional line is at the top.
+window.__codedocs_scroll_to_line = function (position) {
+  if (!currentView) return;
+  const doc = currentView.state.doc;
+  const number = Math.min(Math.max(Math.floor(position), 1), doc.lines);
+  const block = currentView.lineBlockAt(doc.line(number).from);
+  const fraction = position >= 1 ? position - Math.floor(position) : 0;
+  currentView.scrollDOM.scrollTop = documentOffset(currentView) + block.top + fraction * block.height;
+};
+
+function applyDiagnostics() {
+  if (!currentView) return;
+  const length = currentView.state.doc.length;
+  const diagnostics = lastDiagnostics
+    .filter((d) => d.to <= length)
+    .map((d) => ({
+      from: d.from,
+      to: d.to,
+      severity: d.severity,
+      source: d.source,
+      message: d.message,
+      actions: d.fix
+        ? [
+            {
+              name: "Corregir",
+              apply(view) {
+                view.dispatch({ changes: { from: d.fix.from, to: d.fix.to, insert: d.fix.insert } });
+              },
+            },
+          ]
+        : [],
+    }));
+  currentView.dispatch(setDiagnostics(currentView.state, diagnostics));
+}
+
+// Lint results computed in Rust, offsets already in UTF-16 code units. They are
+// kept so an editor created later (switching to Raw) shows them too.
+window.__codedocs_set_diagnostics = function (diagnostics) {
+  lastDiagnostics = Array.isArray(diagnostics) ? diagnostics : [];
+  applyDiagnostics();
+};
+
+window.__codedocs_set_wiki_targets = function (targets) {
+  wikiTargets = Array.isArray(targets) ? targets : [];
+};
+
+// Without an editor yet (e.g. while switching to Raw), the jump waits for the next one.
+window.__codedocs_goto_line = function (lineNumber) {
+  if (!currentView) {
+    pendingGotoLine = lineNumber;
+    return;
+  }
+  const doc = currentView.state.doc;
+  const line = doc.line(Math.min(Math.max(lineNumber, 1), doc.lines));
+  currentView.dispatch({
+    selection: { anchor: line.from },
+    effects: EditorView.scrollIntoView(line.from, { y: "start" }),
+  });
+  currentView.focus();
+};
+
 window.__codedocs_focus = function () {
   if (currentView) currentView.focus();
 };
@@ -109,6 +492,8 @@ window.__codedocs_destroyEditor = function () {
     currentView = null;
   }
   onChangeCallback = null;
+  onCursorLineCallback = null;
+  onScrollLineCallback = null;
 };
 
 window.__codedocs_wrap_selection = function (wrapper) {
@@ -121,6 +506,16 @@ window.__codedocs_wrap_selection = function (wrapper) {
   currentView.focus();
 };
 
+window.__codedocs_insert_text = function (text) {
+  if (!currentView) return;
+  const { from, to } = currentView.state.selection.main;
+  currentView.dispatch({
+    changes: { from, to, insert: text },
+    selection: { anchor: from + text.length },
+  });
+  currentView.focus();
+};
+
 window.__codedocs_insert_link = function () {
   if (!currentView) return;
   const { from, to } = currentView.state.selection.main;
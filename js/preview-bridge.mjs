@@ -20,8 +20,43 @@ window.__codedocs_render_math = function () {
   });
 };
 
+// Diagrams come from the document: Mermaid runs in strict mode, and the SVG it
+// returns is still parsed inert and stripped of scripts and event handlers.
+var mermaidReady = false;
+
+function initMermaid() {
+  if (mermaidReady) return;
+  mermaid.initialize({ startOnLoad: false, securityLevel: "strict" });
+  mermaidReady = true;
+}
+
+function safeSvg(markup) {
+  var svg = new DOMParser().parseFromString(markup, "text/html").querySelector("svg");
+  if (!svg) return null;
+  svg.querySelectorAll("script, iframe, object, embed").forEach(function (node) {
+    node.remove();
+  });
+  [svg].concat(Array.from(svg.querySelectorAll("*"))).forEach(function (node) {
+    Array.from(node.attributes).forEach(function (attr) {
+      var name = attr.name.toLowerCase();
+      var value = attr.value.replace(/\s+/g, "").toLowerCase();
+      if (name.indexOf("on") === 0 || (/href$/.test(name) && value.indexOf("javascript:") === 0)) {
+        node.removeAttribute(attr.name);
+      }
+    });
+  });
+  return document.importNode(svg, true);
+}
+
+function showSource(el, code) {
+  var pre = document.createElement("pre");
+  pre.textContent = code;
+  el.replaceChildren(pre);
+}
+
 window.__codedocs_render_mermaid = function () {
   if (typeof mermaid === "undefined") return;
+  initMermaid();
 
   document.querySelectorAll(".mermaid-block").forEach(function (el) {
     var code = el.getAttribute("data-mermaid");
@@ -29,13 +64,15 @@ window.__codedocs_render_mermaid = function () {
     var id = "mermaid-" + Math.random().toString(36).substr(2, 9);
     try {
       mermaid.render(id, code).then(function (svg) {
-        el.innerHTML = svg.svg;
+        var node = safeSvg(svg.svg);
+        if (!node) return showSource(el, code);
+        el.replaceChildren(node);
         el.removeAttribute("data-mermaid");
       }).catch(function () {
-        el.innerHTML = "<pre>" + code + "</pre>";
+        showSource(el, code);
       });
     } catch (e) {
-      el.innerHTML = "<pre>" + code + "</pre>";
+      showSource(el, code);
     }
   });
 };
@@ -44,3 +81,31 @@ window.__codedocs_render_enhancements = function () {
   window.__codedocs_render_math();
   window.__codedocs_render_mermaid();
 };
+
+// Math as MathML and diagrams as SVG for exported documents, which cannot
+// count on KaTeX, its fonts or Mermaid being there. Anything that fails to
+// render comes back empty and the export keeps its source.
+window.__codedocs_prerender = async function (items) {
+  var results = [];
+  for (var i = 0; i < items.length; i++) {
+    var item = items[i];
+    var html = "";
+    try {
+      if (item.kind === "math" && typeof katex !== "undefined") {
+        html = katex.renderToString(item.tex, {
+          throwOnError: false,
+          displayMode: item.display,
+          output: "mathml",
+        });
+      } else if (item.kind === "diagram" && typeof mermaid !== "undefined") {
+        initMermaid();
+        var svg = safeSvg((await mermaid.render("mermaid-export-" + i, item.code)).svg);
+        html = svg ? svg.outerHTML : "";
+      }
+    } catch (e) {
+      html = "";
+    }
+    results.push(html);
+  }
+  return results;
+};
@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use codedocs_md::assets::{
+    asset_file_name, asset_link, asset_references, image_extension, AssetReport, AssetsConfig,
+};
+use codedocs_md::links::is_within;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use sha2::{Digest, Sha256};
+
+use crate::commands::project_markdown_files;
+use crate::site::normalized;
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> Option<Vec<u8>> {
+    let mut out = Cursor::new(Vec::new());
+    image.write_to(&mut out, format).ok()?;
+    Some(out.into_inner())
+}
+
+fn encode_jpeg(image: &DynamicImage, quality: u8) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let encoder = JpegEncoder::new_with_quality(&mut out, quality.clamp(1, 100));
+    image.to_rgb8().write_with_encoder(encoder).ok()?;
+    Some(out)
+}
+
+/// Scales the image down and re-encodes it as the project asks. Anything
+/// that cannot be decoded, and animated or vector formats, are kept as is.
+fn process(bytes: Vec<u8>, extension: String, config: &AssetsConfig) -> (Vec<u8>, String) {
+    if (config.max_width == 0 && config.jpeg_quality == 0) || matches!(extension.as_str(), "gif" | "svg") {
+        return (bytes, extension);
+    }
+    let Some(format) = ImageFormat::from_extension(&extension) else { return (bytes, extension) };
+    let Ok(mut image) = image::load_from_memory_with_format(&bytes, format) else { return (bytes, extension) };
+
+    let resized = config.max_width > 0 && image.width() > config.max_width;
+    if resized {
+        image = image.resize(config.max_width, u32::MAX, FilterType::Lanczos3);
+    }
+
+    if config.jpeg_quality > 0 && !image.color().has_alpha() {
+        if let Some(jpeg) = encode_jpeg(&image, config.jpeg_quality) {
+            if resized || jpeg.len() < bytes.len() {
+                return (jpeg, "jpg".to_string());
+            }
+        }
+    }
+    if resized {
+        if let Some(encoded) = encode(&image, format) {
+            return (encoded, extension);
+        }
+    }
+    (bytes, extension)
+}
+
+/// Saves an image pasted or dropped into `document_path` in the assets
+/// folder and returns the path to link it with. Saving the same image
+/// twice reuses the file written the first time.
+pub fn save_asset(document_path: &str, file_name: &str, bytes: Vec<u8>, config: &AssetsConfig) -> Result<String, String> {
+    let extension = image_extension(file_name)
+        .or_else(|| {
+            let format = image::guess_format(&bytes).ok()?;
+            format.extensions_str().first().map(|e| e.to_string())
+        })
+        .ok_or("El archivo no es una imagen compatible")?;
+    let (bytes, extension) = process(bytes, extension, config);
+
+    let hash: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    let name = asset_file_name(file_name, &hash, &extension);
+    let folder = Path::new(document_path)
+        .parent()
+        .ok_or("No se pudo determinar la carpeta del documento")?
+        .join(config.folder.trim());
+    let path = folder.join(&name);
+
+    if !path.exists() {
+        fs::create_dir_all(&folder).map_err(|e| format!("Error al crear la carpeta de recursos: {}", e))?;
+        fs::write(&path, bytes).map_err(|e| format!("Error al guardar la imagen: {}", e))?;
+    }
+    Ok(asset_link(&config.folder, &name))
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, out);
+        } else {
+            out.push(path);
+        }
+    }
+}
+
+/// Cross-references the files in every assets folder of the project (the
+/// configured folder next to each document) with what the documents link.
+pub fn asset_report(root: &str, config: &AssetsConfig) -> AssetReport {
+    let documents = project_markdown_files(root);
+    // Assets kept next to the documents themselves cannot be told apart
+    // from the rest of the project.
+    let folder = config.folder.trim().trim_matches(['/', '\\']);
+    let folders: BTreeSet<PathBuf> = documents
+        .iter()
+        .filter_map(|document| Path::new(document).parent())
+        .chain(std::iter::once(Path::new(root)))
+        .map(|dir| dir.join(folder))
+        .filter(|dir| !folder.is_empty() && folder != "." && dir.is_dir())
+        .collect();
+
+    let references: Vec<_> = documents
+        .iter()
+        .filter_map(|document| Some(asset_references(document, &fs::read_to_string(document).ok()?)))
+        .flatten()
+        .collect();
+    let referenced: BTreeSet<String> = references.iter().map(|r| normalized(&r.path)).collect();
+
+    let mut files = Vec::new();
+    for folder in &folders {
+        collect_files(folder, &mut files);
+    }
+    let mut unused: Vec<String> = files
+        .into_iter()
+        .map(|file| file.to_string_lossy().into_owned())
+        .filter(|file| !file.ends_with(".md") && !referenced.contains(&normalized(file)))
+        .collect();
+    unused.sort();
+    unused.dedup();
+
+    let missing = references.into_iter().filter(|r| !Path::new(&r.path).exists()).collect();
+    AssetReport { unused, missing }
+}
+
+/// Moves the given assets to the system trash. Only files inside the
+/// project are touched, wherever `..` or symbolic links in their paths lead.
+pub fn trash_assets(root: &str, paths: &[String]) -> Result<usize, String> {
+    let root = fs::canonicalize(root).map_err(|e| format!("Error al leer {}: {}", root, e))?;
+    let root = root.to_string_lossy();
+    let mut existing = Vec::new();
+    for path in paths {
+        let Ok(file) = fs::canonicalize(path) else { continue };
+        if !is_within(&file.to_string_lossy(), &root) {
+            return Err(format!("«{}» está fuera del proyecto", path));
+        }
+        if file.is_file() {
+            existing.push(file);
+        }
+    }
+    trash::delete_all(&existing).map_err(|e| format!("Error al mover a la papelera: {}", e))?;
+    Ok(existing.len())
+}
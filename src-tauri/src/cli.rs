@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::Path;
+
+use codedocs_md::lint::{lint_document, Severity};
+
+use crate::commands::{load_project_config, project_markdown_files};
+use crate::link_check::check_project;
+
+const USAGE: &str =
+    "Uso: codedocs check-links <carpeta>\n     codedocs lint <carpeta>\n     codedocs export-site <carpeta> <salida>\n     codedocs export-epub <carpeta> <salida>";
+
+/// Handles command-line subcommands. Returns the exit code when the arguments
+/// asked for one, or `None` to start the app normally.
+pub fn run(args: &[String]) -> Option<i32> {
+    match args.first().map(String::as_str) {
+        Some("check-links") => Some(check_links(args.get(1))),
+        Some("lint") => Some(lint(args.get(1))),
+        Some("export-site") => Some(export_site(args.get(1), args.get(2))),
+        Some("export-epub") => Some(export_epub(args.get(1), args.get(2))),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Some(0)
+        }
+        _ => None,
+    }
+}
+
+fn project_folder(folder: Option<&String>) -> Option<&String> {
+    let Some(folder) = folder else {
+        eprintln!("{}", USAGE);
+        return None;
+    };
+    if !Path::new(folder).is_dir() {
+        eprintln!("La ruta proporcionada no es una carpeta válida: {}", folder);
+        return None;
+    }
+    Some(folder)
+}
+
+fn check_links(folder: Option<&String>) -> i32 {
+    let Some(folder) = project_folder(folder) else { return 2 };
+
+    let diagnostics = check_project(folder);
+    for diagnostic in &diagnostics {
+        println!("{}:{}: {} ({})", diagnostic.file, diagnostic.line, diagnostic.reason, diagnostic.target);
+        if let Some(fix) = &diagnostic.fix {
+            println!("    sugerencia: {}", fix.replacement);
+        }
+    }
+
+    if diagnostics.is_empty() {
+        println!("Sin enlaces rotos");
+        0
+    } else {
+        println!("{} enlaces rotos", diagnostics.len());
+        1
+    }
+}
+
+fn lint(folder: Option<&String>) -> i32 {
+    let Some(folder) = project_folder(folder) else { return 2 };
+    let config = match load_project_config(folder.clone()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    let mut count = 0;
+    for path in project_markdown_files(folder) {
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        for diagnostic in lint_document(&content, &config.lint) {
+            let level = if diagnostic.severity == Severity::Error { "error" } else { "aviso" };
+            println!("{}:{}: {} [{}] {}", path, diagnostic.line, level, diagnostic.rule, diagnostic.message);
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        println!("Sin problemas de estilo");
+        0
+    } else {
+        println!("{} problemas de estilo", count);
+        1
+    }
+}
+
+fn export_site(folder: Option<&String>, output: Option<&String>) -> i32 {
+    let Some(folder) = project_folder(folder) else { return 2 };
+    let Some(output) = output else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let config = match load_project_config(folder.clone()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    match crate::site::export_site(folder, output, &config) {
+        Ok(summary) => {
+            println!("{} páginas y {} recursos exportados a {}", summary.pages, summary.assets, summary.output);
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn export_epub(folder: Option<&String>, output: Option<&String>) -> i32 {
+    let Some(folder) = project_folder(folder) else { return 2 };
+    let Some(output) = output else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let config = match load_project_config(folder.clone()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    match crate::epub::export_epub(folder, output, &config) {
+        Ok(summary) => {
+            println!("{} capítulos y {} imágenes exportados a {}", summary.chapters, summary.images, summary.output);
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
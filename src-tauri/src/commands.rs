@@ -2,9 +2,26 @@ use std::{fs, path::Path, sync::Mutex};
 
 use tauri_plugin_dialog::DialogExt;
 
+use notify::event::ModifyKind;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher, Event, EventKind};
 use tauri::Emitter;
 
+use codedocs_md::assets::AssetReport;
+use codedocs_md::check::LinkDiagnostic;
+use codedocs_md::edit::{FileEdits, TextEdit};
+use codedocs_md::config::{ProjectConfig, CONFIG_FILE};
+use codedocs_md::export::HtmlExportOptions;
+use codedocs_md::refactor::RefactorEdits;
+use codedocs_md::extract_headings;
+use codedocs_md::tasks::{extract_tasks, toggle_task, Task};
+use codedocs_md::wikilinks::shortest_wikilink;
+
+use crate::link_check::check_project;
+use crate::link_index::{project_root, Backlink, LinkGraph, LinkIndex, LINK_INDEX};
+use crate::refactor::{self, plan_heading_rename, plan_rename, RenamePlan, UndoOutcome};
+use crate::epub::EpubSummary;
+use crate::site::SiteSummary;
+
 static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
 
 #[tauri::command]
@@ -19,10 +36,10 @@ pub fn open_project_folder(app: tauri::AppHandle) -> Result<String, String> {
 
 #[derive(serde::Serialize, Debug, Clone)]
 pub struct FileEntry {
-    name: String,
-    path: String,
-    is_dir: bool,
-    children: Vec<FileEntry>,
+    pub(crate) name: String,
+    pub(crate) path: String,
+    pub(crate) is_dir: bool,
+    pub(crate) children: Vec<FileEntry>,
 }
 
 fn get_file_tree(base: &Path, current: &Path) -> Vec<FileEntry> {
@@ -94,6 +111,51 @@ pub fn list_markdown_files(folder_path: String) -> Result<Vec<FileEntry>, String
     Ok(tree)
 }
 
+fn collect_markdown_files(tree: &[FileEntry], out: &mut Vec<String>) {
+    for entry in tree {
+        if entry.is_dir {
+            collect_markdown_files(&entry.children, out);
+        } else {
+            out.push(entry.path.clone());
+        }
+    }
+}
+
+/// The tree the sidebar shows for the project at `folder_path`.
+pub(crate) fn project_tree(folder_path: &str) -> Vec<FileEntry> {
+    let base_path = Path::new(folder_path);
+    get_file_tree(base_path, base_path)
+}
+
+pub(crate) fn project_markdown_files(folder_path: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    collect_markdown_files(&project_tree(folder_path), &mut files);
+    files
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct WikiTarget {
+    link: String,
+    path: String,
+    headings: Vec<String>,
+}
+
+/// Every page a `[[wiki-link]]` can point at, with its shortest unique name and headings.
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_wiki_targets(folder_path: String) -> Result<Vec<WikiTarget>, String> {
+    let files = project_markdown_files(&folder_path);
+    Ok(files
+        .iter()
+        .map(|path| WikiTarget {
+            link: shortest_wikilink(path, &files),
+            path: path.clone(),
+            headings: fs::read_to_string(path)
+                .map(|content| extract_headings(&content).into_iter().map(|h| h.text).collect())
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
 #[tauri::command]
 pub fn read_file(path_str: String) -> Result<String, String> {
     let path = Path::new(&path_str);
@@ -106,6 +168,94 @@ pub fn save_file(path_str: String, content: String) -> Result<(), String> {
     fs::write(path, content).map_err(|e| format!("Error al guardar el archivo: {}", e))
 }
 
+/// Saves an image pasted or dropped into the document and returns the path
+/// to link it with.
+#[tauri::command(rename_all = "camelCase")]
+pub fn save_asset(document_path: String, file_name: String, bytes: Vec<u8>) -> Result<String, String> {
+    let config = load_project_config(project_root_for(&document_path))?;
+    crate::assets::save_asset(&document_path, &file_name, bytes, &config.assets)
+}
+
+/// Unused files in the assets folders and references to files that do not exist.
+#[tauri::command(rename_all = "camelCase")]
+pub fn asset_report(folder_path: String) -> Result<AssetReport, String> {
+    let config = load_project_config(folder_path.clone())?;
+    Ok(crate::assets::asset_report(&folder_path, &config.assets))
+}
+
+/// Moves unused assets to the system trash and returns how many were moved.
+#[tauri::command(rename_all = "camelCase")]
+pub fn trash_assets(folder_path: String, paths: Vec<String>) -> Result<usize, String> {
+    crate::assets::trash_assets(&folder_path, &paths)
+}
+
+/// Asks where to save an export of the document at `path`, suggesting its
+/// name with the export's extension next to it.
+fn pick_export_path(app: &tauri::AppHandle, path: &str, filter: &str, extension: &str) -> Result<String, String> {
+    let document = Path::new(path);
+    let stem = document.file_stem().unwrap_or_default().to_string_lossy();
+    let mut dialog = app
+        .dialog()
+        .file()
+        .add_filter(filter, &[extension])
+        .set_file_name(format!("{}.{}", stem, extension));
+    if let Some(folder) = document.parent() {
+        dialog = dialog.set_directory(folder);
+    }
+    match dialog.blocking_save_file() {
+        Some(output) => Ok(output.to_string()),
+        None => Err("Usuario cancelo la accion".to_string()),
+    }
+}
+
+/// Exports the document as a self-contained HTML page and returns where it was saved.
+#[tauri::command]
+pub fn export_html(app: tauri::AppHandle, path: String, options: HtmlExportOptions) -> Result<String, String> {
+    let output = pick_export_path(&app, &path, "HTML", "html")?;
+    let config = load_project_config(project_root_for(&path))?;
+    let html = crate::export::document_html(&path, &output, &config, &options)?;
+    fs::write(&output, html).map_err(|e| format!("Error al guardar la exportacion: {}", e))?;
+    Ok(output)
+}
+
+/// Exports the document as a PDF and returns where it was saved.
+#[tauri::command]
+pub fn export_pdf(app: tauri::AppHandle, path: String) -> Result<String, String> {
+    let output = pick_export_path(&app, &path, "PDF", "pdf")?;
+    let config = load_project_config(project_root_for(&path))?;
+    let pdf = crate::pdf::document_pdf(&path, &config)?;
+    fs::write(&output, pdf).map_err(|e| format!("Error al guardar la exportacion: {}", e))?;
+    Ok(output)
+}
+
+/// Exports the document as a Word file and returns where it was saved.
+#[tauri::command]
+pub fn export_docx(app: tauri::AppHandle, path: String, prerendered: Vec<String>) -> Result<String, String> {
+    let output = pick_export_path(&app, &path, "Word", "docx")?;
+    let docx = crate::docx::document_docx(&path, &prerendered)?;
+    fs::write(&output, docx).map_err(|e| format!("Error al guardar la exportacion: {}", e))?;
+    Ok(output)
+}
+
+/// Exports the project as a static website into a folder the user picks.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_site(app: tauri::AppHandle, folder_path: String) -> Result<SiteSummary, String> {
+    let output = match app.dialog().file().set_directory(&folder_path).blocking_pick_folder() {
+        Some(output) => output.to_string(),
+        None => return Err("Usuario cancelo la accion".to_string()),
+    };
+    let config = load_project_config(folder_path.clone())?;
+    crate::site::export_site(&folder_path, &output, &config)
+}
+
+/// Exports the project as an EPUB book and reports what went into it.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_epub(app: tauri::AppHandle, folder_path: String) -> Result<EpubSummary, String> {
+    let output = pick_export_path(&app, &folder_path, "EPUB", "epub")?;
+    let config = load_project_config(folder_path.clone())?;
+    crate::epub::export_epub(&folder_path, &output, &config)
+}
+
 #[tauri::command]
 pub fn generate_toc(content: String) -> String {
     crate::utils::md::generate_toc(&content)
@@ -117,28 +267,137 @@ pub fn delete_file(path_str: String) -> Result<(), String> {
     fs::remove_file(path).map_err(|e| format!("Error al eliminar el archivo: {}", e))
 }
 
-#[tauri::command(rename_all = "camelCase")]
-pub fn rename_file(old_path: String, new_name: String) -> Result<(), String> {
-    let old_path_buf = Path::new(&old_path);
-    let parent = old_path_buf
+fn renamed_path(old_path: &str, new_name: &str) -> Result<String, String> {
+    let parent = Path::new(old_path)
         .parent()
         .ok_or("No se pudo determinar la carpeta padre")?;
-    let new_path = parent.join(new_name);
+    Ok(parent.join(new_name).to_string_lossy().into_owned())
+}
+
+/// The open project, or the file's own folder when no project is being watched.
+fn project_root_for(path: &str) -> String {
+    project_root().unwrap_or_else(|| {
+        Path::new(path)
+            .parent()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    })
+}
+
+/// What renaming (or moving, when `new_name` has folders) a document would change.
+#[tauri::command(rename_all = "camelCase")]
+pub fn preview_rename(old_path: String, new_name: String) -> Result<RenamePlan, String> {
+    let new_path = renamed_path(&old_path, &new_name)?;
+    plan_rename(&project_root_for(&old_path), &old_path, &new_path)
+}
+
+/// Applies a previewed rename and returns the label to offer for undo.
+#[tauri::command]
+pub fn apply_rename(plan: RenamePlan) -> Result<String, String> {
+    let label = format!(
+        "Renombrar {}",
+        Path::new(&plan.old_path).file_name().unwrap_or_default().to_string_lossy()
+    );
+    refactor::apply(&label, &plan.edits, &[(plan.old_path, plan.new_path)])?;
+    Ok(label)
+}
 
-    fs::rename(old_path_buf, new_path).map_err(|e| format!("Error al renombrar el archivo: {}", e))
+#[tauri::command(rename_all = "camelCase")]
+pub fn rename_file(old_path: String, new_name: String) -> Result<(), String> {
+    apply_rename(preview_rename(old_path, new_name)?).map(|_| ())
+}
+
+/// Edits that renaming the heading on `line` makes across the project.
+#[tauri::command(rename_all = "camelCase")]
+pub fn preview_heading_rename(path: String, line: usize, new_text: String) -> Result<RefactorEdits, String> {
+    plan_heading_rename(&project_root_for(&path), &path, line, &new_text)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn apply_heading_rename(edits: Vec<FileEdits>, new_text: String) -> Result<String, String> {
+    let label = format!("Renombrar encabezado «{}»", new_text.trim());
+    refactor::apply(&label, &edits, &[])?;
+    Ok(label)
 }
 
 #[tauri::command]
-pub fn create_file(folder_path: String, name: String) -> Result<String, String> {
+pub fn undo_refactor() -> Result<UndoOutcome, String> {
+    refactor::undo_last()
+}
+
+#[tauri::command]
+pub fn create_file(folder_path: String, name: String, title: Option<String>) -> Result<String, String> {
     let path = Path::new(&folder_path).join(name);
     if path.exists() {
         return Err("El archivo ya existe".to_string());
     }
-    fs::write(&path, "# Nuevo Archivo\n")
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Error al crear la carpeta: {}", e))?;
+    }
+    let title = title.unwrap_or_else(|| "Nuevo Archivo".to_string());
+    fs::write(&path, format!("# {}\n", title))
         .map_err(|e| format!("Error al crear el archivo: {}", e))?;
     Ok(path.to_string_lossy().into_owned())
 }
 
+#[tauri::command(rename_all = "camelCase")]
+pub fn load_project_config(folder_path: String) -> Result<ProjectConfig, String> {
+    let path = Path::new(&folder_path).join(CONFIG_FILE);
+    if !path.exists() {
+        return Ok(ProjectConfig::default());
+    }
+    let source = fs::read_to_string(&path)
+        .map_err(|e| format!("Error al leer la configuracion del proyecto: {}", e))?;
+    ProjectConfig::from_toml(&source)
+        .map_err(|e| format!("Configuracion del proyecto invalida: {}", e))
+}
+
+/// Whether the user trusts the raw HTML of the project at `folder_path`.
+#[tauri::command(rename_all = "camelCase")]
+pub fn is_project_trusted(app: tauri::AppHandle, folder_path: String) -> bool {
+    crate::trust::is_trusted(&app, &folder_path)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_project_trusted(app: tauri::AppHandle, folder_path: String, trusted: bool) -> Result<(), String> {
+    crate::trust::set_trusted(&app, &folder_path, trusted)
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+/// Edits only change the edited file's links; anything that adds, removes or
+/// renames files can change how other documents resolve, so the index is rebuilt.
+/// A folder that is created, removed, renamed or moved has only its own
+/// documents, and those linking into it, re-read. Returns the folders whose
+/// documents changed.
+fn update_link_index(event: &Event) -> Vec<String> {
+    let Ok(mut lock) = LINK_INDEX.lock() else { return Vec::new() };
+    let Some(index) = lock.as_mut() else { return Vec::new() };
+
+    match event.kind {
+        EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any) => {
+            for path in event.paths.iter().filter(|path| is_markdown(path)) {
+                index.update_file(path);
+            }
+            Vec::new()
+        }
+        EventKind::Modify(ModifyKind::Metadata(_)) => Vec::new(),
+        _ if event.paths.iter().any(|path| is_markdown(path)) => {
+            let root = index.root().to_string();
+            *index = LinkIndex::build(&root);
+            Vec::new()
+        }
+        _ => event
+            .paths
+            .iter()
+            .filter(|path| index.update_folder(path))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+    }
+}
+
 #[tauri::command]
 pub fn watch_folder(app: tauri::AppHandle, folder_path: String) -> Result<(), String> {
     let mut watcher_lock = WATCHER.lock().map_err(|e| format!("Error al bloquear watcher: {}", e))?;
@@ -152,11 +411,12 @@ pub fn watch_folder(app: tauri::AppHandle, folder_path: String) -> Result<(), St
         move |res: Result<Event, notify::Error>| {
             if let Ok(event) = res {
                 if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
-                    let paths: Vec<String> = event.paths
+                    let mut paths: Vec<String> = event.paths
                         .iter()
-                        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+                        .filter(|p| is_markdown(p))
                         .map(|p| p.to_string_lossy().into_owned())
                         .collect();
+                    paths.extend(update_link_index(&event));
 
                     if !paths.is_empty() {
                         let _ = app_handle.emit("fs-change", &paths);
@@ -170,7 +430,14 @@ pub fn watch_folder(app: tauri::AppHandle, folder_path: String) -> Result<(), St
     watcher.watch(Path::new(&folder_path), RecursiveMode::Recursive)
         .map_err(|e| format!("Error al observar carpeta: {}", e))?;
 
+    if let Ok(mut index) = LINK_INDEX.lock() {
+        *index = Some(LinkIndex::build(&folder_path));
+    }
+
     *watcher_lock = Some(watcher);
+
+    // The preview loads the project's images, and only this project's, through its own protocol.
+    crate::project_protocol::set_root(Some(&folder_path));
     Ok(())
 }
 
@@ -178,5 +445,66 @@ pub fn watch_folder(app: tauri::AppHandle, folder_path: String) -> Result<(), St
 pub fn stop_watching() -> Result<(), String> {
     let mut watcher_lock = WATCHER.lock().map_err(|e| format!("Error al bloquear watcher: {}", e))?;
     *watcher_lock = None;
+    if let Ok(mut index) = LINK_INDEX.lock() {
+        *index = None;
+    }
+    crate::project_protocol::set_root(None);
     Ok(())
 }
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_backlinks(path: String) -> Result<Vec<Backlink>, String> {
+    let lock = LINK_INDEX.lock().map_err(|e| format!("Error al bloquear el indice de enlaces: {}", e))?;
+    let index = lock.as_ref().ok_or("El indice de enlaces no esta disponible")?;
+    Ok(index.backlinks(&path))
+}
+
+#[tauri::command]
+pub fn get_link_graph() -> Result<LinkGraph, String> {
+    let lock = LINK_INDEX.lock().map_err(|e| format!("Error al bloquear el indice de enlaces: {}", e))?;
+    let index = lock.as_ref().ok_or("El indice de enlaces no esta disponible")?;
+    Ok(index.graph())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn check_links(folder_path: String) -> Result<Vec<LinkDiagnostic>, String> {
+    if !Path::new(&folder_path).is_dir() {
+        return Err("La ruta proporcionada no es una carpeta válida".to_string());
+    }
+    Ok(check_project(&folder_path))
+}
+
+/// Open tasks of every document in the project, file by file.
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_tasks(folder_path: String) -> Result<Vec<Task>, String> {
+    if !Path::new(&folder_path).is_dir() {
+        return Err("La ruta proporcionada no es una carpeta válida".to_string());
+    }
+    let mut tasks = Vec::new();
+    for path in project_markdown_files(&folder_path) {
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        tasks.extend(extract_tasks(&content).into_iter().filter(|task| !task.checked).map(|task| Task {
+            file: path.clone(),
+            ..task
+        }));
+    }
+    Ok(tasks)
+}
+
+#[tauri::command]
+pub fn toggle_task_in_file(path: String, line: usize) -> Result<(), String> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("Error al leer el archivo: {}", e))?;
+    let toggled = toggle_task(&content, line)
+        .and_then(|edit| edit.apply(&content))
+        .ok_or("No hay ninguna tarea en esa línea; vuelve a cargar la lista de tareas")?;
+    fs::write(&path, toggled).map_err(|e| format!("Error al guardar el archivo: {}", e))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn apply_link_fix(path: String, fix: TextEdit) -> Result<(), String> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("Error al leer el archivo: {}", e))?;
+    let fixed = fix
+        .apply(&content)
+        .ok_or("El archivo cambio desde la ultima comprobacion; vuelve a comprobar los enlaces")?;
+    fs::write(&path, fixed).map_err(|e| format!("Error al guardar el archivo: {}", e))
+}
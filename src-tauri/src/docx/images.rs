@@ -0,0 +1,106 @@
+//! Local images copied into the package, and the drawings that show them.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+
+use codedocs_md::links::{is_relative, percent_decode, resolve_path};
+use image::{ImageFormat, ImageReader};
+
+use super::escape;
+use super::writer::TEXT_WIDTH;
+
+/// Width of the text between the page margins, in EMU.
+pub const TEXT_WIDTH_EMU: u64 = TEXT_WIDTH as u64 * 635;
+
+/// EMU per pixel at 96 pixels per inch.
+const EMU_PER_PIXEL: u64 = 9525;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaFile {
+    /// Name under `word/media/`, which is also its relationship id's suffix.
+    pub name: String,
+    pub bytes: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct Media {
+    pub files: Vec<MediaFile>,
+    by_path: HashMap<String, usize>,
+    drawings: usize,
+}
+
+/// The image at `path` in a format Word shows: PNG, JPEG, GIF and BMP as
+/// they are, anything else `image` decodes as PNG. SVG is left out.
+fn load(path: &str) -> Option<(Vec<u8>, &'static str, u32, u32)> {
+    let bytes = fs::read(path).ok()?;
+    let reader = ImageReader::new(Cursor::new(&bytes)).with_guessed_format().ok()?;
+    let extension = match reader.format()? {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpeg",
+        ImageFormat::Gif => "gif",
+        ImageFormat::Bmp => "bmp",
+        _ => {
+            let image = reader.decode().ok()?;
+            let mut png = Vec::new();
+            image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+            return Some((png, "png", image.width(), image.height()));
+        }
+    };
+    let (width, height) = reader.into_dimensions().ok()?;
+    Some((bytes, extension, width, height))
+}
+
+impl Media {
+    /// The image a document in `document_dir` refers to as `src`, added once
+    /// however many times it appears. `None` if it is not a local image.
+    pub fn add(&mut self, document_dir: &str, src: &str) -> Option<usize> {
+        if !is_relative(src) {
+            return None;
+        }
+        let path = resolve_path(document_dir, &percent_decode(src.split(['?', '#']).next().unwrap_or(src)));
+        if let Some(index) = self.by_path.get(&path) {
+            return Some(*index);
+        }
+        let (bytes, extension, width, height) = load(&path)?;
+        let index = self.files.len();
+        self.files.push(MediaFile { name: format!("image{}.{}", index + 1, extension), bytes, width, height });
+        self.by_path.insert(path, index);
+        Some(index)
+    }
+
+    /// An inline drawing of the image at `index`, at its size at 96 pixels
+    /// per inch or shrunk to `max_width` EMU.
+    pub fn drawing(&mut self, index: usize, alt: &str, max_width: u64) -> String {
+        let file = &self.files[index];
+        let (mut width, mut height) = (u64::from(file.width) * EMU_PER_PIXEL, u64::from(file.height) * EMU_PER_PIXEL);
+        if width > max_width && width > 0 {
+            height = height * max_width / width;
+            width = max_width;
+        }
+        self.drawings += 1;
+        let id = self.drawings;
+        format!(
+            concat!(
+                "<w:r><w:drawing><wp:inline distT=\"0\" distB=\"0\" distL=\"0\" distR=\"0\">",
+                "<wp:extent cx=\"{w}\" cy=\"{h}\"/>",
+                "<wp:docPr id=\"{id}\" name=\"Imagen {id}\" descr=\"{alt}\"/>",
+                "<wp:cNvGraphicFramePr><a:graphicFrameLocks noChangeAspect=\"1\"/></wp:cNvGraphicFramePr>",
+                "<a:graphic><a:graphicData uri=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">",
+                "<pic:pic><pic:nvPicPr><pic:cNvPr id=\"{id}\" name=\"{name}\"/><pic:cNvPicPr/></pic:nvPicPr>",
+                "<pic:blipFill><a:blip r:embed=\"rMedia{index}\"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill>",
+                "<pic:spPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"{w}\" cy=\"{h}\"/></a:xfrm>",
+                "<a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></pic:spPr></pic:pic>",
+                "</a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"
+            ),
+            w = width,
+            h = height,
+            id = id,
+            alt = escape(alt),
+            name = escape(&file.name),
+            index = index + 1,
+        )
+    }
+}
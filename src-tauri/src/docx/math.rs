@@ -0,0 +1,191 @@
+//! Formulas as Office Math (OMML), from the MathML KaTeX renders them to.
+
+use roxmltree::{Document, Node};
+
+use super::escape;
+
+/// Operators drawn as n-ary symbols, with their limits above and below.
+const NARY: &[&str] = &["∑", "∏", "∐", "∫", "∬", "∭", "∮", "⋃", "⋂", "⋁", "⋀", "⨁", "⨂", "⨀"];
+
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(Node::is_element)
+}
+
+fn run(text: &str, properties: &str) -> String {
+    format!("<m:r>{}<m:t xml:space=\"preserve\">{}</m:t></m:r>", properties, escape(text))
+}
+
+/// The limits of a sum, product or integral: its symbol, where the limits
+/// go and the limits below and above.
+type Limits<'a, 'input> = (String, &'static str, Option<Node<'a, 'input>>, Option<Node<'a, 'input>>);
+
+fn nary_limits<'a, 'input>(node: Node<'a, 'input>) -> Option<Limits<'a, 'input>> {
+    let mut args = elements(node);
+    let base = args.next().filter(|base| base.tag_name().name() == "mo")?;
+    let symbol = base.text().unwrap_or_default().trim().to_string();
+    if !NARY.contains(&symbol.as_str()) {
+        return None;
+    }
+    let (location, sub, sup) = match node.tag_name().name() {
+        "msub" => ("subSup", args.next(), None),
+        "msup" => ("subSup", None, args.next()),
+        "msubsup" => ("subSup", args.next(), args.next()),
+        "munder" => ("undOvr", args.next(), None),
+        "mover" => ("undOvr", None, args.next()),
+        "munderover" => ("undOvr", args.next(), args.next()),
+        _ => return None,
+    };
+    Some((symbol, location, sub, sup))
+}
+
+/// A sequence of elements. A sum, product or integral takes what follows it
+/// as its operand.
+fn children(node: Node, out: &mut String) {
+    let nodes: Vec<Node> = elements(node).collect();
+    for (ix, child) in nodes.iter().enumerate() {
+        let Some((symbol, location, sub, sup)) = nary_limits(*child) else {
+            convert(*child, out);
+            continue;
+        };
+        out.push_str(&format!("<m:nary><m:naryPr><m:chr m:val=\"{}\"/><m:limLoc m:val=\"{}\"/>", escape(&symbol), location));
+        if sub.is_none() {
+            out.push_str("<m:subHide m:val=\"1\"/>");
+        }
+        if sup.is_none() {
+            out.push_str("<m:supHide m:val=\"1\"/>");
+        }
+        out.push_str("</m:naryPr>");
+        argument(sub, "sub", out);
+        argument(sup, "sup", out);
+        out.push_str("<m:e>");
+        for operand in &nodes[ix + 1..] {
+            convert(*operand, out);
+        }
+        out.push_str("</m:e></m:nary>");
+        return;
+    }
+}
+
+/// A child as an OMML argument such as `m:e` or `m:num`.
+fn argument(node: Option<Node>, tag: &str, out: &mut String) {
+    out.push_str(&format!("<m:{}>", tag));
+    if let Some(node) = node {
+        convert(node, out);
+    }
+    out.push_str(&format!("</m:{}>", tag));
+}
+
+fn convert(node: Node, out: &mut String) {
+    let mut args = elements(node);
+    match node.tag_name().name() {
+        "mi" => {
+            let text = node.text().unwrap_or_default();
+            // Single letters are italic; names like `sin` and `mathrm` text are not.
+            let upright = node.attribute("mathvariant") == Some("normal") || text.chars().count() > 1;
+            out.push_str(&run(text, if upright { "<m:rPr><m:sty m:val=\"p\"/></m:rPr>" } else { "" }));
+        }
+        "mn" | "mo" => out.push_str(&run(node.text().unwrap_or_default(), "")),
+        "mtext" | "ms" => out.push_str(&run(node.text().unwrap_or_default(), "<m:rPr><m:nor/></m:rPr>")),
+        "mspace" => out.push_str(&run(" ", "")),
+        "annotation" | "annotation-xml" | "mphantom" => {}
+        "mfrac" => {
+            // Binomials are fractions without a bar, `linethickness="0px"`.
+            let no_bar = node
+                .attribute("linethickness")
+                .and_then(|t| t.trim_end_matches(|c: char| c.is_ascii_alphabetic()).parse::<f32>().ok())
+                .is_some_and(|t| t == 0.0);
+            out.push_str("<m:f>");
+            if no_bar {
+                out.push_str("<m:fPr><m:type m:val=\"noBar\"/></m:fPr>");
+            }
+            argument(args.next(), "num", out);
+            argument(args.next(), "den", out);
+            out.push_str("</m:f>");
+        }
+        "msup" | "msub" | "msubsup" => {
+            let base = args.next();
+            let (tag, parts): (&str, &[&str]) = match node.tag_name().name() {
+                "msup" => ("sSup", &["sup"]),
+                "msub" => ("sSub", &["sub"]),
+                _ => ("sSubSup", &["sub", "sup"]),
+            };
+            out.push_str(&format!("<m:{}>", tag));
+            argument(base, "e", out);
+            for part in parts {
+                argument(args.next(), part, out);
+            }
+            out.push_str(&format!("</m:{}>", tag));
+        }
+        "msqrt" => {
+            out.push_str("<m:rad><m:radPr><m:degHide m:val=\"1\"/></m:radPr><m:deg/><m:e>");
+            children(node, out);
+            out.push_str("</m:e></m:rad>");
+        }
+        "mroot" => {
+            let (base, index) = (args.next(), args.next());
+            out.push_str("<m:rad>");
+            argument(index, "deg", out);
+            argument(base, "e", out);
+            out.push_str("</m:rad>");
+        }
+        "mover" | "munder" | "munderover" => {
+            let base = args.next();
+            let tag = node.tag_name().name();
+            if tag == "mover" && node.attribute("accent") == Some("true") {
+                let accent = args.next().and_then(|accent| accent.text()).unwrap_or("^").trim().to_string();
+                out.push_str(&format!("<m:acc><m:accPr><m:chr m:val=\"{}\"/></m:accPr>", escape(&accent)));
+                argument(base, "e", out);
+                out.push_str("</m:acc>");
+                return;
+            }
+            let (under, over) = match tag {
+                "mover" => (None, args.next()),
+                "munder" => (args.next(), None),
+                _ => (args.next(), args.next()),
+            };
+            if over.is_some() {
+                out.push_str("<m:limUpp><m:e>");
+            }
+            if under.is_some() {
+                out.push_str("<m:limLow>");
+                argument(base, "e", out);
+                argument(under, "lim", out);
+                out.push_str("</m:limLow>");
+            } else if let Some(base) = base {
+                convert(base, out);
+            }
+            if over.is_some() {
+                out.push_str("</m:e>");
+                argument(over, "lim", out);
+                out.push_str("</m:limUpp>");
+            }
+        }
+        "mtable" => {
+            out.push_str("<m:m>");
+            for row in elements(node) {
+                out.push_str("<m:mr>");
+                for cell in elements(row) {
+                    out.push_str("<m:e>");
+                    children(cell, out);
+                    out.push_str("</m:e>");
+                }
+                out.push_str("</m:mr>");
+            }
+            out.push_str("</m:m>");
+        }
+        // mrow, mstyle, semantics, mpadded, menclose and anything else: their contents.
+        _ => children(node, out),
+    }
+}
+
+/// The `<math>` element of KaTeX's output as an `m:oMath` element, or `None`
+/// if there is none to convert.
+pub fn omml(rendered: &str) -> Option<String> {
+    let start = rendered.find("<math")?;
+    let end = rendered.rfind("</math>")? + "</math>".len();
+    let document = Document::parse(rendered.get(start..end)?).ok()?;
+    let mut out = String::from("<m:oMath>");
+    children(document.root_element(), &mut out);
+    out.push_str("</m:oMath>");
+    Some(out)
+}
@@ -0,0 +1,173 @@
+//! Word export: the document's markdown events written as WordprocessingML,
+//! with Word's own heading, list, quote and footnote styles so the result
+//! can be edited like any other Word document.
+
+mod images;
+mod math;
+mod package;
+mod writer;
+
+use std::collections::HashMap;
+use std::fs;
+
+use codedocs_md::export::{document_title, enhancements, Enhancement};
+use codedocs_md::frontmatter::parse_front_matter;
+use codedocs_md::links::parent_dir;
+use codedocs_md::{extract_headings, render_markdown, RenderOptions};
+
+/// Text for XML content and attributes. Control characters XML cannot
+/// hold are dropped.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The document at `path` as a `.docx` file. `prerendered` is what the
+/// webview rendered for the document's math and diagrams, in the order
+/// [`enhancements`] lists them; formulas become Word equations from it and
+/// keep their TeX source without it.
+pub fn document_docx(path: &str, prerendered: &[String]) -> Result<Vec<u8>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Error al leer el archivo: {}", e))?;
+    let math: HashMap<(String, bool), String> = enhancements(&render_markdown(&content, &RenderOptions::default()))
+        .into_iter()
+        .zip(prerendered)
+        .filter_map(|(enhancement, rendered)| match enhancement {
+            Enhancement::Math { tex, display } => Some(((tex.trim().to_string(), display), rendered.clone())),
+            Enhancement::Diagram { .. } => None,
+        })
+        .collect();
+    let slugs: Vec<String> = extract_headings(&content).into_iter().map(|heading| heading.slug).collect();
+
+    let body = writer::write_body(&content, &math, &slugs, parent_dir(path));
+    let author = parse_front_matter(&content)
+        .and_then(|front_matter| front_matter.get("author").or_else(|| front_matter.get("authors")).map(|a| a.display()))
+        .unwrap_or_default();
+    package::package(&body, &document_title(&content, path), &author)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{Cursor, Read};
+
+    use super::math::omml;
+    use super::writer::{write_body, Numbering};
+    use super::{document_docx, package};
+    use codedocs_md::extract_headings;
+
+    const FRACTION: &str = "<span class=\"katex\"><math xmlns=\"http://www.w3.org/1998/Math/MathML\"><semantics>\
+                            <mrow><mfrac><mi>a</mi><mn>2</mn></mfrac></mrow>\
+                            <annotation encoding=\"application/x-tex\">\\frac{a}{2}</annotation>\
+                            </semantics></math></span>";
+
+    fn body(content: &str, math: &HashMap<(String, bool), String>) -> super::writer::Body {
+        let slugs: Vec<String> = extract_headings(content).into_iter().map(|heading| heading.slug).collect();
+        write_body(content, math, &slugs, "")
+    }
+
+    fn entries(bytes: &[u8]) -> HashMap<String, String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        (0..archive.len())
+            .map(|ix| {
+                let mut file = archive.by_index(ix).unwrap();
+                let mut text = String::new();
+                file.read_to_string(&mut text).unwrap();
+                (file.name().to_string(), text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn links_point_at_relationships_and_heading_bookmarks() {
+        let content = "# Inicio\n\nVer [la web](https://x.org), [el final](#final) y [[otra]].\n\n# Final\n";
+        let body = body(content, &HashMap::new());
+        assert_eq!(body.links, vec!["https://x.org"]);
+        assert!(body.document.contains("<w:hyperlink r:id=\"rLink1\""));
+        assert!(body.document.contains("w:name=\"_Heading2\"/><w:bookmarkEnd"));
+        assert!(body.document.contains("<w:hyperlink w:anchor=\"_Heading2\""));
+        assert_eq!(body.document.matches("<w:hyperlink").count(), 2);
+        assert!(body.document.contains("<w:pStyle w:val=\"Heading1\"/>"));
+    }
+
+    #[test]
+    fn lists_and_footnotes_are_numbered() {
+        let content = "- uno\n  1. dos\n\n3. tres\n\nNota[^a] y otra[^a].\n\n[^a]: La primera.\n";
+        let body = body(content, &HashMap::new());
+        assert_eq!(
+            body.numbering,
+            vec![
+                Numbering { ordered: false, level: 0, start: 1 },
+                Numbering { ordered: true, level: 1, start: 1 },
+                Numbering { ordered: true, level: 0, start: 3 },
+            ]
+        );
+        assert!(body.document.contains("<w:footnoteReference w:id=\"1\"/>"));
+        // A note referenced twice is one footnote.
+        assert_eq!(body.document.matches("<w:footnoteReference w:id=\"1\"/>").count(), 2);
+        assert_eq!(body.footnotes.matches("<w:footnote ").count(), 1);
+        assert!(body.footnotes.contains("La primera."));
+    }
+
+    #[test]
+    fn formulas_become_equations_or_keep_their_source() {
+        let math = HashMap::from([(("\\frac{a}{2}".to_string(), true), FRACTION.to_string())]);
+        let rendered = body("$$\\frac{a}{2}$$\n\n$x^2$\n", &math);
+        assert!(rendered.document.contains("<m:oMathPara><m:oMath><m:f>"));
+        assert!(rendered.document.contains("<w:t xml:space=\"preserve\">x^2</w:t>"));
+
+        let source = body("$$\\frac{a}{2}$$\n", &HashMap::new());
+        assert!(source.document.contains("<w:pStyle w:val=\"SourceCode\"/>"));
+        assert!(!source.document.contains("<m:oMath>"));
+        assert_eq!(omml("<span>sin fórmula</span>"), None);
+    }
+
+    #[test]
+    fn the_package_holds_well_formed_parts() {
+        let content = "# Título\n\n| a | b |\n|---|--:|\n| 1 & 2 | <x> |\n\n[web](https://x.org/?a=1&b=2)\n";
+        let bytes = package::package(&body(content, &HashMap::new()), "Guía & notas", "Ana").unwrap();
+        let entries = entries(&bytes);
+        for part in [
+            "[Content_Types].xml",
+            "_rels/.rels",
+            "docProps/core.xml",
+            "word/document.xml",
+            "word/_rels/document.xml.rels",
+            "word/styles.xml",
+            "word/numbering.xml",
+            "word/footnotes.xml",
+            "word/settings.xml",
+        ] {
+            let xml = entries.get(part).unwrap_or_else(|| panic!("falta {}", part));
+            assert!(roxmltree::Document::parse(xml).is_ok(), "{} no es XML válido", part);
+        }
+        assert!(entries["word/document.xml"].contains("<w:tbl>"));
+        assert!(entries["word/_rels/document.xml.rels"].contains("Target=\"https://x.org/?a=1&amp;b=2\""));
+        assert!(entries["docProps/core.xml"].contains("<dc:title>Guía &amp; notas</dc:title>"));
+    }
+
+    #[test]
+    fn documents_take_their_title_and_author_from_the_front_matter() {
+        let dir = std::env::temp_dir().join(format!("codedocs-docx-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("guia.md");
+        std::fs::write(&path, "---\ntitle: Manual\nauthor: Ana\n---\n\n# Uso\n\n$$\\frac{a}{2}$$\n").unwrap();
+
+        let bytes = document_docx(&path.to_string_lossy(), &[FRACTION.to_string()]).unwrap();
+        let entries = entries(&bytes);
+        assert!(entries["docProps/core.xml"].contains("<dc:title>Manual</dc:title><dc:creator>Ana</dc:creator>"));
+        assert!(entries["word/document.xml"].contains("<m:oMathPara>"));
+        assert!(document_docx(&dir.join("falta.md").to_string_lossy(), &[]).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+//! The parts of a `.docx` package and the zip file that holds them.
+
+use std::io::{Cursor, Write};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::escape;
+use super::writer::{Body, Numbering};
+
+const W: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const R: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const RELATIONSHIPS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+
+const STYLES: &str = concat!(
+    "<w:docDefaults><w:rPrDefault><w:rPr>",
+    "<w:rFonts w:ascii=\"Calibri\" w:hAnsi=\"Calibri\" w:eastAsia=\"Calibri\" w:cs=\"Calibri\"/>",
+    "<w:color w:val=\"2F2F2F\"/><w:sz w:val=\"22\"/><w:szCs w:val=\"22\"/><w:lang w:val=\"es-ES\"/>",
+    "</w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after=\"160\" w:line=\"300\" w:lineRule=\"auto\"/>",
+    "</w:pPr></w:pPrDefault></w:docDefaults>",
+    "<w:style w:type=\"paragraph\" w:default=\"1\" w:styleId=\"Normal\"><w:name w:val=\"Normal\"/><w:qFormat/></w:style>",
+    "<w:style w:type=\"character\" w:default=\"1\" w:styleId=\"DefaultParagraphFont\">",
+    "<w:name w:val=\"Default Paragraph Font\"/><w:uiPriority w:val=\"1\"/><w:semiHidden/></w:style>",
+    "<w:style w:type=\"table\" w:default=\"1\" w:styleId=\"TableNormal\"><w:name w:val=\"Normal Table\"/>",
+    "<w:semiHidden/><w:tblPr><w:tblInd w:w=\"0\" w:type=\"dxa\"/><w:tblCellMar>",
+    "<w:top w:w=\"0\" w:type=\"dxa\"/><w:left w:w=\"108\" w:type=\"dxa\"/>",
+    "<w:bottom w:w=\"0\" w:type=\"dxa\"/><w:right w:w=\"108\" w:type=\"dxa\"/>",
+    "</w:tblCellMar></w:tblPr></w:style>",
+    "<w:style w:type=\"numbering\" w:default=\"1\" w:styleId=\"NoList\"><w:name w:val=\"No List\"/><w:semiHidden/></w:style>",
+);
+
+/// Font size in half points of each heading level.
+const HEADING_SIZES: [u32; 6] = [40, 32, 28, 24, 22, 22];
+
+const OTHER_STYLES: &str = concat!(
+    "<w:style w:type=\"paragraph\" w:styleId=\"ListParagraph\"><w:name w:val=\"List Paragraph\"/>",
+    "<w:basedOn w:val=\"Normal\"/><w:qFormat/><w:pPr><w:spacing w:after=\"60\"/><w:contextualSpacing/></w:pPr></w:style>",
+    "<w:style w:type=\"paragraph\" w:styleId=\"Quote\"><w:name w:val=\"Quote\"/><w:basedOn w:val=\"Normal\"/>",
+    "<w:next w:val=\"Normal\"/><w:qFormat/><w:pPr><w:pBdr><w:left w:val=\"single\" w:sz=\"18\" w:space=\"8\" ",
+    "w:color=\"FFB74D\"/></w:pBdr><w:ind w:left=\"720\"/></w:pPr><w:rPr><w:color w:val=\"5F6060\"/></w:rPr></w:style>",
+    "<w:style w:type=\"paragraph\" w:customStyle=\"1\" w:styleId=\"SourceCode\"><w:name w:val=\"Source Code\"/>",
+    "<w:basedOn w:val=\"Normal\"/><w:qFormat/><w:pPr><w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"F4F5F5\"/>",
+    "<w:spacing w:after=\"160\" w:line=\"240\" w:lineRule=\"auto\"/></w:pPr>",
+    "<w:rPr><w:rFonts w:ascii=\"Consolas\" w:hAnsi=\"Consolas\" w:cs=\"Consolas\"/><w:sz w:val=\"19\"/>",
+    "<w:szCs w:val=\"19\"/></w:rPr></w:style>",
+    "<w:style w:type=\"character\" w:customStyle=\"1\" w:styleId=\"VerbatimChar\"><w:name w:val=\"Verbatim Char\"/>",
+    "<w:rPr><w:rFonts w:ascii=\"Consolas\" w:hAnsi=\"Consolas\" w:cs=\"Consolas\"/><w:sz w:val=\"20\"/>",
+    "<w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"F4F5F5\"/></w:rPr></w:style>",
+    "<w:style w:type=\"character\" w:styleId=\"Hyperlink\"><w:name w:val=\"Hyperlink\"/>",
+    "<w:rPr><w:color w:val=\"0563C1\"/><w:u w:val=\"single\"/></w:rPr></w:style>",
+    "<w:style w:type=\"paragraph\" w:styleId=\"FootnoteText\"><w:name w:val=\"footnote text\"/>",
+    "<w:basedOn w:val=\"Normal\"/><w:pPr><w:spacing w:after=\"0\" w:line=\"240\" w:lineRule=\"auto\"/></w:pPr>",
+    "<w:rPr><w:sz w:val=\"18\"/><w:szCs w:val=\"18\"/></w:rPr></w:style>",
+    "<w:style w:type=\"character\" w:styleId=\"FootnoteReference\"><w:name w:val=\"footnote reference\"/>",
+    "<w:rPr><w:vertAlign w:val=\"superscript\"/></w:rPr></w:style>",
+    "<w:style w:type=\"paragraph\" w:customStyle=\"1\" w:styleId=\"Rule\"><w:name w:val=\"Horizontal Rule\"/>",
+    "<w:basedOn w:val=\"Normal\"/><w:pPr><w:pBdr><w:bottom w:val=\"single\" w:sz=\"6\" w:space=\"1\" ",
+    "w:color=\"E6E7E7\"/></w:pBdr></w:pPr></w:style>",
+    "<w:style w:type=\"table\" w:styleId=\"Table\"><w:name w:val=\"Table\"/><w:basedOn w:val=\"TableNormal\"/>",
+    "<w:pPr><w:spacing w:before=\"60\" w:after=\"60\"/></w:pPr><w:tblPr><w:tblBorders>",
+    "<w:top w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"E6E7E7\"/>",
+    "<w:bottom w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"E6E7E7\"/>",
+    "<w:insideH w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"E6E7E7\"/>",
+    "</w:tblBorders></w:tblPr><w:tblStylePr w:type=\"firstRow\"><w:rPr><w:b/></w:rPr><w:tcPr><w:tcBorders>",
+    "<w:bottom w:val=\"single\" w:sz=\"12\" w:space=\"0\" w:color=\"E6E7E7\"/></w:tcBorders></w:tcPr>",
+    "</w:tblStylePr></w:style>",
+);
+
+const SETTINGS: &str = concat!(
+    "<w:footnotePr><w:footnote w:id=\"-1\"/><w:footnote w:id=\"0\"/></w:footnotePr>",
+    "<w:compat><w:compatSetting w:name=\"compatibilityMode\" w:uri=\"http://schemas.microsoft.com/office/word\" ",
+    "w:val=\"15\"/></w:compat>",
+);
+
+/// A4 with the PDF export's default margins.
+const SECTION: &str = concat!(
+    "<w:sectPr><w:footnotePr><w:numFmt w:val=\"decimal\"/></w:footnotePr>",
+    "<w:pgSz w:w=\"11906\" w:h=\"16838\"/>",
+    "<w:pgMar w:top=\"1417\" w:right=\"1134\" w:bottom=\"1417\" w:left=\"1134\" w:header=\"709\" ",
+    "w:footer=\"709\" w:gutter=\"0\"/></w:sectPr>",
+);
+
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+fn styles() -> String {
+    let mut styles = format!("{}<w:styles xmlns:w=\"{}\">{}", XML_HEADER, W, STYLES);
+    for (ix, size) in HEADING_SIZES.iter().enumerate() {
+        styles.push_str(&format!(
+            concat!(
+                "<w:style w:type=\"paragraph\" w:styleId=\"Heading{0}\"><w:name w:val=\"heading {0}\"/>",
+                "<w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/><w:uiPriority w:val=\"9\"/><w:qFormat/>",
+                "<w:pPr><w:keepNext/><w:keepLines/><w:spacing w:before=\"{2}\" w:after=\"120\"/>",
+                "<w:outlineLvl w:val=\"{1}\"/></w:pPr><w:rPr><w:b/><w:bCs/><w:sz w:val=\"{3}\"/>",
+                "<w:szCs w:val=\"{3}\"/></w:rPr></w:style>"
+            ),
+            ix + 1,
+            ix,
+            if ix == 0 { 480 } else { 320 },
+            size
+        ));
+    }
+    styles.push_str(OTHER_STYLES);
+    styles.push_str("</w:styles>");
+    styles
+}
+
+/// Two abstract lists, bullets and numbers, and one instance per list of
+/// the document so each ordered list starts at its own number.
+fn numbering(lists: &[Numbering]) -> String {
+    let mut xml = format!("{}<w:numbering xmlns:w=\"{}\">", XML_HEADER, W);
+    for ordered in [false, true] {
+        xml.push_str(&format!(
+            "<w:abstractNum w:abstractNumId=\"{}\"><w:multiLevelType w:val=\"hybridMultilevel\"/>",
+            u8::from(ordered)
+        ));
+        for level in 0..9 {
+            let (format, text) = if ordered {
+                ("decimal", format!("%{}.", level + 1))
+            } else {
+                ("bullet", BULLETS[level % BULLETS.len()].to_string())
+            };
+            xml.push_str(&format!(
+                concat!(
+                    "<w:lvl w:ilvl=\"{0}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{1}\"/>",
+                    "<w:lvlText w:val=\"{2}\"/><w:lvlJc w:val=\"left\"/>",
+                    "<w:pPr><w:ind w:left=\"{3}\" w:hanging=\"360\"/></w:pPr></w:lvl>"
+                ),
+                level,
+                format,
+                text,
+                (level + 1) * 720
+            ));
+        }
+        xml.push_str("</w:abstractNum>");
+    }
+    for (ix, list) in lists.iter().enumerate() {
+        xml.push_str(&format!(
+            concat!(
+                "<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"{}\"/>",
+                "<w:lvlOverride w:ilvl=\"{}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride></w:num>"
+            ),
+            ix + 1,
+            u8::from(list.ordered),
+            list.level,
+            list.start
+        ));
+    }
+    xml.push_str("</w:numbering>");
+    xml
+}
+
+fn footnotes(body: &str) -> String {
+    format!(
+        concat!(
+            "{}<w:footnotes xmlns:w=\"{}\" xmlns:r=\"{}\" xmlns:m=\"{}\">",
+            "<w:footnote w:type=\"separator\" w:id=\"-1\"><w:p><w:pPr><w:spacing w:after=\"0\"/></w:pPr>",
+            "<w:r><w:separator/></w:r></w:p></w:footnote>",
+            "<w:footnote w:type=\"continuationSeparator\" w:id=\"0\"><w:p><w:pPr><w:spacing w:after=\"0\"/></w:pPr>",
+            "<w:r><w:continuationSeparator/></w:r></w:p></w:footnote>{}</w:footnotes>"
+        ),
+        XML_HEADER,
+        W,
+        R,
+        "http://schemas.openxmlformats.org/officeDocument/2006/math",
+        body
+    )
+}
+
+fn document(body: &str) -> String {
+    format!(
+        concat!(
+            "{}<w:document xmlns:w=\"{}\" xmlns:r=\"{}\" ",
+            "xmlns:m=\"http://schemas.openxmlformats.org/officeDocument/2006/math\" ",
+            "xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\" ",
+            "xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" ",
+            "xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">",
+            "<w:body>{}{}</w:body></w:document>"
+        ),
+        XML_HEADER,
+        W,
+        R,
+        body,
+        SECTION
+    )
+}
+
+fn content_types() -> String {
+    let override_part = |part: &str, kind: &str| format!("<Override PartName=\"{}\" ContentType=\"{}\"/>", part, kind);
+    let wordml = "application/vnd.openxmlformats-officedocument.wordprocessingml";
+    let mut xml = format!(
+        concat!(
+            "{}<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">",
+            "<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>",
+            "<Default Extension=\"xml\" ContentType=\"application/xml\"/>",
+            "<Default Extension=\"png\" ContentType=\"image/png\"/>",
+            "<Default Extension=\"jpeg\" ContentType=\"image/jpeg\"/>",
+            "<Default Extension=\"gif\" ContentType=\"image/gif\"/>",
+            "<Default Extension=\"bmp\" ContentType=\"image/bmp\"/>"
+        ),
+        XML_HEADER
+    );
+    xml.push_str(&override_part("/word/document.xml", &format!("{}.document.main+xml", wordml)));
+    xml.push_str(&override_part("/word/styles.xml", &format!("{}.styles+xml", wordml)));
+    xml.push_str(&override_part("/word/numbering.xml", &format!("{}.numbering+xml", wordml)));
+    xml.push_str(&override_part("/word/footnotes.xml", &format!("{}.footnotes+xml", wordml)));
+    xml.push_str(&override_part("/word/settings.xml", &format!("{}.settings+xml", wordml)));
+    xml.push_str(&override_part("/docProps/core.xml", "application/vnd.openxmlformats-package.core-properties+xml"));
+    xml.push_str("</Types>");
+    xml
+}
+
+fn relationship(id: &str, kind: &str, target: &str, external: bool) -> String {
+    format!(
+        "<Relationship Id=\"{}\" Type=\"{}/{}\" Target=\"{}\"{}/>",
+        id,
+        R,
+        kind,
+        escape(target),
+        if external { " TargetMode=\"External\"" } else { "" }
+    )
+}
+
+fn package_relationships() -> String {
+    format!(
+        "{}<Relationships xmlns=\"{}\">{}<Relationship Id=\"rCore\" Type=\"{}\" Target=\"docProps/core.xml\"/></Relationships>",
+        XML_HEADER,
+        RELATIONSHIPS,
+        relationship("rDocument", "officeDocument", "word/document.xml", false),
+        "http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties"
+    )
+}
+
+fn document_relationships(body: &Body) -> String {
+    let mut xml = format!("{}<Relationships xmlns=\"{}\">", XML_HEADER, RELATIONSHIPS);
+    xml.push_str(&relationship("rStyles", "styles", "styles.xml", false));
+    xml.push_str(&relationship("rNumbering", "numbering", "numbering.xml", false));
+    xml.push_str(&relationship("rFootnotes", "footnotes", "footnotes.xml", false));
+    xml.push_str(&relationship("rSettings", "settings", "settings.xml", false));
+    for (ix, url) in body.links.iter().enumerate() {
+        xml.push_str(&relationship(&format!("rLink{}", ix + 1), "hyperlink", url, true));
+    }
+    for (ix, file) in body.media.files.iter().enumerate() {
+        xml.push_str(&relationship(&format!("rMedia{}", ix + 1), "image", &format!("media/{}", file.name), false));
+    }
+    xml.push_str("</Relationships>");
+    xml
+}
+
+fn core_properties(title: &str, author: &str) -> String {
+    format!(
+        concat!(
+            "{}<cp:coreProperties ",
+            "xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" ",
+            "xmlns:dc=\"http://purl.org/dc/elements/1.1/\">",
+            "<dc:title>{}</dc:title><dc:creator>{}</dc:creator></cp:coreProperties>"
+        ),
+        XML_HEADER,
+        escape(title),
+        escape(author)
+    )
+}
+
+/// The `.docx` file with `body` and its title and author in the properties.
+pub fn package(body: &Body, title: &str, author: &str) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: &str, bytes: &[u8]| {
+        zip.start_file(name, options).map_err(|e| format!("Error al crear el documento: {}", e))?;
+        zip.write_all(bytes).map_err(|e| format!("Error al crear el documento: {}", e))
+    };
+    add("[Content_Types].xml", content_types().as_bytes())?;
+    add("_rels/.rels", package_relationships().as_bytes())?;
+    add("docProps/core.xml", core_properties(title, author).as_bytes())?;
+    add("word/document.xml", document(&body.document).as_bytes())?;
+    add("word/_rels/document.xml.rels", document_relationships(body).as_bytes())?;
+    add("word/styles.xml", styles().as_bytes())?;
+    add("word/numbering.xml", numbering(&body.numbering).as_bytes())?;
+    add("word/footnotes.xml", footnotes(&body.footnotes).as_bytes())?;
+    add("word/settings.xml", format!("{}<w:settings xmlns:w=\"{}\">{}</w:settings>", XML_HEADER, W, SETTINGS).as_bytes())?;
+    for file in &body.media.files {
+        add(&format!("word/media/{}", file.name), &file.bytes)?;
+    }
+    let cursor = zip.finish().map_err(|e| format!("Error al crear el documento: {}", e))?;
+    Ok(cursor.into_inner())
+}
@@ -0,0 +1,529 @@
+//! The document's pulldown-cmark events as WordprocessingML.
+
+use std::collections::HashMap;
+
+use codedocs_md::links::percent_decode;
+use codedocs_md::options;
+use pulldown_cmark::{Alignment, Event, LinkType, Options, Parser, Tag, TagEnd};
+
+use super::escape;
+use super::images::{Media, TEXT_WIDTH_EMU};
+use super::math::omml;
+
+/// Width of the text between the page margins, in twentieths of a point.
+pub const TEXT_WIDTH: u32 = 9638;
+
+/// Indentation of each level of lists and block quotes, in twentieths of a point.
+const INDENT: u32 = 720;
+
+/// A list, for `numbering.xml`: its kind, level and first number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Numbering {
+    pub ordered: bool,
+    pub level: usize,
+    pub start: u64,
+}
+
+/// Everything the package needs besides the fixed parts.
+#[derive(Debug, Default)]
+pub struct Body {
+    pub document: String,
+    /// The `w:footnote` elements, numbered from 1.
+    pub footnotes: String,
+    /// One entry per list, numbered from 1.
+    pub numbering: Vec<Numbering>,
+    /// Web addresses the hyperlinks point at, as relationships `rLink1`, `rLink2`...
+    pub links: Vec<String>,
+    pub media: Media,
+}
+
+struct List {
+    num_id: usize,
+    /// The next paragraph starts an item and shows its number or bullet.
+    item_start: bool,
+}
+
+struct Writer<'a> {
+    body: Body,
+    /// Output stack: the document, and the footnote being written.
+    out: Vec<String>,
+    paragraph_open: bool,
+    heading: Option<u8>,
+    lists: Vec<List>,
+    quotes: u32,
+    code: Option<String>,
+    table: Option<Vec<Alignment>>,
+    cell: usize,
+    in_metadata: bool,
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    /// Open links, and whether each became a `w:hyperlink`.
+    links: Vec<bool>,
+    image: Option<(String, String)>,
+    /// Bookmark names by heading slug, in the order the headings come.
+    bookmarks: Vec<(String, String)>,
+    headings_seen: usize,
+    /// Footnote numbers by label, in the order of their first reference.
+    footnote_ids: HashMap<String, usize>,
+    footnote_bodies: HashMap<usize, String>,
+    footnote_first: bool,
+    math: &'a HashMap<(String, bool), String>,
+    document_dir: &'a str,
+}
+
+impl Writer<'_> {
+    fn push(&mut self, xml: &str) {
+        self.out.last_mut().expect("an output").push_str(xml);
+    }
+
+    fn indent(&self) -> u32 {
+        (self.lists.len() as u32 + self.quotes) * INDENT
+    }
+
+    /// Opens a paragraph for the text to come, styled after the blocks it is in.
+    fn open_paragraph(&mut self) {
+        if self.paragraph_open {
+            return;
+        }
+        self.paragraph_open = true;
+        // Children of `w:pPr` in the schema's order: style, numbering, then
+        // indentation or alignment.
+        let mut properties = String::new();
+        let style = match self.heading {
+            Some(level) => Some(format!("Heading{}", level)),
+            None if self.out.len() > 1 => Some("FootnoteText".to_string()),
+            None if self.table.is_some() => None,
+            None if self.quotes > 0 => Some("Quote".to_string()),
+            None if !self.lists.is_empty() => Some("ListParagraph".to_string()),
+            None => None,
+        };
+        if let Some(style) = style {
+            properties.push_str(&format!("<w:pStyle w:val=\"{}\"/>", style));
+        }
+        let numbered = self.lists.last_mut().filter(|list| list.item_start).map(|list| {
+            list.item_start = false;
+            list.num_id
+        });
+        if let Some(num_id) = numbered.filter(|_| self.heading.is_none() && self.table.is_none()) {
+            properties.push_str(&format!(
+                "<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr>",
+                self.lists.len() - 1,
+                num_id
+            ));
+        }
+        if let Some(alignment) = self.table.as_ref().and_then(|alignments| alignments.get(self.cell)) {
+            match alignment {
+                Alignment::Center => properties.push_str("<w:jc w:val=\"center\"/>"),
+                Alignment::Right => properties.push_str("<w:jc w:val=\"right\"/>"),
+                _ => {}
+            }
+        } else if self.heading.is_none() && self.indent() > 0 {
+            // Numbered paragraphs take their indentation from the numbering.
+            if numbered.is_none() {
+                properties.push_str(&format!("<w:ind w:left=\"{}\"/>", self.indent()));
+            }
+        }
+
+        let mut xml = String::from("<w:p>");
+        if !properties.is_empty() {
+            xml.push_str(&format!("<w:pPr>{}</w:pPr>", properties));
+        }
+        // Links within the document point at a bookmark on their heading.
+        if let Some((_, name)) = self.bookmarks.get(self.headings_seen).filter(|_| self.heading.is_some()) {
+            xml.push_str(&format!(
+                "<w:bookmarkStart w:id=\"{0}\" w:name=\"{1}\"/><w:bookmarkEnd w:id=\"{0}\"/>",
+                self.headings_seen, name
+            ));
+        }
+        if std::mem::take(&mut self.footnote_first) {
+            xml.push_str("<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\"/></w:rPr><w:footnoteRef/></w:r>");
+            xml.push_str("<w:r><w:t xml:space=\"preserve\"> </w:t></w:r>");
+        }
+        self.push(&xml);
+    }
+
+    fn close_paragraph(&mut self) {
+        if self.paragraph_open {
+            self.push("</w:p>");
+            self.paragraph_open = false;
+        }
+    }
+
+    fn run_properties(&self, code: bool) -> String {
+        let mut properties = String::new();
+        if code {
+            properties.push_str("<w:rStyle w:val=\"VerbatimChar\"/>");
+        } else if self.links.iter().any(|linked| *linked) {
+            properties.push_str("<w:rStyle w:val=\"Hyperlink\"/>");
+        }
+        if self.bold > 0 {
+            properties.push_str("<w:b/>");
+        }
+        if self.italic > 0 {
+            properties.push_str("<w:i/>");
+        }
+        if self.strike > 0 {
+            properties.push_str("<w:strike/>");
+        }
+        if properties.is_empty() {
+            properties
+        } else {
+            format!("<w:rPr>{}</w:rPr>", properties)
+        }
+    }
+
+    fn text(&mut self, text: &str, code: bool) {
+        if let Some((_, alt)) = &mut self.image {
+            alt.push_str(text);
+            return;
+        }
+        self.open_paragraph();
+        let run = format!(
+            "<w:r>{}<w:t xml:space=\"preserve\">{}</w:t></w:r>",
+            self.run_properties(code),
+            escape(text)
+        );
+        self.push(&run);
+    }
+
+    fn line_break(&mut self) {
+        self.open_paragraph();
+        self.push("<w:r><w:br/></w:r>");
+    }
+
+    fn math(&mut self, tex: &str, display: bool) {
+        let converted = self.math.get(&(tex.trim().to_string(), display)).and_then(|mathml| omml(mathml));
+        match converted {
+            Some(math) => {
+                self.open_paragraph();
+                if display {
+                    self.push(&format!("<m:oMathPara>{}</m:oMathPara>", math));
+                } else {
+                    self.push(&math);
+                }
+            }
+            // Without the webview's rendering the formula keeps its source.
+            None if display => {
+                self.close_paragraph();
+                self.code_block(tex.trim());
+            }
+            None => self.text(tex, true),
+        }
+    }
+
+    fn code_block(&mut self, code: &str) {
+        let mut xml = String::from("<w:p><w:pPr><w:pStyle w:val=\"SourceCode\"/>");
+        if self.indent() > 0 {
+            xml.push_str(&format!("<w:ind w:left=\"{}\"/>", self.indent()));
+        }
+        xml.push_str("</w:pPr>");
+        for (ix, line) in code.split('\n').enumerate() {
+            if ix > 0 {
+                xml.push_str("<w:r><w:br/></w:r>");
+            }
+            xml.push_str(&format!("<w:r><w:t xml:space=\"preserve\">{}</w:t></w:r>", escape(line)));
+        }
+        xml.push_str("</w:p>");
+        self.push(&xml);
+    }
+
+    fn image(&mut self, src: &str, alt: &str) {
+        match self.body.media.add(self.document_dir, src) {
+            Some(index) => {
+                self.open_paragraph();
+                let max_width = match &self.table {
+                    // The column, less the cell margins.
+                    Some(_) => u64::from(self.column_width().saturating_sub(216)) * 635,
+                    None => TEXT_WIDTH_EMU.saturating_sub(u64::from(self.indent()) * 635),
+                };
+                let drawing = self.body.media.drawing(index, alt, max_width);
+                self.push(&drawing);
+            }
+            None => {
+                let text = if alt.trim().is_empty() { src.to_string() } else { alt.to_string() };
+                self.italic += 1;
+                self.text(&text, false);
+                self.italic -= 1;
+            }
+        }
+    }
+
+    fn footnote_id(&mut self, label: &str) -> usize {
+        let next = self.footnote_ids.len() + 1;
+        *self.footnote_ids.entry(label.to_string()).or_insert(next)
+    }
+
+    fn link(&mut self, link_type: LinkType, url: &str) {
+        if matches!(link_type, LinkType::WikiLink { .. }) {
+            self.links.push(false);
+            return;
+        }
+        let external = ["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme));
+        let target = if external {
+            self.body.links.push(url.to_string());
+            Some(format!("r:id=\"rLink{}\"", self.body.links.len()))
+        } else if let Some(anchor) = url.strip_prefix('#') {
+            let anchor = percent_decode(anchor);
+            self.bookmarks
+                .iter()
+                .find(|(slug, _)| *slug == anchor)
+                .map(|(_, name)| format!("w:anchor=\"{}\"", name))
+        } else {
+            None
+        };
+        match target {
+            Some(target) => {
+                self.open_paragraph();
+                self.push(&format!("<w:hyperlink {} w:history=\"1\">", target));
+                self.links.push(true);
+            }
+            None => self.links.push(false),
+        }
+    }
+
+    fn column_width(&self) -> u32 {
+        let columns = self.table.as_ref().map_or(1, |alignments| alignments.len().max(1)) as u32;
+        TEXT_WIDTH.saturating_sub(self.indent()) / columns
+    }
+
+    fn table_start(&mut self, alignments: Vec<Alignment>) {
+        self.close_paragraph();
+        let columns = alignments.len().max(1) as u32;
+        self.table = Some(alignments);
+        let width = self.column_width();
+        let mut xml = String::from("<w:tbl><w:tblPr><w:tblStyle w:val=\"Table\"/>");
+        xml.push_str(&format!("<w:tblW w:w=\"{}\" w:type=\"dxa\"/>", width * columns));
+        if self.indent() > 0 {
+            xml.push_str(&format!("<w:tblInd w:w=\"{}\" w:type=\"dxa\"/>", self.indent()));
+        }
+        xml.push_str("<w:tblLook w:val=\"04A0\" w:firstRow=\"1\" w:lastRow=\"0\" w:firstColumn=\"0\" w:lastColumn=\"0\" w:noHBand=\"1\" w:noVBand=\"1\"/>");
+        xml.push_str("</w:tblPr><w:tblGrid>");
+        for _ in 0..columns {
+            xml.push_str(&format!("<w:gridCol w:w=\"{}\"/>", width));
+        }
+        xml.push_str("</w:tblGrid>");
+        self.push(&xml);
+    }
+
+    fn cell_start(&mut self) {
+        let width = self.column_width();
+        self.push(&format!("<w:tc><w:tcPr><w:tcW w:w=\"{}\" w:type=\"dxa\"/></w:tcPr>", width));
+        self.open_paragraph();
+    }
+
+    fn cell_end(&mut self) {
+        // Every cell needs a paragraph, even an empty one.
+        self.open_paragraph();
+        self.close_paragraph();
+        self.push("</w:tc>");
+        self.cell += 1;
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.close_paragraph(),
+            Tag::Heading { level, .. } => {
+                self.close_paragraph();
+                self.heading = Some(level as u8);
+            }
+            Tag::BlockQuote(_) => {
+                self.close_paragraph();
+                self.quotes += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.close_paragraph();
+                self.code = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.close_paragraph();
+                self.body.numbering.push(Numbering {
+                    ordered: start.is_some(),
+                    level: self.lists.len(),
+                    start: start.unwrap_or(1),
+                });
+                self.lists.push(List { num_id: self.body.numbering.len(), item_start: false });
+            }
+            Tag::Item => {
+                self.close_paragraph();
+                if let Some(list) = self.lists.last_mut() {
+                    list.item_start = true;
+                }
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.close_paragraph();
+                let id = self.footnote_id(&label);
+                self.out.push(format!("<w:footnote w:id=\"{}\">", id));
+                self.footnote_first = true;
+            }
+            Tag::Table(alignments) => self.table_start(alignments),
+            Tag::TableHead => {
+                self.bold += 1;
+                self.cell = 0;
+                self.push("<w:tr><w:trPr><w:tblHeader/></w:trPr>");
+            }
+            Tag::TableRow => {
+                self.cell = 0;
+                self.push("<w:tr>");
+            }
+            Tag::TableCell => self.cell_start(),
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { link_type, dest_url, .. } => self.link(link_type, &dest_url),
+            Tag::Image { dest_url, .. } => self.image = Some((dest_url.to_string(), String::new())),
+            Tag::MetadataBlock(_) => self.in_metadata = true,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.close_paragraph(),
+            TagEnd::Heading(_) => {
+                self.open_paragraph();
+                self.close_paragraph();
+                self.heading = None;
+                self.headings_seen += 1;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.close_paragraph();
+                self.quotes = self.quotes.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code.take().unwrap_or_default();
+                self.code_block(code.strip_suffix('\n').unwrap_or(&code));
+            }
+            TagEnd::List(_) => {
+                self.close_paragraph();
+                self.lists.pop();
+            }
+            TagEnd::Item => self.close_paragraph(),
+            TagEnd::FootnoteDefinition => {
+                self.close_paragraph();
+                if self.out.len() > 1 {
+                    let mut footnote = self.out.pop().unwrap_or_default();
+                    footnote.push_str("</w:footnote>");
+                    if let Some(id) = footnote_id_of(&footnote) {
+                        self.footnote_bodies.insert(id, footnote);
+                    }
+                }
+            }
+            TagEnd::Table => {
+                self.push("</w:tbl>");
+                self.table = None;
+                // Word needs a paragraph between two tables and after one that ends a cell.
+                self.push("<w:p/>");
+            }
+            TagEnd::TableHead => {
+                self.bold = self.bold.saturating_sub(1);
+                self.push("</w:tr>");
+            }
+            TagEnd::TableRow => self.push("</w:tr>"),
+            TagEnd::TableCell => self.cell_end(),
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.strike = self.strike.saturating_sub(1),
+            TagEnd::Link => {
+                let hyperlink = self.links.pop().unwrap_or(false);
+                if hyperlink {
+                    self.push("</w:hyperlink>");
+                }
+            }
+            TagEnd::Image => {
+                if let Some((src, alt)) = self.image.take() {
+                    self.image(&src, &alt);
+                }
+            }
+            TagEnd::MetadataBlock(_) => self.in_metadata = false,
+            _ => {}
+        }
+    }
+}
+
+fn is_line_break(html: &str) -> bool {
+    let tag: String = html.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_lowercase();
+    matches!(tag.as_str(), "<br>" | "<br/>")
+}
+
+fn footnote_id_of(footnote: &str) -> Option<usize> {
+    let rest = footnote.strip_prefix("<w:footnote w:id=\"")?;
+    rest.split('"').next()?.parse().ok()
+}
+
+/// The body of `word/document.xml` and the footnotes, lists, links and
+/// images it refers to. `math` maps a formula's TeX, and whether it is
+/// displayed, to the MathML KaTeX rendered for it; `slugs` are the ids of
+/// the headings in order, for links within the document.
+pub fn write_body(content: &str, math: &HashMap<(String, bool), String>, slugs: &[String], document_dir: &str) -> Body {
+    let mut writer = Writer {
+        body: Body::default(),
+        out: vec![String::new()],
+        paragraph_open: false,
+        heading: None,
+        lists: Vec::new(),
+        quotes: 0,
+        code: None,
+        table: None,
+        cell: 0,
+        in_metadata: false,
+        bold: 0,
+        italic: 0,
+        strike: 0,
+        links: Vec::new(),
+        image: None,
+        bookmarks: slugs.iter().enumerate().map(|(ix, slug)| (slug.clone(), format!("_Heading{}", ix + 1))).collect(),
+        headings_seen: 0,
+        footnote_ids: HashMap::new(),
+        footnote_bodies: HashMap::new(),
+        footnote_first: false,
+        math,
+        document_dir,
+    };
+
+    for event in Parser::new_ext(content, options() | Options::ENABLE_MATH) {
+        match event {
+            Event::Start(tag) => writer.start(tag),
+            Event::End(tag) => writer.end(tag),
+            Event::Text(_) if writer.in_metadata => {}
+            Event::Text(text) => match &mut writer.code {
+                Some(code) => code.push_str(&text),
+                None => writer.text(&text, false),
+            },
+            Event::Code(code) => writer.text(&code, true),
+            Event::InlineMath(tex) => writer.math(&tex, false),
+            Event::DisplayMath(tex) => writer.math(&tex, true),
+            Event::SoftBreak => writer.text(" ", false),
+            Event::HardBreak => writer.line_break(),
+            // Other raw HTML is left out.
+            Event::InlineHtml(html) if is_line_break(&html) => writer.line_break(),
+            Event::FootnoteReference(label) => {
+                let id = writer.footnote_id(&label);
+                writer.open_paragraph();
+                writer.push(&format!(
+                    "<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\"/></w:rPr><w:footnoteReference w:id=\"{}\"/></w:r>",
+                    id
+                ));
+            }
+            Event::TaskListMarker(checked) => writer.text(if checked { "☒ " } else { "☐ " }, false),
+            Event::Rule => {
+                writer.close_paragraph();
+                writer.push("<w:p><w:pPr><w:pStyle w:val=\"Rule\"/></w:pPr></w:p>");
+            }
+            _ => {}
+        }
+    }
+    writer.close_paragraph();
+
+    // Referenced footnotes without a definition still need an entry.
+    let mut ids: Vec<usize> = writer.footnote_ids.values().copied().collect();
+    ids.sort_unstable();
+    for id in ids {
+        let footnote = writer.footnote_bodies.remove(&id).unwrap_or_else(|| {
+            format!("<w:footnote w:id=\"{}\"><w:p><w:pPr><w:pStyle w:val=\"FootnoteText\"/></w:pPr></w:p></w:footnote>", id)
+        });
+        writer.body.footnotes.push_str(&footnote);
+    }
+    writer.body.document = writer.out.swap_remove(0);
+    writer.body
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use codedocs_md::assets::image_mime;
+use codedocs_md::config::ProjectConfig;
+use codedocs_md::epub::{
+    book_images, book_metadata, chapter_page, epub_css, nav_document, package_document, timestamp, unlink_files,
+    xhtml, ManifestItem, CONTAINER_XML, MIMETYPE,
+};
+use codedocs_md::export::{apply_enhancements, document_title};
+use codedocs_md::links::parent_dir;
+use codedocs_md::site::{is_draft, navigation, page_output, reading_order, rewrite_doc_links, NavItem};
+use codedocs_md::{extract_headings, render_markdown, Heading, RenderOptions};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::commands::project_tree;
+use crate::export::image_path;
+use crate::site::{collect_pages, normalized, site_entries};
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct EpubSummary {
+    pub chapters: usize,
+    pub images: usize,
+    pub output: String,
+}
+
+/// Image types every EPUB reader shows.
+const BOOK_IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/svg+xml", "image/webp"];
+
+struct BookImage {
+    path: String,
+    href: String,
+    media_type: &'static str,
+}
+
+/// Adds the image at `path` to the book once, and returns where chapters find it.
+fn add_image(images: &mut Vec<BookImage>, path: &str) -> Option<String> {
+    let key = normalized(path);
+    if let Some(image) = images.iter().find(|image| normalized(&image.path) == key) {
+        return Some(image.href.clone());
+    }
+    let extension = Path::new(path).extension()?.to_string_lossy().to_lowercase();
+    let media_type = image_mime(&extension).filter(|mime| BOOK_IMAGE_TYPES.contains(mime))?;
+    if !Path::new(path).is_file() {
+        return None;
+    }
+    let href = format!("images/image{}.{}", images.len() + 1, extension);
+    images.push(BookImage { path: path.to_string(), href: href.clone(), media_type });
+    Some(href)
+}
+
+/// The navigation with each page's url replaced by its chapter's file.
+fn with_files(items: &[NavItem], file_for: &HashMap<String, String>) -> Vec<NavItem> {
+    items
+        .iter()
+        .map(|item| NavItem {
+            title: item.title.clone(),
+            url: item.url.as_ref().and_then(|url| file_for.get(url).cloned()),
+            children: with_files(&item.children, file_for),
+        })
+        .collect()
+}
+
+/// A stable `urn:uuid:` for books whose front matter has no identifier,
+/// so exporting the same project twice gives the same book.
+fn book_uuid(root: &str, title: &str) -> String {
+    let mut bytes = Sha256::digest(format!("{}\n{}", normalized(root), title).as_bytes())[..16].to_vec();
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("urn:uuid:{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Bundles the documents of the project at `root` into the EPUB 3 book at
+/// `output`: one chapter per document in the order of the site's
+/// navigation (the tree, or the front matter's `order`) after the root
+/// `index.md`, a table of contents with their headings, the images they use
+/// and the metadata of that `index.md`, or else of the first chapter. Drafts
+/// are left out.
+pub fn export_epub(root: &str, output: &str, config: &ProjectConfig) -> Result<EpubSummary, String> {
+    let entries = site_entries(&project_tree(root));
+    let mut documents = Vec::new();
+    collect_pages(&entries, &mut documents);
+    let project_files: Vec<String> = documents.iter().map(|(path, _)| path.to_string()).collect();
+    let pages: HashMap<String, (&str, &str)> = documents
+        .iter()
+        .filter(|(_, content)| !is_draft(content))
+        .map(|(path, content)| (page_output(root, path), (*path, *content)))
+        .collect();
+
+    let mut nav = navigation(root, &entries);
+    // The project's index is the front of the book, wherever the tree puts it.
+    if let Some(ix) = nav.iter().position(|item| item.url.as_deref() == Some("index.html")) {
+        let index = nav.remove(ix);
+        nav.insert(0, index);
+    }
+    let order = reading_order(&nav);
+    if order.is_empty() {
+        return Err("No se encontraron archivos Markdown en la carpeta seleccionada".to_string());
+    }
+    let file_for: HashMap<String, String> = order
+        .iter()
+        .enumerate()
+        .map(|(ix, url)| (url.clone(), format!("chapter-{:03}.xhtml", ix + 1)))
+        .collect();
+    let chapter_for = |path: &str| file_for.get(&page_output(root, path)).cloned();
+    let chapter_files: Vec<String> = file_for.values().cloned().collect();
+
+    let (meta_path, meta_content) = pages.get("index.html").or_else(|| pages.get(&order[0])).copied().unwrap_or_default();
+    let mut metadata = book_metadata(meta_content, meta_path);
+    if metadata.identifier.is_empty() {
+        metadata.identifier = book_uuid(root, &metadata.title);
+    }
+
+    let mut images = Vec::new();
+    let cover = metadata
+        .cover
+        .as_deref()
+        .and_then(|cover| image_path(parent_dir(meta_path), cover))
+        .and_then(|path| add_image(&mut images, &path));
+
+    let mut chapters = Vec::new();
+    let mut headings: HashMap<String, Vec<Heading>> = HashMap::new();
+    for url in &order {
+        let Some(&(path, content)) = pages.get(url) else { continue };
+        let file = file_for[url].clone();
+        let options = RenderOptions {
+            trusted_html: false,
+            document_path: Some(path.to_string()),
+            project_files: project_files.clone(),
+            ..config.render_options()
+        };
+        // Math and diagrams keep their source: readers do not run KaTeX or Mermaid.
+        let body = apply_enhancements(&render_markdown(content, &options), &[]);
+        let body = rewrite_doc_links(&body, &file, chapter_for);
+        let body = book_images(&body, |src| {
+            image_path(parent_dir(path), src).and_then(|image| add_image(&mut images, &image))
+        });
+        let body = xhtml(&unlink_files(&body, &chapter_files));
+        chapters.push((file.clone(), chapter_page(&document_title(content, path), &metadata.language, &body)));
+        headings.insert(file, extract_headings(content));
+    }
+
+    let nav_xhtml = nav_document(&metadata.language, &with_files(&nav, &file_for), |file| {
+        headings.get(file).cloned().unwrap_or_default()
+    });
+    let mut manifest = vec![
+        ManifestItem {
+            id: "nav".to_string(),
+            href: "nav.xhtml".to_string(),
+            media_type: "application/xhtml+xml".to_string(),
+            properties: Some("nav"),
+        },
+        ManifestItem {
+            id: "style".to_string(),
+            href: "style.css".to_string(),
+            media_type: "text/css".to_string(),
+            properties: None,
+        },
+    ];
+    for (ix, (file, _)) in chapters.iter().enumerate() {
+        manifest.push(ManifestItem {
+            id: format!("chapter-{}", ix + 1),
+            href: file.clone(),
+            media_type: "application/xhtml+xml".to_string(),
+            properties: None,
+        });
+    }
+    for (ix, image) in images.iter().enumerate() {
+        manifest.push(ManifestItem {
+            id: format!("image-{}", ix + 1),
+            href: image.href.clone(),
+            media_type: image.media_type.to_string(),
+            properties: (cover.as_deref() == Some(image.href.as_str())).then_some("cover-image"),
+        });
+    }
+    let spine: Vec<String> = (1..=chapters.len()).map(|ix| format!("chapter-{}", ix)).collect();
+    let modified = timestamp(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()));
+    let opf = package_document(&metadata, &modified, &manifest, &spine);
+
+    let error = |e: zip::result::ZipError| format!("Error al crear el libro: {}", e);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // The mimetype goes first and uncompressed, so readers can tell the file is a book.
+    zip.start_file("mimetype", SimpleFileOptions::default().compression_method(CompressionMethod::Stored))
+        .map_err(error)?;
+    zip.write_all(MIMETYPE.as_bytes()).map_err(|e| format!("Error al crear el libro: {}", e))?;
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: &str, bytes: &[u8]| -> Result<(), String> {
+        zip.start_file(name, deflated).map_err(error)?;
+        zip.write_all(bytes).map_err(|e| format!("Error al crear el libro: {}", e))
+    };
+    add("META-INF/container.xml", CONTAINER_XML.as_bytes())?;
+    add("OEBPS/content.opf", opf.as_bytes())?;
+    add("OEBPS/nav.xhtml", nav_xhtml.as_bytes())?;
+    add("OEBPS/style.css", epub_css().as_bytes())?;
+    for (file, page) in &chapters {
+        add(&format!("OEBPS/{}", file), page.as_bytes())?;
+    }
+    for image in &images {
+        let bytes = fs::read(&image.path).map_err(|e| format!("Error al leer {}: {}", image.path, e))?;
+        add(&format!("OEBPS/{}", image.href), &bytes)?;
+    }
+    let book = zip.finish().map_err(error)?.into_inner();
+    fs::write(output, book).map_err(|e| format!("Error al escribir {}: {}", output, e))?;
+
+    Ok(EpubSummary { chapters: chapters.len(), images: images.len(), output: output.to_string() })
+}
@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use codedocs_md::assets::image_mime;
+use codedocs_md::config::ProjectConfig;
+use codedocs_md::export::{apply_enhancements, document_title, rewrite_image_sources, standalone_html, HtmlExportOptions};
+use codedocs_md::links::{is_relative, parent_dir, percent_decode, percent_encode, relative_path, resolve_path};
+use codedocs_md::{extract_headings, render_markdown};
+
+/// Where a relative image `src` of the document in `document_dir` points.
+pub(crate) fn image_path(document_dir: &str, src: &str) -> Option<String> {
+    if !is_relative(src) {
+        return None;
+    }
+    let file = src.split(['?', '#']).next().unwrap_or_default();
+    Some(resolve_path(document_dir, &percent_decode(file)))
+}
+
+/// A local image as a `data:` URI.
+pub(crate) fn image_data_uri(path: &str) -> Option<String> {
+    let extension = Path::new(path).extension()?.to_string_lossy().into_owned();
+    let mime = image_mime(&extension)?;
+    let bytes = fs::read(path).ok()?;
+    Some(format!("data:{};base64,{}", mime, STANDARD.encode(bytes)))
+}
+
+/// The document at `path` as a standalone page to be written to `output_path`:
+/// rendered like the preview, with the math and diagrams the webview
+/// pre-rendered, and its images embedded or linked from the output's folder.
+pub fn document_html(path: &str, output_path: &str, config: &ProjectConfig, options: &HtmlExportOptions) -> Result<String, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Error al leer el archivo: {}", e))?;
+    let document_dir = parent_dir(path);
+    let output_dir = parent_dir(output_path);
+
+    let body = render_markdown(&content, &config.render_options());
+    let body = apply_enhancements(&body, &options.prerendered);
+    let body = rewrite_image_sources(&body, |src| {
+        let image = image_path(document_dir, src)?;
+        if options.embed_images {
+            image_data_uri(&image)
+        } else {
+            let link = relative_path(output_dir, &image);
+            Some(link.split('/').map(percent_encode).collect::<Vec<_>>().join("/"))
+        }
+    });
+
+    Ok(standalone_html(
+        &document_title(&content, path),
+        &body,
+        &extract_headings(&content),
+        options,
+    ))
+}
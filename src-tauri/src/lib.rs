@@ -4,11 +4,28 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+mod assets;
+pub mod cli;
 mod commands;
+mod docx;
+mod epub;
+mod export;
+mod link_check;
+mod link_index;
+mod pdf;
+mod project_protocol;
+mod refactor;
+mod site;
+mod trust;
 mod utils;
 use crate::commands::{
-    create_file, delete_file, generate_toc, list_markdown_files, open_project_folder, read_file,
-    rename_file, save_file, watch_folder, stop_watching,
+    apply_heading_rename, apply_link_fix, apply_rename, asset_report, check_links, create_file,
+    delete_file, export_docx, export_epub, export_html, export_pdf, export_site, generate_toc,
+    get_backlinks, get_link_graph, is_project_trusted, list_markdown_files, list_tasks,
+    list_wiki_targets, load_project_config, open_project_folder, preview_heading_rename,
+    preview_rename, read_file, rename_file, save_asset, save_file,
+    set_project_trusted, toggle_task_in_file, trash_assets, undo_refactor, watch_folder,
+    stop_watching,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -16,11 +33,13 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .register_uri_scheme_protocol(project_protocol::SCHEME, |_ctx, request| project_protocol::serve(request))
         .invoke_handler(tauri::generate_handler![
             greet,
             open_project_folder,
             list_markdown_files,
             save_file,
+            save_asset,
             read_file,
             generate_toc,
             delete_file,
@@ -28,6 +47,28 @@ pub fn run() {
             create_file,
             watch_folder,
             stop_watching,
+            load_project_config,
+            is_project_trusted,
+            set_project_trusted,
+            list_wiki_targets,
+            get_backlinks,
+            get_link_graph,
+            check_links,
+            apply_link_fix,
+            list_tasks,
+            toggle_task_in_file,
+            asset_report,
+            trash_assets,
+            export_html,
+            export_pdf,
+            export_docx,
+            export_site,
+            export_epub,
+            preview_rename,
+            apply_rename,
+            preview_heading_rename,
+            apply_heading_rename,
+            undo_refactor,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
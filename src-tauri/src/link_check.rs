@@ -0,0 +1,29 @@
+use std::fs;
+use std::path::Path;
+
+use codedocs_md::check::{check_document, LinkDiagnostic, ProjectDocs};
+use codedocs_md::extract_headings;
+
+use crate::commands::project_markdown_files;
+
+/// Runs the link checker over every markdown file under `root`.
+pub fn check_project(root: &str) -> Vec<LinkDiagnostic> {
+    let files = project_markdown_files(root);
+    let documents: Vec<(String, String)> = files
+        .iter()
+        .filter_map(|path| Some((path.clone(), fs::read_to_string(path).ok()?)))
+        .collect();
+
+    let project = ProjectDocs {
+        files,
+        headings: documents
+            .iter()
+            .map(|(path, content)| (path.clone(), extract_headings(content)))
+            .collect(),
+    };
+
+    documents
+        .iter()
+        .flat_map(|(path, content)| check_document(path, content, &project, &|target| Path::new(target).exists()))
+        .collect()
+}
@@ -0,0 +1,285 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use codedocs_md::links::{extract_links, is_within, link_destination};
+use serde::Serialize;
+
+/// Links between the project's documents, kept current by the folder watcher.
+pub static LINK_INDEX: Mutex<Option<LinkIndex>> = Mutex::new(None);
+
+/// Root folder of the project being watched, if any.
+pub fn project_root() -> Option<String> {
+    LINK_INDEX.lock().ok()?.as_ref().map(|index| index.root.clone())
+}
+
+#[derive(Debug, Clone)]
+struct OutgoingLink {
+    target: String,
+    line: usize,
+    snippet: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Backlink {
+    source: String,
+    line: usize,
+    snippet: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GraphNode {
+    path: String,
+    name: String,
+    incoming: usize,
+    outgoing: usize,
+    orphan: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GraphEdge {
+    source: String,
+    target: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LinkGraph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+pub struct LinkIndex {
+    root: String,
+    files: Vec<String>,
+    outgoing: HashMap<String, Vec<OutgoingLink>>,
+}
+
+/// The source line a link sits on, trimmed to something a sidebar can show.
+fn snippet(content: &str, line: usize) -> String {
+    let text = content.lines().nth(line.saturating_sub(1)).unwrap_or_default().trim();
+    if text.chars().count() > 160 {
+        format!("{}…", text.chars().take(160).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+impl LinkIndex {
+    pub fn build(root: &str) -> Self {
+        let mut index = LinkIndex {
+            root: root.to_string(),
+            files: crate::commands::project_markdown_files(root),
+            outgoing: HashMap::new(),
+        };
+        for path in index.files.clone() {
+            index.index_file(&path);
+        }
+        index
+    }
+
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    fn index_file(&mut self, path: &str) {
+        let Ok(content) = fs::read_to_string(path) else {
+            self.outgoing.remove(path);
+            return;
+        };
+        let links = extract_links(&content)
+            .iter()
+            .filter(|link| !link.image)
+            .filter_map(|link| {
+                let (target, _) = link_destination(link, path, &self.files)?;
+                (target != path && self.files.contains(&target)).then(|| OutgoingLink {
+                    target,
+                    line: link.line,
+                    snippet: snippet(&content, link.line),
+                })
+            })
+            .collect();
+        self.outgoing.insert(path.to_string(), links);
+    }
+
+    /// Re-reads one edited document.
+    pub fn update_file(&mut self, path: &Path) {
+        let path = path.to_string_lossy();
+        if self.files.iter().any(|f| *f == path) {
+            self.index_file(&path);
+        }
+    }
+
+    /// Re-reads the documents below a folder that appeared, vanished or was
+    /// renamed, and the documents that linked into it. Returns whether the
+    /// folder held or holds any document.
+    pub fn update_folder(&mut self, dir: &Path) -> bool {
+        let dir = dir.to_string_lossy();
+        let old: Vec<String> = self.files.iter().filter(|f| is_within(f, &dir)).cloned().collect();
+        let new = if Path::new(&*dir).is_dir() { crate::commands::project_markdown_files(&dir) } else { Vec::new() };
+        if old.is_empty() && new.is_empty() {
+            return false;
+        }
+        let linking: Vec<String> = self
+            .outgoing
+            .iter()
+            .filter(|(source, links)| !is_within(source, &dir) && links.iter().any(|link| old.contains(&link.target)))
+            .map(|(source, _)| source.clone())
+            .collect();
+
+        self.files.retain(|f| !is_within(f, &dir));
+        for path in &old {
+            self.outgoing.remove(path);
+        }
+        self.files.extend(new.iter().cloned());
+        for path in new.iter().chain(&linking) {
+            self.index_file(path);
+        }
+        true
+    }
+
+    pub fn backlinks(&self, path: &str) -> Vec<Backlink> {
+        let mut backlinks: Vec<Backlink> = self
+            .outgoing
+            .iter()
+            .flat_map(|(source, links)| {
+                links.iter().filter(|link| link.target == path).map(|link| Backlink {
+                    source: source.clone(),
+                    line: link.line,
+                    snippet: link.snippet.clone(),
+                })
+            })
+            .collect();
+        backlinks.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)));
+        backlinks
+    }
+
+    pub fn graph(&self) -> LinkGraph {
+        let edges: BTreeSet<(&str, &str)> = self
+            .outgoing
+            .iter()
+            .flat_map(|(source, links)| links.iter().map(move |link| (source.as_str(), link.target.as_str())))
+            .collect();
+
+        let nodes = self
+            .files
+            .iter()
+            .map(|path| {
+                let incoming = edges.iter().filter(|(_, target)| target == path).count();
+                let outgoing = edges.iter().filter(|(source, _)| source == path).count();
+                GraphNode {
+                    path: path.clone(),
+                    name: Path::new(path)
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    incoming,
+                    outgoing,
+                    orphan: incoming == 0 && outgoing == 0,
+                }
+            })
+            .collect();
+
+        LinkGraph {
+            nodes,
+            edges: edges
+                .into_iter()
+                .map(|(source, target)| GraphEdge {
+                    source: source.to_string(),
+                    target: target.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A fresh project folder under the system temp dir with the given files.
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("codedocs-link-index-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    fn path(root: &Path, relative: &str) -> String {
+        root.join(relative).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn backlinks_list_each_linking_line() {
+        let root = project(
+            "backlinks",
+            &[
+                ("a.md", "Ver [b](b.md) y [[c]].\n\n![img](b.md)\n\nOtra vez [b](./b.md#uso)\n"),
+                ("b.md", "# B\n\n[a](a.md) [yo](#b) [fuera](../x.md)\n"),
+                ("notas/c.md", "Sin enlaces\n"),
+            ],
+        );
+        let index = LinkIndex::build(&root.to_string_lossy());
+
+        let backlinks = index.backlinks(&path(&root, "b.md"));
+        let lines: Vec<(String, usize, &str)> =
+            backlinks.iter().map(|b| (b.source.clone(), b.line, b.snippet.as_str())).collect();
+        assert_eq!(
+            lines,
+            [
+                (path(&root, "a.md"), 1, "Ver [b](b.md) y [[c]]."),
+                (path(&root, "a.md"), 5, "Otra vez [b](./b.md#uso)"),
+            ]
+        );
+        assert_eq!(index.backlinks(&path(&root, "notas/c.md")).len(), 1);
+        assert_eq!(index.backlinks(&path(&root, "a.md")).len(), 1);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn the_graph_has_one_edge_per_linked_pair_and_marks_orphans() {
+        let root = project(
+            "graph",
+            &[("a.md", "[b](b.md) [otra](b.md)\n"), ("b.md", "[a](a.md)\n"), ("solo.md", "Nada\n")],
+        );
+        let graph = LinkIndex::build(&root.to_string_lossy()).graph();
+
+        assert_eq!(graph.edges.len(), 2);
+        let node = |name: &str| graph.nodes.iter().find(|n| n.name == name).unwrap();
+        assert_eq!((node("a").incoming, node("a").outgoing, node("a").orphan), (1, 1, false));
+        assert!(node("solo").orphan);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn updates_follow_edits_and_renamed_folders() {
+        let root = project("updates", &[("a.md", "[c](notas/c.md)\n"), ("notas/c.md", "C\n")]);
+        let mut index = LinkIndex::build(&root.to_string_lossy());
+        assert_eq!(index.backlinks(&path(&root, "notas/c.md")).len(), 1);
+
+        fs::write(root.join("a.md"), "Sin enlaces\n").unwrap();
+        index.update_file(&root.join("a.md"));
+        assert!(index.backlinks(&path(&root, "notas/c.md")).is_empty());
+
+        fs::write(root.join("a.md"), "[c](notas/c.md)\n").unwrap();
+        index.update_file(&root.join("a.md"));
+        fs::rename(root.join("notas"), root.join("docs")).unwrap();
+        assert!(index.update_folder(&root.join("notas")));
+        assert!(index.update_folder(&root.join("docs")));
+        assert!(!index.update_folder(&root.join("vacia")));
+        assert!(!index.files.contains(&path(&root, "notas/c.md")));
+        // The documents that linked into the folder are read again.
+        assert!(index.outgoing[&path(&root, "a.md")].is_empty());
+
+        fs::write(root.join("a.md"), "[c](docs/c.md)\n").unwrap();
+        index.update_file(&root.join("a.md"));
+        assert_eq!(index.backlinks(&path(&root, "docs/c.md")).len(), 1);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,293 @@
+//! The document as the blocks the PDF lays out, from pulldown-cmark's events.
+
+use std::collections::HashMap;
+
+use codedocs_md::options;
+use pulldown_cmark::{Alignment, Event, LinkType, Parser, Tag, TagEnd};
+
+use super::fonts::Font;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strike: bool,
+    /// External URL or `#anchor` the text links to.
+    pub link: Option<String>,
+    /// Source of the image this span stands for; its text is the alt text.
+    pub image: Option<String>,
+}
+
+impl Span {
+    pub fn font(&self) -> Font {
+        if self.code {
+            Font::Mono
+        } else {
+            Font::styled(self.bold, self.italic)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading { level: u8, spans: Vec<Span> },
+    Paragraph(Vec<Span>),
+    Code(String),
+    Quote(Vec<Block>),
+    List { start: Option<u64>, items: Vec<Item> },
+    /// The first row is the header.
+    Table { alignments: Vec<Alignment>, rows: Vec<Vec<Vec<Span>>> },
+    Image { src: String, alt: String },
+    Rule,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Item {
+    pub task: Option<bool>,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footnote {
+    pub number: usize,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+    /// Footnotes in the order of their numbers, shown after the document.
+    pub footnotes: Vec<Footnote>,
+}
+
+/// Containers whose blocks are still being collected.
+enum Frame {
+    Blocks(Vec<Block>),
+    Quote(Vec<Block>),
+    List { start: Option<u64>, items: Vec<Item> },
+    Item(Item),
+    Footnote { number: usize, blocks: Vec<Block> },
+    Table { alignments: Vec<Alignment>, rows: Vec<Vec<Vec<Span>>>, row: Vec<Vec<Span>> },
+}
+
+#[derive(Default)]
+struct Builder {
+    stack: Vec<Frame>,
+    spans: Vec<Span>,
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    links: Vec<Option<String>>,
+    image: Option<String>,
+    code: Option<String>,
+    in_metadata: bool,
+    footnotes: HashMap<String, usize>,
+    done_footnotes: Vec<Footnote>,
+}
+
+/// Only links a PDF can follow are kept: web and mail addresses, and anchors
+/// to headings of the same document.
+fn pdf_link(link_type: LinkType, url: &str) -> Option<String> {
+    let external = ["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme));
+    (!matches!(link_type, LinkType::WikiLink { .. }) && (external || url.starts_with('#'))).then(|| url.to_string())
+}
+
+impl Builder {
+    fn push_block(&mut self, block: Block) {
+        match self.stack.last_mut() {
+            Some(Frame::Blocks(blocks) | Frame::Quote(blocks) | Frame::Footnote { blocks, .. }) => blocks.push(block),
+            Some(Frame::Item(item)) => item.blocks.push(block),
+            _ => {}
+        }
+    }
+
+    /// Text of tight list items comes without a paragraph around it.
+    fn flush_spans(&mut self) {
+        if self.spans.iter().any(|span| !span.text.trim().is_empty() || span.image.is_some()) {
+            let spans = std::mem::take(&mut self.spans);
+            self.push_paragraph(spans);
+        }
+        self.spans.clear();
+    }
+
+    /// A paragraph of nothing but images becomes the images themselves.
+    fn push_paragraph(&mut self, spans: Vec<Span>) {
+        let only_images = spans.iter().all(|span| span.image.is_some() || span.text.trim().is_empty())
+            && spans.iter().any(|span| span.image.is_some());
+        if only_images {
+            for span in spans {
+                if let Some(src) = span.image {
+                    self.push_block(Block::Image { src, alt: span.text });
+                }
+            }
+        } else {
+            self.push_block(Block::Paragraph(spans));
+        }
+    }
+
+    fn push_text(&mut self, text: &str, code: bool) {
+        if let Some(src) = &self.image {
+            // Alt text collects into the image's own span.
+            if let Some(span) = self.spans.last_mut().filter(|span| span.image.as_ref() == Some(src)) {
+                span.text.push_str(text);
+            }
+            return;
+        }
+        self.spans.push(Span {
+            text: text.to_string(),
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            code,
+            strike: self.strike > 0,
+            link: self.links.iter().rev().find_map(Clone::clone),
+            image: None,
+        });
+    }
+
+    fn footnote_number(&mut self, label: &str) -> usize {
+        let next = self.footnotes.len() + 1;
+        *self.footnotes.entry(label.to_string()).or_insert(next)
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {}
+            Tag::Heading { .. } | Tag::BlockQuote(_) | Tag::CodeBlock(_) | Tag::List(_) | Tag::Table(_) => {
+                self.flush_spans();
+                match tag {
+                    Tag::BlockQuote(_) => self.stack.push(Frame::Quote(Vec::new())),
+                    Tag::CodeBlock(_) => self.code = Some(String::new()),
+                    Tag::List(start) => self.stack.push(Frame::List { start, items: Vec::new() }),
+                    Tag::Table(alignments) => {
+                        self.stack.push(Frame::Table { alignments, rows: Vec::new(), row: Vec::new() })
+                    }
+                    _ => {}
+                }
+            }
+            Tag::Item => self.stack.push(Frame::Item(Item::default())),
+            Tag::FootnoteDefinition(label) => {
+                let number = self.footnote_number(&label);
+                self.stack.push(Frame::Footnote { number, blocks: Vec::new() });
+            }
+            Tag::TableHead | Tag::TableRow | Tag::TableCell => self.spans.clear(),
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { link_type, dest_url, .. } => self.links.push(pdf_link(link_type, &dest_url)),
+            Tag::Image { dest_url, .. } => {
+                self.spans.push(Span { image: Some(dest_url.to_string()), italic: true, ..Span::default() });
+                self.image = Some(dest_url.to_string());
+            }
+            Tag::MetadataBlock(_) => self.in_metadata = true,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                let spans = std::mem::take(&mut self.spans);
+                self.push_paragraph(spans);
+            }
+            TagEnd::Heading(level) => {
+                let spans = std::mem::take(&mut self.spans);
+                self.push_block(Block::Heading { level: level as u8, spans });
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code.take().unwrap_or_default();
+                self.push_block(Block::Code(code.strip_suffix('\n').unwrap_or(&code).to_string()));
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_spans();
+                if let Some(Frame::Quote(blocks)) = self.stack.pop() {
+                    self.push_block(Block::Quote(blocks));
+                }
+            }
+            TagEnd::Item => {
+                self.flush_spans();
+                if let Some(Frame::Item(item)) = self.stack.pop() {
+                    if let Some(Frame::List { items, .. }) = self.stack.last_mut() {
+                        items.push(item);
+                    }
+                }
+            }
+            TagEnd::List(_) => {
+                if let Some(Frame::List { start, items }) = self.stack.pop() {
+                    self.push_block(Block::List { start, items });
+                }
+            }
+            TagEnd::FootnoteDefinition => {
+                self.flush_spans();
+                if let Some(Frame::Footnote { number, blocks }) = self.stack.pop() {
+                    self.done_footnotes.push(Footnote { number, blocks });
+                }
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.spans);
+                if let Some(Frame::Table { row, .. }) = self.stack.last_mut() {
+                    row.push(cell);
+                }
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if let Some(Frame::Table { rows, row, .. }) = self.stack.last_mut() {
+                    rows.push(std::mem::take(row));
+                }
+            }
+            TagEnd::Table => {
+                if let Some(Frame::Table { alignments, rows, .. }) = self.stack.pop() {
+                    self.push_block(Block::Table { alignments, rows });
+                }
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.strike = self.strike.saturating_sub(1),
+            TagEnd::Link => {
+                self.links.pop();
+            }
+            TagEnd::Image => self.image = None,
+            TagEnd::MetadataBlock(_) => self.in_metadata = false,
+            _ => {}
+        }
+    }
+}
+
+/// The blocks of a markdown document. Raw HTML is left out; math and
+/// diagrams keep their source.
+pub fn parse(content: &str) -> Document {
+    let mut builder = Builder { stack: vec![Frame::Blocks(Vec::new())], ..Builder::default() };
+    for event in Parser::new_ext(content, options()) {
+        match event {
+            Event::Start(tag) => builder.start(tag),
+            Event::End(tag) => builder.end(tag),
+            Event::Text(_) if builder.in_metadata => {}
+            Event::Text(text) => match &mut builder.code {
+                Some(code) => code.push_str(&text),
+                None => builder.push_text(&text, false),
+            },
+            Event::Code(code) => builder.push_text(&code, true),
+            Event::SoftBreak => builder.push_text(" ", false),
+            Event::HardBreak => builder.push_text("\n", false),
+            Event::FootnoteReference(label) => {
+                let number = builder.footnote_number(&label);
+                builder.push_text(&format!("[{}]", number), false);
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(Frame::Item(item)) = builder.stack.last_mut() {
+                    item.task = Some(checked);
+                }
+            }
+            Event::Rule => builder.push_block(Block::Rule),
+            _ => {}
+        }
+    }
+
+    let blocks = match builder.stack.into_iter().next() {
+        Some(Frame::Blocks(blocks)) => blocks,
+        _ => Vec::new(),
+    };
+    let mut footnotes = builder.done_footnotes;
+    footnotes.sort_by_key(|footnote| footnote.number);
+    Document { blocks, footnotes }
+}
@@ -0,0 +1,140 @@
+//! The standard Type 1 fonts every PDF reader has, so nothing needs
+//! embedding: Helvetica for text and Courier for code, in WinAnsiEncoding.
+
+/// Glyph widths of Helvetica for the WinAnsi codes 32 to 255, in thousandths
+/// of the font size. The oblique variants share them.
+const HELVETICA: [u16; 224] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, 350,
+    556, 350, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 350, 611, 350,
+    350, 222, 222, 333, 333, 350, 556, 1000, 333, 1000, 500, 333, 944, 350, 500, 667,
+    278, 333, 556, 556, 556, 556, 260, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    400, 584, 333, 333, 333, 556, 537, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    667, 667, 667, 667, 667, 667, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+
+const HELVETICA_BOLD: [u16; 224] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584, 350,
+    556, 350, 278, 556, 500, 1000, 556, 556, 333, 1000, 667, 333, 1000, 350, 611, 350,
+    350, 278, 278, 500, 500, 350, 556, 1000, 333, 1000, 556, 333, 944, 350, 500, 667,
+    278, 333, 556, 556, 556, 556, 280, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    400, 584, 333, 333, 333, 611, 556, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    722, 722, 722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    556, 556, 556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278,
+    611, 611, 611, 611, 611, 611, 611, 584, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+/// Every Courier glyph is this wide.
+const COURIER: u16 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Font {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+}
+
+impl Font {
+    pub const ALL: [Font; 5] = [Font::Regular, Font::Bold, Font::Italic, Font::BoldItalic, Font::Mono];
+
+    pub fn base_name(self) -> &'static [u8] {
+        match self {
+            Font::Regular => b"Helvetica",
+            Font::Bold => b"Helvetica-Bold",
+            Font::Italic => b"Helvetica-Oblique",
+            Font::BoldItalic => b"Helvetica-BoldOblique",
+            Font::Mono => b"Courier",
+        }
+    }
+
+    /// Name of the font in the page resources.
+    pub fn resource_name(self) -> &'static [u8] {
+        match self {
+            Font::Regular => b"F1",
+            Font::Bold => b"F2",
+            Font::Italic => b"F3",
+            Font::BoldItalic => b"F4",
+            Font::Mono => b"F5",
+        }
+    }
+
+    pub fn styled(bold: bool, italic: bool) -> Font {
+        match (bold, italic) {
+            (false, false) => Font::Regular,
+            (true, false) => Font::Bold,
+            (false, true) => Font::Italic,
+            (true, true) => Font::BoldItalic,
+        }
+    }
+
+    fn glyph_width(self, code: u8) -> u16 {
+        let index = usize::from(code.max(32) - 32);
+        match self {
+            Font::Regular | Font::Italic => HELVETICA[index],
+            Font::Bold | Font::BoldItalic => HELVETICA_BOLD[index],
+            Font::Mono => COURIER,
+        }
+    }
+
+    /// Width of `text` at `size` points.
+    pub fn width(self, text: &str, size: f32) -> f32 {
+        let units: u32 = text.chars().map(|c| u32::from(self.glyph_width(win_ansi(c)))).sum();
+        units as f32 * size / 1000.0
+    }
+}
+
+/// The WinAnsi code of a character; what the encoding lacks becomes `?`.
+pub fn win_ansi(c: char) -> u8 {
+    match c {
+        '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as u8,
+        '€' => 128,
+        '‚' => 130,
+        'ƒ' => 131,
+        '„' => 132,
+        '…' => 133,
+        '†' => 134,
+        '‡' => 135,
+        'ˆ' => 136,
+        '‰' => 137,
+        'Š' => 138,
+        '‹' => 139,
+        'Œ' => 140,
+        'Ž' => 142,
+        '‘' => 145,
+        '’' => 146,
+        '“' => 147,
+        '”' => 148,
+        '•' => 149,
+        '–' => 150,
+        '—' => 151,
+        '˜' => 152,
+        '™' => 153,
+        'š' => 154,
+        '›' => 155,
+        'œ' => 156,
+        'ž' => 158,
+        'Ÿ' => 159,
+        '\t' => b' ',
+        _ => b'?',
+    }
+}
+
+/// `text` as the bytes a WinAnsi-encoded string shows it with.
+pub fn encode(text: &str) -> Vec<u8> {
+    text.chars().map(win_ansi).collect()
+}
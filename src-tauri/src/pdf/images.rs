@@ -0,0 +1,51 @@
+//! Local images in the forms a PDF can embed.
+
+use std::fs;
+
+use image::{ColorType, ImageDecoder, ImageFormat, ImageReader};
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageData {
+    /// A JPEG file as is.
+    Jpeg { bytes: Vec<u8>, gray: bool },
+    /// Compressed RGB samples and, for images with transparency, their alpha.
+    Pixels { rgb: Vec<u8>, alpha: Option<Vec<u8>> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: ImageData,
+}
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    compress_to_vec_zlib(data, 6)
+}
+
+/// The image at `path`, or `None` if it is missing or in a format that
+/// cannot be decoded (SVG among them).
+pub fn load_image(path: &str) -> Option<PdfImage> {
+    let reader = ImageReader::open(path).ok()?.with_guessed_format().ok()?;
+    if reader.format() == Some(ImageFormat::Jpeg) {
+        let decoder = reader.into_decoder().ok()?;
+        let (width, height) = decoder.dimensions();
+        let gray = match decoder.color_type() {
+            ColorType::L8 => true,
+            ColorType::Rgb8 => false,
+            _ => return None,
+        };
+        let bytes = fs::read(path).ok()?;
+        return Some(PdfImage { width, height, data: ImageData::Jpeg { bytes, gray } });
+    }
+
+    let image = reader.decode().ok()?;
+    let (width, height) = (image.width(), image.height());
+    let alpha = image.color().has_alpha().then(|| {
+        let samples: Vec<u8> = image.to_rgba8().pixels().map(|pixel| pixel[3]).collect();
+        compress(&samples)
+    });
+    let rgb = compress(image.to_rgb8().as_raw());
+    Some(PdfImage { width, height, data: ImageData::Pixels { rgb, alpha } })
+}
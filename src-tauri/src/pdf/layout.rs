@@ -0,0 +1,677 @@
+//! Blocks placed on pages: where each piece of text, rule and image goes.
+//! Coordinates are PDF points with the origin at the bottom left.
+
+use std::collections::HashMap;
+
+use codedocs_md::links::{is_relative, percent_decode, resolve_path};
+use codedocs_md::pdf::{page_text, Cover, PdfConfig};
+use pulldown_cmark::Alignment;
+
+use super::blocks::{Block, Document, Item, Span};
+use super::fonts::Font;
+use super::images::{load_image, PdfImage};
+
+pub type Color = (f32, f32, f32);
+
+const TEXT: Color = (0.184, 0.184, 0.184);
+const MUTED: Color = (0.373, 0.376, 0.376);
+const FAINT: Color = (0.639, 0.643, 0.643);
+const SURFACE: Color = (0.957, 0.961, 0.961);
+const BORDER: Color = (0.902, 0.906, 0.906);
+const ORANGE: Color = (1.0, 0.718, 0.302);
+
+/// Line height as a multiple of the font size.
+const LEADING: f32 = 1.45;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    Text { x: f32, y: f32, font: Font, size: f32, color: Color, text: String },
+    Rect { x: f32, y: f32, width: f32, height: f32, color: Color },
+    Line { from: (f32, f32), to: (f32, f32), width: f32, color: Color },
+    Image { image: usize, x: f32, y: f32, width: f32, height: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Url(String),
+    /// A heading of the document, by page and height.
+    Position(usize, f32),
+}
+
+/// Left, bottom, right and top.
+pub type Area = (f32, f32, f32, f32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub rect: Area,
+    pub target: Target,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Page {
+    pub ops: Vec<Op>,
+    pub links: Vec<Link>,
+}
+
+/// An entry of the PDF outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub level: u8,
+    pub title: String,
+    pub page: usize,
+    pub top: f32,
+}
+
+pub struct Laid {
+    pub width: f32,
+    pub height: f32,
+    pub pages: Vec<Page>,
+    pub bookmarks: Vec<Bookmark>,
+    pub images: Vec<PdfImage>,
+}
+
+/// A run of text in one style, as placed on a line.
+#[derive(Debug, Clone, PartialEq)]
+struct Piece {
+    text: String,
+    font: Font,
+    size: f32,
+    color: Color,
+    code: bool,
+    strike: bool,
+    link: Option<String>,
+}
+
+impl Piece {
+    fn width(&self) -> f32 {
+        self.font.width(&self.text, self.size)
+    }
+
+    fn same_style(&self, other: &Piece) -> bool {
+        self.font == other.font
+            && self.size == other.size
+            && self.color == other.color
+            && self.code == other.code
+            && self.strike == other.strike
+            && self.link == other.link
+    }
+}
+
+fn pieces(spans: &[Span], size: f32, color: Color) -> Vec<Piece> {
+    let mut out = Vec::new();
+    for span in spans {
+        let piece = |text: &str| Piece {
+            text: text.to_string(),
+            font: span.font(),
+            size: if span.code { size * 0.9 } else { size },
+            color: if span.link.is_some() { TEXT } else { color },
+            code: span.code,
+            strike: span.strike,
+            link: span.link.clone(),
+        };
+        // Words keep the spaces after them; line breaks stand alone.
+        let mut word = String::new();
+        let mut in_space = false;
+        for c in span.text.chars() {
+            if c == '\n' {
+                if !word.is_empty() {
+                    out.push(piece(&word));
+                }
+                out.push(piece("\n"));
+                word.clear();
+                in_space = false;
+                continue;
+            }
+            let is_space = c.is_whitespace();
+            if in_space && !is_space {
+                out.push(piece(&word));
+                word.clear();
+            }
+            in_space = is_space;
+            word.push(if is_space { ' ' } else { c });
+        }
+        if !word.is_empty() {
+            out.push(piece(&word));
+        }
+    }
+    out
+}
+
+fn push_piece(line: &mut Vec<Piece>, piece: Piece) {
+    match line.last_mut() {
+        Some(last) if last.same_style(&piece) => last.text.push_str(&piece.text),
+        _ => line.push(piece),
+    }
+}
+
+/// Splits a word wider than a line into chunks that fit.
+fn split_word(piece: Piece, max: f32) -> Vec<Piece> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    for c in piece.text.chars() {
+        chunk.push(c);
+        if piece.font.width(&chunk, piece.size) > max && chunk.chars().count() > 1 {
+            chunk.pop();
+            chunks.push(Piece { text: std::mem::take(&mut chunk), ..piece.clone() });
+            chunk.push(c);
+        }
+    }
+    chunks.push(Piece { text: chunk, ..piece });
+    chunks
+}
+
+/// Greedy line breaking at spaces.
+fn break_lines(pieces: Vec<Piece>, max: f32) -> Vec<Vec<Piece>> {
+    let mut lines = Vec::new();
+    let mut line: Vec<Piece> = Vec::new();
+    let mut width = 0.0;
+    for piece in pieces {
+        if piece.text == "\n" {
+            lines.push(std::mem::take(&mut line));
+            width = 0.0;
+            continue;
+        }
+        let word = piece.font.width(piece.text.trim_end(), piece.size);
+        if width + word > max && !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+            width = 0.0;
+        }
+        if line.is_empty() && piece.text.trim().is_empty() {
+            continue;
+        }
+        let parts = if word > max { split_word(piece, max) } else { vec![piece] };
+        let count = parts.len();
+        for (ix, part) in parts.into_iter().enumerate() {
+            width += part.width();
+            push_piece(&mut line, part);
+            if ix + 1 < count {
+                lines.push(std::mem::take(&mut line));
+                width = 0.0;
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn line_width(line: &[Piece]) -> f32 {
+    let trimmed = line.last().map_or(0.0, |last| last.width() - last.font.width(last.text.trim_end(), last.size));
+    line.iter().map(Piece::width).sum::<f32>() - trimmed
+}
+
+fn plain_text(spans: &[Span]) -> String {
+    spans.iter().map(|span| span.text.as_str()).collect::<String>().trim().to_string()
+}
+
+struct Layouter<'a> {
+    config: &'a PdfConfig,
+    document_dir: &'a str,
+    width: f32,
+    height: f32,
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+    size: f32,
+    color: Color,
+    /// Distance from the left margin where blocks start.
+    indent: f32,
+    /// Where the bars of the enclosing block quotes go.
+    bars: Vec<f32>,
+    /// List marker waiting for the first line of its item.
+    marker: Option<(String, Font, f32)>,
+    y: f32,
+    pages: Vec<Page>,
+    bookmarks: Vec<Bookmark>,
+    images: Vec<PdfImage>,
+    /// Slugs of the headings in document order, as the preview gives them.
+    slugs: std::vec::IntoIter<String>,
+    anchors: HashMap<String, (usize, f32)>,
+    /// Links by page, resolved once every heading has its place.
+    pending_links: Vec<(usize, Area, String)>,
+}
+
+impl Layouter<'_> {
+    fn page(&mut self) -> &mut Page {
+        self.pages.last_mut().expect("at least one page")
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Page::default());
+        self.y = self.top;
+    }
+
+    fn at_top(&self) -> bool {
+        self.y >= self.top
+    }
+
+    /// Starts a new page unless `height` still fits on this one.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < self.bottom && !self.at_top() {
+            self.new_page();
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        if !self.at_top() {
+            self.y = (self.y - height).max(self.bottom);
+        }
+    }
+
+    fn x(&self) -> f32 {
+        self.left + self.indent
+    }
+
+    fn available(&self) -> f32 {
+        self.right - self.x()
+    }
+
+    fn text(&mut self, x: f32, y: f32, font: Font, size: f32, color: Color, text: &str) {
+        if !text.trim().is_empty() {
+            let text = text.to_string();
+            self.page().ops.push(Op::Text { x, y, font, size, color, text });
+        }
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.page().ops.push(Op::Rect { x, y, width, height, color });
+    }
+
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: Color) {
+        self.page().ops.push(Op::Line { from, to, width, color });
+    }
+
+    /// Draws the quote bars and any pending list marker beside a line that
+    /// spans from `self.y` down by `height`.
+    fn decorate(&mut self, height: f32, baseline: f32) {
+        let (top, bars) = (self.y, self.bars.clone());
+        for x in bars {
+            self.line((x, top), (x, top - height), 2.0, ORANGE);
+        }
+        if let Some((marker, font, size)) = self.marker.take() {
+            let x = self.x() - font.width(&marker, size) - self.size * 0.4;
+            self.text(x, baseline, font, size, self.color, &marker);
+        }
+    }
+
+    fn draw_line(&mut self, line: &[Piece], x: f32, baseline: f32, height: f32) {
+        let mut x = x;
+        for piece in line {
+            let width = piece.width();
+            if piece.code {
+                self.rect(x - 1.0, baseline - piece.size * 0.3, width + 2.0, piece.size * 1.25, SURFACE);
+            }
+            self.text(x, baseline, piece.font, piece.size, piece.color, &piece.text);
+            let visible = piece.font.width(piece.text.trim_end(), piece.size);
+            if piece.strike {
+                self.line((x, baseline + piece.size * 0.3), (x + visible, baseline + piece.size * 0.3), 0.6, piece.color);
+            }
+            if let Some(link) = &piece.link {
+                self.line((x, baseline - 1.5), (x + visible, baseline - 1.5), 0.6, ORANGE);
+                let rect = (x, baseline - height * 0.3, x + visible, baseline + height * 0.7);
+                let page = self.pages.len() - 1;
+                self.pending_links.push((page, rect, link.clone()));
+            }
+            x += width;
+        }
+    }
+
+    /// Lays out text over as many lines and pages as it needs.
+    fn flow(&mut self, spans: &[Span], size: f32, align: Alignment) {
+        let lines = break_lines(pieces(spans, size, self.color), self.available());
+        let height = size * LEADING;
+        for line in lines {
+            self.ensure(height);
+            let baseline = self.y - height * 0.5 - size * 0.3;
+            self.decorate(height, baseline);
+            let x = match align {
+                Alignment::Center => self.x() + (self.available() - line_width(&line)) / 2.0,
+                Alignment::Right => self.right - line_width(&line),
+                _ => self.x(),
+            };
+            self.draw_line(&line, x, baseline, height);
+            self.y -= height;
+        }
+    }
+
+    fn heading(&mut self, level: u8, spans: &[Span]) {
+        let scale = match level {
+            1 => 2.0,
+            2 => 1.6,
+            3 => 1.3,
+            4 => 1.15,
+            _ => 1.0,
+        };
+        let size = self.size * scale;
+        if level == 1 && self.config.break_before_h1 && !self.at_top() {
+            self.new_page();
+        }
+        self.gap(size * 0.8);
+        // Keep the heading with the start of what follows it.
+        self.ensure(size * LEADING + self.size * LEADING * 3.0);
+
+        let page = self.pages.len() - 1;
+        if let Some(slug) = self.slugs.next() {
+            self.anchors.entry(slug).or_insert((page, self.y));
+        }
+        self.bookmarks.push(Bookmark { level, title: plain_text(spans), page, top: self.y });
+
+        let bold: Vec<Span> = spans.iter().map(|span| Span { bold: true, ..span.clone() }).collect();
+        self.flow(&bold, size, Alignment::None);
+        if level <= 2 {
+            let y = self.y - size * 0.15;
+            let (x, right) = (self.x(), self.right);
+            self.line((x, y), (right, y), if level == 1 { 1.0 } else { 0.5 }, BORDER);
+            self.y -= size * 0.3;
+        }
+        self.gap(self.size * 0.4);
+    }
+
+    fn code(&mut self, code: &str) {
+        let size = self.size * 0.85;
+        let height = size * LEADING;
+        let padding = size * 0.8;
+        let columns = ((self.available() - padding * 2.0) / Font::Mono.width(" ", size)).max(1.0) as usize;
+
+        self.ensure(padding + height);
+        let (x, width) = (self.x(), self.available());
+        self.rect(x, self.y - padding, width, padding, SURFACE);
+        self.y -= padding;
+        for source_line in code.split('\n') {
+            let chars: Vec<char> = source_line.replace('\t', "    ").chars().collect();
+            let chunks: Vec<String> = if chars.is_empty() {
+                vec![String::new()]
+            } else {
+                chars.chunks(columns).map(|chunk| chunk.iter().collect()).collect()
+            };
+            for chunk in chunks {
+                if self.y - height < self.bottom {
+                    self.new_page();
+                }
+                let baseline = self.y - height * 0.5 - size * 0.3;
+                self.rect(x, self.y - height, width, height, SURFACE);
+                self.decorate(height, baseline);
+                self.text(x + padding, baseline, Font::Mono, size, TEXT, &chunk);
+                self.y -= height;
+            }
+        }
+        let bottom_padding = padding.min(self.y - self.bottom);
+        self.rect(x, self.y - bottom_padding, width, bottom_padding, SURFACE);
+        self.y -= bottom_padding;
+        self.gap(self.size * 0.8);
+    }
+
+    fn quote(&mut self, blocks: &[Block]) {
+        self.bars.push(self.x() + 1.0);
+        self.indent += self.size * 1.2;
+        let color = std::mem::replace(&mut self.color, MUTED);
+        self.blocks(blocks);
+        self.color = color;
+        self.indent -= self.size * 1.2;
+        self.bars.pop();
+    }
+
+    fn list(&mut self, start: Option<u64>, items: &[Item]) {
+        let indent = self.size * 1.6;
+        self.indent += indent;
+        for (ix, item) in items.iter().enumerate() {
+            let marker = match (item.task, start) {
+                (Some(true), _) => ("[x]".to_string(), Font::Mono),
+                (Some(false), _) => ("[ ]".to_string(), Font::Mono),
+                (None, Some(start)) => (format!("{}.", start + ix as u64), Font::Regular),
+                (None, None) => ("•".to_string(), Font::Regular),
+            };
+            self.marker = Some((marker.0, marker.1, self.size));
+            if item.blocks.is_empty() {
+                let height = self.size * LEADING;
+                self.ensure(height);
+                self.decorate(height, self.y - height * 0.5 - self.size * 0.3);
+                self.y -= height;
+            }
+            self.blocks(&item.blocks);
+            self.marker = None;
+        }
+        self.indent -= indent;
+        self.gap(self.size * 0.3);
+    }
+
+    fn table(&mut self, alignments: &[Alignment], rows: &[Vec<Vec<Span>>]) {
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let size = self.size * 0.9;
+        let height = size * LEADING;
+        let padding = size * 0.6;
+        let natural: Vec<f32> = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| pieces(cell, size, self.color).iter().map(Piece::width).sum::<f32>())
+                    .fold(size * 3.0, f32::max)
+                    + padding * 2.0
+            })
+            .collect();
+        let total: f32 = natural.iter().sum();
+        let widths: Vec<f32> = natural.iter().map(|width| self.available() * width / total).collect();
+
+        for (row_ix, row) in rows.iter().enumerate() {
+            let header = row_ix == 0;
+            let cells: Vec<Vec<Vec<Piece>>> = (0..columns)
+                .map(|column| {
+                    let cell = row.get(column).cloned().unwrap_or_default();
+                    let cell: Vec<Span> = cell.into_iter().map(|span| Span { bold: span.bold || header, ..span }).collect();
+                    break_lines(pieces(&cell, size, self.color), widths[column] - padding * 2.0)
+                })
+                .collect();
+            let lines = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+            let row_height = lines as f32 * height + padding * 2.0;
+            self.ensure(row_height);
+
+            let (x0, width) = (self.x(), self.available());
+            if header {
+                self.rect(x0, self.y - row_height, width, row_height, SURFACE);
+            }
+            self.decorate(row_height, self.y - padding - height * 0.5 - size * 0.3);
+            let mut x = x0;
+            for (column, lines) in cells.iter().enumerate() {
+                for (line_ix, line) in lines.iter().enumerate() {
+                    let baseline = self.y - padding - height * line_ix as f32 - height * 0.5 - size * 0.3;
+                    let inner = widths[column] - padding * 2.0;
+                    let offset = match alignments.get(column) {
+                        Some(Alignment::Center) => (inner - line_width(line)) / 2.0,
+                        Some(Alignment::Right) => inner - line_width(line),
+                        _ => 0.0,
+                    };
+                    self.draw_line(line, x + padding + offset, baseline, height);
+                }
+                x += widths[column];
+            }
+            self.y -= row_height;
+            let y = self.y;
+            self.line((x0, y), (x0 + width, y), if header { 1.5 } else { 0.5 }, BORDER);
+        }
+        self.gap(self.size * 0.8);
+    }
+
+    fn image(&mut self, src: &str, alt: &str) {
+        let image = is_relative(src)
+            .then(|| resolve_path(self.document_dir, &percent_decode(src.split(['?', '#']).next().unwrap_or(src))))
+            .and_then(|path| load_image(&path));
+        let Some(image) = image else {
+            let text = if alt.trim().is_empty() { src.to_string() } else { alt.to_string() };
+            let span = Span { text, italic: true, ..Span::default() };
+            let color = std::mem::replace(&mut self.color, FAINT);
+            self.flow(&[span], self.size, Alignment::None);
+            self.color = color;
+            self.gap(self.size * 0.6);
+            return;
+        };
+
+        // Pixels at 96 per inch, shrunk to fit the page.
+        let (pixels_wide, pixels_high) = (image.width as f32 * 0.75, image.height as f32 * 0.75);
+        let scale = (self.available() / pixels_wide).min((self.top - self.bottom) / pixels_high).min(1.0);
+        let (width, height) = (pixels_wide * scale, pixels_high * scale);
+        self.ensure(height);
+        self.decorate(height, self.y - self.size);
+        let index = self.images.len();
+        self.images.push(image);
+        let x = self.x();
+        let y = self.y - height;
+        self.page().ops.push(Op::Image { image: index, x, y, width, height });
+        self.y -= height;
+        self.gap(self.size * 0.8);
+    }
+
+    fn rule(&mut self) {
+        self.gap(self.size * 0.5);
+        self.ensure(self.size);
+        let (x, right, y) = (self.x(), self.right, self.y);
+        self.line((x, y), (right, y), 1.0, BORDER);
+        self.gap(self.size);
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            match block {
+                Block::Heading { level, spans } => self.heading(*level, spans),
+                Block::Paragraph(spans) => {
+                    self.flow(spans, self.size, Alignment::None);
+                    self.gap(self.size * 0.6);
+                }
+                Block::Code(code) => self.code(code),
+                Block::Quote(blocks) => self.quote(blocks),
+                Block::List { start, items } => self.list(*start, items),
+                Block::Table { alignments, rows } => self.table(alignments, rows),
+                Block::Image { src, alt } => self.image(src, alt),
+                Block::Rule => self.rule(),
+            }
+        }
+    }
+
+    fn cover(&mut self, cover: &Cover) {
+        let spans = |text: &str, bold: bool| vec![Span { text: text.to_string(), bold, ..Span::default() }];
+        self.y = self.top - (self.top - self.bottom) * 0.3;
+        self.flow(&spans(&cover.title, true), self.size * 2.4, Alignment::Center);
+        if !cover.subtitle.is_empty() {
+            self.gap(self.size);
+            self.color = MUTED;
+            self.flow(&spans(&cover.subtitle, false), self.size * 1.3, Alignment::Center);
+        }
+        self.gap(self.size * 3.0);
+        self.color = MUTED;
+        for line in [&cover.author, &cover.date] {
+            if !line.is_empty() {
+                self.flow(&spans(line, false), self.size, Alignment::Center);
+            }
+        }
+        self.color = TEXT;
+        self.new_page();
+    }
+
+    fn footnotes(&mut self, document: &Document) {
+        if document.footnotes.is_empty() {
+            return;
+        }
+        self.gap(self.size);
+        self.ensure(self.size * 3.0);
+        let (x, y) = (self.x(), self.y);
+        self.line((x, y), (x + (self.right - x) / 3.0, y), 0.5, BORDER);
+        self.gap(self.size * 0.6);
+
+        let size = self.size;
+        self.size = size * 0.85;
+        self.indent += size * 1.6;
+        for footnote in &document.footnotes {
+            self.marker = Some((format!("{}.", footnote.number), Font::Regular, self.size));
+            self.blocks(&footnote.blocks);
+            self.marker = None;
+        }
+        self.indent -= size * 1.6;
+        self.size = size;
+    }
+
+    /// The header and footer of every page after the cover.
+    fn page_furniture(&mut self, title: &str, first: usize) {
+        let pages = self.pages.len() - first;
+        let size = self.size * 0.8;
+        let (_, _, margin_bottom, _) = self.config.margins.points();
+        let header_y = self.top + (self.height - self.top) / 2.0 - size * 0.3;
+        let footer_y = margin_bottom / 2.0 - size * 0.3;
+        let center = (self.left + self.right) / 2.0;
+        for (ix, page) in self.pages.iter_mut().enumerate().skip(first) {
+            let number = ix - first + 1;
+            for (template, y) in [(&self.config.header, header_y), (&self.config.footer, footer_y)] {
+                let text = page_text(template, title, number, pages);
+                if !text.trim().is_empty() {
+                    let x = center - Font::Regular.width(&text, size) / 2.0;
+                    page.ops.push(Op::Text { x, y, font: Font::Regular, size, color: FAINT, text });
+                }
+            }
+        }
+    }
+}
+
+/// Places the document on pages, after a cover when there is one.
+pub fn layout(
+    document: &Document,
+    title: &str,
+    cover: Option<&Cover>,
+    slugs: Vec<String>,
+    document_dir: &str,
+    config: &PdfConfig,
+) -> Laid {
+    let (width, height) = config.page_size.dimensions();
+    let (top, right, bottom, left) = config.margins.points();
+    let mut layouter = Layouter {
+        config,
+        document_dir,
+        width,
+        height,
+        left,
+        right: width - right,
+        top: height - top,
+        bottom,
+        size: config.font_size.max(4.0),
+        color: TEXT,
+        indent: 0.0,
+        bars: Vec::new(),
+        marker: None,
+        y: height - top,
+        pages: vec![Page::default()],
+        bookmarks: Vec::new(),
+        images: Vec::new(),
+        slugs: slugs.into_iter(),
+        anchors: HashMap::new(),
+        pending_links: Vec::new(),
+    };
+
+    if let Some(cover) = cover {
+        layouter.cover(cover);
+    }
+    let first = layouter.pages.len() - 1;
+    layouter.blocks(&document.blocks);
+    layouter.footnotes(document);
+    layouter.page_furniture(title, first);
+
+    for (page, rect, link) in std::mem::take(&mut layouter.pending_links) {
+        let target = match link.strip_prefix('#') {
+            Some(anchor) => match layouter.anchors.get(&percent_decode(anchor)) {
+                Some(&(page, top)) => Target::Position(page, top),
+                None => continue,
+            },
+            None => Target::Url(link),
+        };
+        layouter.pages[page].links.push(Link { rect, target });
+    }
+
+    Laid {
+        width: layouter.width,
+        height: layouter.height,
+        pages: layouter.pages,
+        bookmarks: layouter.bookmarks,
+        images: layouter.images,
+    }
+}
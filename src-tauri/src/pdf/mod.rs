@@ -0,0 +1,110 @@
+//! PDF export laid out in Rust with the standard PDF fonts, so the result
+//! looks the same whatever webview or printer the system has.
+
+mod blocks;
+mod fonts;
+mod images;
+mod layout;
+mod writer;
+
+use std::fs;
+
+use codedocs_md::config::ProjectConfig;
+use codedocs_md::export::document_title;
+use codedocs_md::extract_headings;
+use codedocs_md::links::parent_dir;
+use codedocs_md::pdf::cover;
+
+/// The document at `path` as a PDF file: a cover from its front matter,
+/// the pages with their header and footer, and an outline of its headings.
+pub fn document_pdf(path: &str, config: &ProjectConfig) -> Result<Vec<u8>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Error al leer el archivo: {}", e))?;
+    let title = document_title(&content, path);
+    let front_matter = cover(&content, path);
+    let slugs = extract_headings(&content).into_iter().map(|heading| heading.slug).collect();
+
+    let laid = layout::layout(
+        &blocks::parse(&content),
+        &title,
+        front_matter.as_ref().filter(|_| config.pdf.cover),
+        slugs,
+        parent_dir(path),
+        &config.pdf,
+    );
+    let author = front_matter.map(|cover| cover.author).unwrap_or_default();
+    Ok(writer::write_pdf(&laid, &title, &author))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::blocks::{parse, Block};
+    use super::fonts::encode;
+    use super::layout::{layout, Laid, Op, Target};
+    use super::writer::write_pdf;
+    use codedocs_md::extract_headings;
+    use codedocs_md::pdf::{Cover, PdfConfig};
+
+    const DOC: &str = "# Primera\n\nVer [la segunda](#segunda) y [la web](https://x.org).[^1]\n\n\
+                       ## Detalle\n\n# Segunda\n\nFin.\n\n[^1]: Una nota.\n";
+
+    fn laid(content: &str, cover: Option<&Cover>) -> Laid {
+        let slugs = extract_headings(content).into_iter().map(|heading| heading.slug).collect();
+        layout(&parse(content), "Guía", cover, slugs, "/docs", &PdfConfig::default())
+    }
+
+    fn texts(laid: &Laid, page: usize) -> Vec<&str> {
+        laid.pages[page]
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Text { text, .. } => Some(text.trim()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocks_keep_only_links_a_pdf_can_follow() {
+        let content = "# T\n\n[w](https://x.org) [d](otro.md) [[wiki]] [a](#t)[^n]\n\n- [x] hecho\n\n[^n]: Nota\n";
+        let document = parse(content);
+        let Block::Paragraph(spans) = &document.blocks[1] else { panic!("{:?}", document.blocks[1]) };
+        let links: Vec<&str> = spans.iter().filter_map(|span| span.link.as_deref()).collect();
+        assert_eq!(links, ["https://x.org", "#t"]);
+        let Block::List { items, .. } = &document.blocks[2] else { panic!("{:?}", document.blocks[2]) };
+        assert_eq!(items[0].task, Some(true));
+        assert_eq!(document.footnotes.len(), 1);
+        assert_eq!(document.footnotes[0].number, 1);
+    }
+
+    #[test]
+    fn top_level_headings_start_pages_with_bookmarks_and_links() {
+        let laid = laid(DOC, None);
+        assert_eq!(laid.pages.len(), 2);
+        let outline: Vec<(u8, &str, usize)> =
+            laid.bookmarks.iter().map(|b| (b.level, b.title.as_str(), b.page)).collect();
+        assert_eq!(outline, [(1, "Primera", 0), (2, "Detalle", 0), (1, "Segunda", 1)]);
+
+        let targets: Vec<&Target> = laid.pages[0].links.iter().map(|link| &link.target).collect();
+        assert!(matches!(targets[..], [Target::Position(1, _), Target::Url(ref url)] if url == "https://x.org"));
+        assert!(texts(&laid, 0).contains(&"Guía") && texts(&laid, 0).contains(&"1 / 2"));
+        assert!(texts(&laid, 1).contains(&"2 / 2"));
+    }
+
+    #[test]
+    fn the_cover_is_left_out_of_the_page_numbers() {
+        let cover = Cover { title: "Guía".to_string(), author: "Ana".to_string(), ..Default::default() };
+        let laid = laid("Texto\n", Some(&cover));
+        assert_eq!(laid.pages.len(), 2);
+        assert!(texts(&laid, 0).contains(&"Ana") && !texts(&laid, 0).contains(&"1 / 1"));
+        assert!(texts(&laid, 1).contains(&"1 / 1"));
+    }
+
+    #[test]
+    fn the_file_has_an_outline_and_winansi_text() {
+        let bytes = write_pdf(&laid(DOC, None), "Guía", "Ana");
+        let find = |needle: &[u8]| bytes.windows(needle.len()).any(|window| window == needle);
+        assert!(bytes.starts_with(b"%PDF-") && find(b"%%EOF"));
+        assert!(find(b"/Outlines") && find(b"/URI") && find(b"/GoTo"));
+        assert_eq!(encode("Año — 5 € ✓"), b"A\xf1o \x97 5 \x80 ?");
+    }
+}
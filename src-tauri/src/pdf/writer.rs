@@ -0,0 +1,209 @@
+//! Laid-out pages as a PDF file.
+
+use pdf_writer::types::{ActionType, AnnotationType, PageMode};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use super::fonts::{encode, Font};
+use super::images::{compress, ImageData};
+use super::layout::{Bookmark, Laid, Op, Target};
+
+/// Where each bookmark sits in the outline tree.
+struct OutlineNode {
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+fn outline_tree(bookmarks: &[Bookmark]) -> (Vec<usize>, Vec<OutlineNode>) {
+    let mut roots = Vec::new();
+    let mut nodes: Vec<OutlineNode> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for (ix, bookmark) in bookmarks.iter().enumerate() {
+        while open.last().is_some_and(|&last| bookmarks[last].level >= bookmark.level) {
+            open.pop();
+        }
+        let parent = open.last().copied();
+        match parent {
+            Some(parent) => nodes[parent].children.push(ix),
+            None => roots.push(ix),
+        }
+        nodes.push(OutlineNode { parent, children: Vec::new() });
+        open.push(ix);
+    }
+    (roots, nodes)
+}
+
+fn descendants(nodes: &[OutlineNode], ix: usize) -> usize {
+    nodes[ix].children.iter().map(|&child| 1 + descendants(nodes, child)).sum()
+}
+
+fn content(ops: &[Op]) -> Vec<u8> {
+    let mut content = Content::new();
+    for op in ops {
+        match op {
+            Op::Text { x, y, font, size, color, text } => {
+                content.begin_text();
+                content.set_fill_rgb(color.0, color.1, color.2);
+                content.set_font(Name(font.resource_name()), *size);
+                content.next_line(*x, *y);
+                content.show(Str(&encode(text)));
+                content.end_text();
+            }
+            Op::Rect { x, y, width, height, color } => {
+                content.set_fill_rgb(color.0, color.1, color.2);
+                content.rect(*x, *y, *width, *height);
+                content.fill_nonzero();
+            }
+            Op::Line { from, to, width, color } => {
+                content.set_stroke_rgb(color.0, color.1, color.2);
+                content.set_line_width(*width);
+                content.move_to(from.0, from.1);
+                content.line_to(to.0, to.1);
+                content.stroke();
+            }
+            Op::Image { image, x, y, width, height } => {
+                content.save_state();
+                content.transform([*width, 0.0, 0.0, *height, *x, *y]);
+                content.x_object(Name(format!("Im{}", image).as_bytes()));
+                content.restore_state();
+            }
+        }
+    }
+    content.finish()
+}
+
+/// The PDF file of the laid-out document, with its outline built from the
+/// bookmarks.
+pub fn write_pdf(laid: &Laid, title: &str, author: &str) -> Vec<u8> {
+    let mut next = Ref::new(1);
+    let catalog_id = next.bump();
+    let page_tree_id = next.bump();
+    let info_id = next.bump();
+    let outline_id = next.bump();
+    let font_ids: Vec<(Font, Ref)> = Font::ALL.iter().map(|&font| (font, next.bump())).collect();
+    let image_ids: Vec<(Ref, Ref)> = laid.images.iter().map(|_| (next.bump(), next.bump())).collect();
+    let page_ids: Vec<(Ref, Ref)> = laid.pages.iter().map(|_| (next.bump(), next.bump())).collect();
+    let item_ids: Vec<Ref> = laid.bookmarks.iter().map(|_| next.bump()).collect();
+
+    let mut pdf = Pdf::new();
+    let mut catalog = pdf.catalog(catalog_id);
+    catalog.pages(page_tree_id);
+    if !laid.bookmarks.is_empty() {
+        catalog.outlines(outline_id).page_mode(PageMode::UseOutlines);
+    }
+    catalog.finish();
+
+    let mut info = pdf.document_info(info_id);
+    info.title(TextStr(title)).creator(TextStr("CodeDocs"));
+    if !author.is_empty() {
+        info.author(TextStr(author));
+    }
+    info.finish();
+
+    pdf.pages(page_tree_id).kids(page_ids.iter().map(|(page, _)| *page)).count(page_ids.len() as i32);
+    for (font, id) in &font_ids {
+        pdf.type1_font(*id).base_font(Name(font.base_name())).encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+
+    for (image, (id, mask_id)) in laid.images.iter().zip(&image_ids) {
+        let (width, height) = (image.width as i32, image.height as i32);
+        match &image.data {
+            ImageData::Jpeg { bytes, gray } => {
+                let mut xobject = pdf.image_xobject(*id, bytes);
+                xobject.filter(Filter::DctDecode);
+                xobject.width(width).height(height).bits_per_component(8);
+                if *gray {
+                    xobject.color_space().device_gray();
+                } else {
+                    xobject.color_space().device_rgb();
+                }
+            }
+            ImageData::Pixels { rgb, alpha } => {
+                let mut xobject = pdf.image_xobject(*id, rgb);
+                xobject.filter(Filter::FlateDecode);
+                xobject.width(width).height(height).bits_per_component(8);
+                xobject.color_space().device_rgb();
+                if alpha.is_some() {
+                    xobject.s_mask(*mask_id);
+                }
+                xobject.finish();
+                if let Some(alpha) = alpha {
+                    let mut mask = pdf.image_xobject(*mask_id, alpha);
+                    mask.filter(Filter::FlateDecode);
+                    mask.width(width).height(height).bits_per_component(8);
+                    mask.color_space().device_gray();
+                }
+            }
+        }
+    }
+
+    let image_names: Vec<String> = (0..laid.images.len()).map(|ix| format!("Im{}", ix)).collect();
+    for (page, (page_id, content_id)) in laid.pages.iter().zip(&page_ids) {
+        let data = compress(&content(&page.ops));
+        pdf.stream(*content_id, &data).filter(Filter::FlateDecode);
+
+        let mut writer = pdf.page(*page_id);
+        writer
+            .parent(page_tree_id)
+            .media_box(Rect::new(0.0, 0.0, laid.width, laid.height))
+            .contents(*content_id);
+        let mut resources = writer.resources();
+        let mut fonts = resources.fonts();
+        for (font, id) in &font_ids {
+            fonts.pair(Name(font.resource_name()), *id);
+        }
+        fonts.finish();
+        let mut xobjects = resources.x_objects();
+        for (name, (id, _)) in image_names.iter().zip(&image_ids) {
+            xobjects.pair(Name(name.as_bytes()), *id);
+        }
+        xobjects.finish();
+        resources.finish();
+
+        if !page.links.is_empty() {
+            let mut annotations = writer.annotations();
+            for link in &page.links {
+                let (left, bottom, right, top) = link.rect;
+                let mut annotation = annotations.push();
+                annotation.subtype(AnnotationType::Link).rect(Rect::new(left, bottom, right, top)).border(0.0, 0.0, 0.0, None);
+                let mut action = annotation.action();
+                match &link.target {
+                    Target::Url(url) => {
+                        action.action_type(ActionType::Uri).uri(Str(url.as_bytes()));
+                    }
+                    Target::Position(page, top) => {
+                        action.action_type(ActionType::GoTo).destination().page(page_ids[*page].0).xyz(0.0, *top, None);
+                    }
+                }
+            }
+        }
+    }
+
+    if !laid.bookmarks.is_empty() {
+        let (roots, nodes) = outline_tree(&laid.bookmarks);
+        let mut outline = pdf.outline(outline_id);
+        outline.first(item_ids[roots[0]]).last(item_ids[*roots.last().unwrap_or(&0)]).count(laid.bookmarks.len() as i32);
+        outline.finish();
+
+        for (ix, bookmark) in laid.bookmarks.iter().enumerate() {
+            let node = &nodes[ix];
+            let siblings = node.parent.map_or(&roots, |parent| &nodes[parent].children);
+            let position = siblings.iter().position(|&sibling| sibling == ix).unwrap_or(0);
+
+            let mut item = pdf.outline_item(item_ids[ix]);
+            item.title(TextStr(&bookmark.title));
+            item.parent(node.parent.map_or(outline_id, |parent| item_ids[parent]));
+            if position > 0 {
+                item.prev(item_ids[siblings[position - 1]]);
+            }
+            if let Some(&next) = siblings.get(position + 1) {
+                item.next(item_ids[next]);
+            }
+            if let (Some(&first), Some(&last)) = (node.children.first(), node.children.last()) {
+                item.first(item_ids[first]).last(item_ids[last]).count(descendants(&nodes, ix) as i32);
+            }
+            item.dest().page(page_ids[bookmark.page].0).xyz(0.0, bookmark.top, None);
+        }
+    }
+
+    pdf.finish()
+}
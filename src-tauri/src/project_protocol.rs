@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use codedocs_md::assets::image_mime;
+use codedocs_md::links::{is_within, percent_decode};
+use tauri::http::{header, Request, Response, StatusCode};
+
+/// Scheme the preview loads the project's images through; the frontend
+/// builds its urls with `convertFileSrc(path, "project")`.
+pub const SCHEME: &str = "project";
+
+/// Folder of the open project. Tauri's asset scope can only grow, so files
+/// are served from here instead and opening another project drops access
+/// to the previous one.
+static PROJECT_ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub fn set_root(root: Option<&str>) {
+    if let Ok(mut lock) = PROJECT_ROOT.lock() {
+        *lock = root.and_then(|root| fs::canonicalize(root).ok());
+    }
+}
+
+fn respond(status: StatusCode, mime: &str, body: Vec<u8>) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, mime)
+        .body(body)
+        .unwrap_or_default()
+}
+
+/// The file the request names, if it lies inside the open project.
+pub fn serve(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let uri_path = request.uri().path();
+    let path = percent_decode(uri_path.strip_prefix('/').unwrap_or(uri_path));
+    let root = PROJECT_ROOT.lock().ok().and_then(|lock| lock.clone());
+    let file = fs::canonicalize(&path).ok().filter(|file| {
+        root.as_ref().is_some_and(|root| is_within(&file.to_string_lossy(), &root.to_string_lossy()))
+    });
+    let Some(file) = file else {
+        return respond(StatusCode::FORBIDDEN, "text/plain", Vec::new());
+    };
+    match fs::read(&file) {
+        Ok(bytes) => {
+            let extension = file.extension().unwrap_or_default().to_string_lossy();
+            respond(StatusCode::OK, image_mime(&extension).unwrap_or("application/octet-stream"), bytes)
+        }
+        Err(_) => respond(StatusCode::NOT_FOUND, "text/plain", Vec::new()),
+    }
+}
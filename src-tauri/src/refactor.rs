@@ -0,0 +1,259 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use codedocs_md::edit::{apply_edits, inverse_edits, FileEdits};
+use codedocs_md::refactor::{rename_edits, rename_heading_edits, RefactorEdits, SkippedLink};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::project_markdown_files;
+
+const UNDO_LIMIT: usize = 20;
+
+/// What a refactoring changed, so it can be put back: the moves, and the
+/// edits that revert each file as long as nobody touched it since.
+struct UndoEntry {
+    label: String,
+    moves: Vec<(String, String)>,
+    reverts: Vec<FileEdits>,
+}
+
+static UNDO_JOURNAL: Mutex<Vec<UndoEntry>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenamePlan {
+    pub old_path: String,
+    pub new_path: String,
+    pub edits: Vec<FileEdits>,
+    /// Links to the document that cannot be updated and will break.
+    #[serde(default)]
+    pub skipped: Vec<SkippedLink>,
+}
+
+/// What undoing a refactoring did: its label and the files left as they
+/// are because they changed after it.
+#[derive(Serialize, Debug, Clone)]
+pub struct UndoOutcome {
+    pub label: String,
+    pub kept: Vec<String>,
+}
+
+fn read_documents(root: &str) -> Vec<(String, String)> {
+    project_markdown_files(root)
+        .into_iter()
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            Some((path, content))
+        })
+        .collect()
+}
+
+pub fn plan_rename(root: &str, old_path: &str, new_path: &str) -> Result<RenamePlan, String> {
+    if !Path::new(old_path).is_file() {
+        return Err("El archivo a renombrar no existe".to_string());
+    }
+    if Path::new(new_path).exists() {
+        return Err("Ya existe un archivo con ese nombre".to_string());
+    }
+    let RefactorEdits { edits, skipped } = rename_edits(old_path, new_path, &read_documents(root));
+    Ok(RenamePlan {
+        old_path: old_path.to_string(),
+        new_path: new_path.to_string(),
+        edits,
+        skipped,
+    })
+}
+
+pub fn plan_heading_rename(root: &str, path: &str, line: usize, new_text: &str) -> Result<RefactorEdits, String> {
+    if new_text.trim().is_empty() {
+        return Err("El encabezado no puede quedar vacío".to_string());
+    }
+    let mut documents = read_documents(root);
+    if !documents.iter().any(|(doc, _)| doc == path) {
+        let content = fs::read_to_string(path).map_err(|e| format!("Error al leer el archivo: {}", e))?;
+        documents.push((path.to_string(), content));
+    }
+    let config = crate::commands::load_project_config(root.to_string()).unwrap_or_default();
+    rename_heading_edits(path, line, new_text, &documents, &config.toc)
+        .ok_or_else(|| "No hay un encabezado en esa línea o el nuevo texto lo convertiría en otra cosa".to_string())
+}
+
+/// Writes through a temporary file so a crash never leaves a half-written document.
+fn write_atomic(path: &str, content: &str) -> std::io::Result<()> {
+    let temp = format!("{}.codedocs-tmp", path);
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)
+}
+
+fn restore(originals: &[(String, String)], moves: &[(String, String)]) {
+    for (from, to) in moves.iter().rev() {
+        let _ = fs::rename(to, from);
+    }
+    for (path, content) in originals {
+        let _ = write_atomic(path, content);
+    }
+}
+
+/// Applies every edit and then every move, or none of them: on the first
+/// failure whatever was already written is put back.
+pub fn apply(label: &str, edits: &[FileEdits], moves: &[(String, String)]) -> Result<(), String> {
+    let mut updates = Vec::new();
+    for file in edits {
+        let original = fs::read_to_string(&file.path)
+            .map_err(|e| format!("Error al leer {}: {}", file.path, e))?;
+        let updated = apply_edits(&original, &file.edits).ok_or_else(|| {
+            format!("{} cambio desde la vista previa; vuelve a intentarlo", file.path)
+        })?;
+        updates.push((file.path.clone(), original, updated));
+    }
+
+    let mut originals = Vec::new();
+    for (path, original, updated) in &updates {
+        if let Err(e) = write_atomic(path, updated) {
+            restore(&originals, &[]);
+            return Err(format!("Error al guardar {}: {}", path, e));
+        }
+        originals.push((path.clone(), original.clone()));
+    }
+
+    let mut done = Vec::new();
+    for (from, to) in moves {
+        let moved = Path::new(to)
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::rename(from, to));
+        if let Err(e) = moved {
+            restore(&originals, &done);
+            return Err(format!("Error al mover {}: {}", from, e));
+        }
+        done.push((from.clone(), to.clone()));
+    }
+
+    let reverts = edits
+        .iter()
+        .map(|file| FileEdits { path: file.path.clone(), edits: inverse_edits(&file.edits) })
+        .collect();
+    if let Ok(mut journal) = UNDO_JOURNAL.lock() {
+        journal.push(UndoEntry {
+            label: label.to_string(),
+            moves: done,
+            reverts,
+        });
+        if journal.len() > UNDO_LIMIT {
+            journal.remove(0);
+        }
+    }
+    Ok(())
+}
+
+/// Reverts the most recent refactoring. A file edited after it keeps its
+/// current content, and a moved file that is gone or whose old place is
+/// taken stays where it is; both are reported instead of overwritten.
+pub fn undo_last() -> Result<UndoOutcome, String> {
+    let entry = UNDO_JOURNAL
+        .lock()
+        .map_err(|e| format!("Error al bloquear el historial: {}", e))?
+        .pop()
+        .ok_or("No hay cambios que deshacer")?;
+
+    let mut kept = Vec::new();
+    let mut stayed = Vec::new();
+    for (from, to) in entry.moves.iter().rev() {
+        if !Path::new(to).exists() || Path::new(from).exists() {
+            kept.push(to.clone());
+            stayed.push(from.as_str());
+            continue;
+        }
+        fs::rename(to, from).map_err(|e| format!("Error al deshacer el movimiento de {}: {}", to, e))?;
+    }
+    // The edits of a file that stayed where it was moved are kept with it.
+    for file in entry.reverts.iter().filter(|file| !stayed.contains(&file.path.as_str())) {
+        let reverted = fs::read_to_string(&file.path)
+            .ok()
+            .and_then(|current| apply_edits(&current, &file.edits));
+        match reverted {
+            Some(content) => {
+                write_atomic(&file.path, &content).map_err(|e| format!("Error al restaurar {}: {}", file.path, e))?
+            }
+            None => kept.push(file.path.clone()),
+        }
+    }
+    Ok(UndoOutcome { label: entry.label, kept })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A fresh project folder under the system temp dir with the given files.
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("codedocs-refactor-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    fn path(root: &Path, relative: &str) -> String {
+        root.join(relative).to_string_lossy().into_owned()
+    }
+
+    fn read(root: &Path, relative: &str) -> String {
+        fs::read_to_string(root.join(relative)).unwrap()
+    }
+
+    #[test]
+    fn renames_apply_and_undo_leaving_later_edits_alone() {
+        let root = project("undo", &[("index.md", "[a](notas/a.md)\n"), ("notas/a.md", "[i](../index.md)\n")]);
+        let (old, new) = (path(&root, "notas/a.md"), path(&root, "archivo/2024/a.md"));
+        let rename = || {
+            let plan = plan_rename(&root.to_string_lossy(), &old, &new).unwrap();
+            apply("Renombrar", &plan.edits, &[(old.clone(), new.clone())]).unwrap();
+        };
+
+        rename();
+        assert_eq!(read(&root, "index.md"), "[a](archivo/2024/a.md)\n");
+        assert_eq!(read(&root, "archivo/2024/a.md"), "[i](../../index.md)\n");
+        assert!(!Path::new(&old).exists());
+
+        let outcome = undo_last().unwrap();
+        assert_eq!((outcome.label.as_str(), outcome.kept.len()), ("Renombrar", 0));
+        assert_eq!(read(&root, "index.md"), "[a](notas/a.md)\n");
+        assert_eq!(read(&root, "notas/a.md"), "[i](../index.md)\n");
+        assert!(!Path::new(&new).exists());
+
+        rename();
+        fs::write(root.join("index.md"), "Editado después\n").unwrap();
+        let outcome = undo_last().unwrap();
+        assert_eq!(outcome.kept, [path(&root, "index.md")]);
+        assert_eq!(read(&root, "index.md"), "Editado después\n");
+        assert_eq!(read(&root, "notas/a.md"), "[i](../index.md)\n");
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn stale_plans_change_nothing() {
+        let root = project("stale", &[("index.md", "[a](a.md)\n"), ("a.md", "A\n")]);
+        let (old, new) = (path(&root, "a.md"), path(&root, "b.md"));
+        let plan = plan_rename(&root.to_string_lossy(), &old, &new).unwrap();
+        fs::write(root.join("index.md"), "Ver [a](a.md)\n").unwrap();
+
+        assert!(apply("Renombrar", &plan.edits, &[(old.clone(), new.clone())]).is_err());
+        assert_eq!(read(&root, "index.md"), "Ver [a](a.md)\n");
+        assert!(Path::new(&old).exists() && !Path::new(&new).exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn renames_onto_missing_or_existing_files_are_refused() {
+        let root = project("refused", &[("a.md", ""), ("b.md", "")]);
+        let root_str = root.to_string_lossy();
+        assert!(plan_rename(&root_str, &path(&root, "zzz.md"), &path(&root, "c.md")).is_err());
+        assert!(plan_rename(&root_str, &path(&root, "a.md"), &path(&root, "b.md")).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
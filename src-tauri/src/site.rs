@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use codedocs_md::assets::asset_references;
+use codedocs_md::config::ProjectConfig;
+use codedocs_md::export::document_title;
+use codedocs_md::links::{is_within, percent_encode, relative_path, resolve_path};
+use codedocs_md::site::{
+    is_draft, navigation, page_output, reading_order, rewrite_doc_links, search_entry, site_css, site_page, SiteEntry,
+    SITE_JS,
+};
+use codedocs_md::{extract_headings, render_markdown, RenderOptions};
+
+use crate::commands::{project_tree, FileEntry};
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SiteSummary {
+    pub pages: usize,
+    pub assets: usize,
+    pub output: String,
+}
+
+pub(crate) fn site_entries(tree: &[FileEntry]) -> Vec<SiteEntry> {
+    tree.iter()
+        .filter_map(|entry| {
+            if entry.is_dir {
+                Some(SiteEntry::Folder { name: entry.name.clone(), children: site_entries(&entry.children) })
+            } else {
+                let content = fs::read_to_string(&entry.path).ok()?;
+                Some(SiteEntry::Page { path: entry.path.clone(), content })
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn collect_pages<'a>(entries: &'a [SiteEntry], out: &mut Vec<(&'a str, &'a str)>) {
+    for entry in entries {
+        match entry {
+            SiteEntry::Page { path, content } => out.push((path, content)),
+            SiteEntry::Folder { children, .. } => collect_pages(children, out),
+        }
+    }
+}
+
+/// Paths compare with `/` separators and `.` and `..` resolved.
+pub(crate) fn normalized(path: &str) -> String {
+    resolve_path("", path)
+}
+
+fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Error al crear la carpeta {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("Error al escribir {}: {}", path.display(), e))
+}
+
+/// A page that sends the browser to the first page of the site, for sites
+/// without an `index.md` at the top.
+fn redirect_page(url: &str) -> String {
+    format!(
+        concat!(
+            "<!DOCTYPE html>\n<html lang=\"es\">\n<head>\n<meta charset=\"utf-8\">\n",
+            "<meta http-equiv=\"refresh\" content=\"0; url={0}\">\n</head>\n",
+            "<body>\n<a href=\"{0}\">{0}</a>\n</body>\n</html>\n"
+        ),
+        url
+    )
+}
+
+/// Renders every document of the project at `root` into `output` as a static
+/// website: the pages in the same folders as their documents, with links
+/// between documents pointing at their pages, the images and files they
+/// reference, a stylesheet, a search index and the script that uses it.
+/// Drafts are left out.
+pub fn export_site(root: &str, output: &str, config: &ProjectConfig) -> Result<SiteSummary, String> {
+    if is_within(&normalized(root), &normalized(output)) {
+        return Err("La carpeta de salida no puede contener el proyecto".to_string());
+    }
+
+    let entries = site_entries(&project_tree(root));
+    let mut documents = Vec::new();
+    collect_pages(&entries, &mut documents);
+    if documents.is_empty() {
+        return Err("No se encontraron archivos Markdown en la carpeta seleccionada".to_string());
+    }
+
+    let project_files: Vec<String> = documents.iter().map(|(path, _)| path.to_string()).collect();
+    let pages: BTreeMap<String, String> = documents
+        .iter()
+        .filter(|(_, content)| !is_draft(content))
+        .map(|(path, _)| (normalized(path), page_output(root, path)))
+        .collect();
+    let page_for = |path: &str| pages.get(&normalized(path)).cloned();
+
+    let nav = navigation(root, &entries);
+    let site_title = documents
+        .iter()
+        .find(|(path, _)| page_output(root, path) == "index.html")
+        .map(|(path, content)| document_title(content, path))
+        .unwrap_or_else(|| {
+            Path::new(root).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+        });
+
+    let output_dir = Path::new(output);
+    let mut search = Vec::new();
+    let mut assets = BTreeMap::new();
+    for (path, content) in documents.iter().filter(|(_, content)| !is_draft(content)) {
+        let current = page_output(root, path);
+        let options = RenderOptions {
+            document_path: Some(path.to_string()),
+            project_files: project_files.clone(),
+            ..config.render_options()
+        };
+        let body = rewrite_doc_links(&render_markdown(content, &options), &current, page_for);
+        let title = document_title(content, path);
+        let html = site_page(&site_title, &title, &body, &extract_headings(content), &nav, &current);
+        write_file(&output_dir.join(&current), html)?;
+        search.push(search_entry(&title, &current, content, &body));
+
+        assets.extend(
+            asset_references(path, content)
+                .into_iter()
+                .filter(|reference| is_within(&reference.path, root) && Path::new(&reference.path).is_file())
+                .map(|reference| (normalized(&reference.path), reference.path)),
+        );
+    }
+
+    if !pages.values().any(|url| url == "index.html") {
+        if let Some(first) = reading_order(&nav).first() {
+            let url: Vec<String> = first.split('/').map(percent_encode).collect();
+            write_file(&output_dir.join("index.html"), redirect_page(&url.join("/")))?;
+        }
+    }
+    write_file(&output_dir.join("style.css"), site_css())?;
+    write_file(&output_dir.join("site.js"), SITE_JS)?;
+    let index = serde_json::to_string(&search).map_err(|e| e.to_string())?;
+    write_file(&output_dir.join("search-index.js"), format!("window.__SITE_SEARCH_INDEX = {};\n", index))?;
+
+    for (key, asset) in &assets {
+        let target = output_dir.join(relative_path(root, key));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Error al crear la carpeta {}: {}", parent.display(), e))?;
+        }
+        fs::copy(asset, &target).map_err(|e| format!("Error al copiar {}: {}", asset, e))?;
+    }
+
+    Ok(SiteSummary { pages: search.len(), assets: assets.len(), output: output.to_string() })
+}
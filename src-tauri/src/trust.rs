@@ -0,0 +1,50 @@
+//! Projects whose raw HTML the user chose to see unsanitized. The list lives
+//! in the app's config folder, keyed by each project's canonical path, so a
+//! repository cannot trust itself by shipping a setting.
+
+use std::fs;
+use std::path::PathBuf;
+
+use tauri::Manager;
+
+const TRUST_FILE: &str = "trusted_projects.json";
+
+fn trust_file(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(TRUST_FILE))
+        .map_err(|e| format!("No se encontro la carpeta de configuracion: {}", e))
+}
+
+fn project_key(folder_path: &str) -> Result<String, String> {
+    fs::canonicalize(folder_path)
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|e| format!("Error al leer la carpeta del proyecto: {}", e))
+}
+
+fn trusted_projects(app: &tauri::AppHandle) -> Vec<String> {
+    trust_file(app)
+        .ok()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|source| serde_json::from_str(&source).ok())
+        .unwrap_or_default()
+}
+
+pub fn is_trusted(app: &tauri::AppHandle, folder_path: &str) -> bool {
+    project_key(folder_path).is_ok_and(|key| trusted_projects(app).contains(&key))
+}
+
+pub fn set_trusted(app: &tauri::AppHandle, folder_path: &str, trusted: bool) -> Result<(), String> {
+    let key = project_key(folder_path)?;
+    let mut projects = trusted_projects(app);
+    projects.retain(|project| *project != key);
+    if trusted {
+        projects.push(key);
+    }
+    let file = trust_file(app)?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Error al guardar la confianza del proyecto: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&projects).unwrap_or_default();
+    fs::write(file, json).map_err(|e| format!("Error al guardar la confianza del proyecto: {}", e))
+}
@@ -1,9 +1,11 @@
 use crate::components::layout::Layout;
+use codedocs_md::highlight_css;
 use leptos::prelude::*;
 
 #[component]
 pub fn App() -> impl IntoView {
     view! {
+       <style id="codedocs-highlight">{highlight_css()}</style>
        <Layout />
     }
 }
@@ -0,0 +1,117 @@
+use std::collections::BTreeSet;
+
+use codedocs_md::assets::AssetReport;
+use codedocs_md::links::relative_path;
+use leptos::prelude::*;
+
+const SECTION_CLASS: &str = "px-4 pt-2 pb-0.5 text-[11px] font-medium text-base-500 dark:text-base-400";
+
+/// Unused files of the assets folders, to move to the trash, and references
+/// to files that do not exist.
+#[component]
+pub fn AssetsPanel(
+    report: ReadSignal<AssetReport>,
+    root: ReadSignal<String>,
+    is_loading: ReadSignal<bool>,
+    on_open: Callback<(String, usize)>,
+    on_trash: Callback<Vec<String>>,
+    on_refresh: Callback<()>,
+    on_close: Callback<()>,
+) -> impl IntoView {
+    // Every unused asset starts selected.
+    let selected = RwSignal::new(BTreeSet::<String>::new());
+    Effect::new(move |_| {
+        selected.set(report.with(|r| r.unused.iter().cloned().collect()));
+    });
+    let display = move |path: &str| root.with_untracked(|root| relative_path(root, path));
+
+    view! {
+        <div class="h-48 flex flex-col border-t border-base-200 dark:border-base-800 bg-base-50 dark:bg-base-900 flex-shrink-0">
+            <div class="flex items-center justify-between px-4 py-1.5 border-b border-base-200 dark:border-base-800">
+                <h2 class="text-[10px] font-bold uppercase tracking-widest text-base-400 dark:text-base-500">
+                    {move || report.with(|r| format!("Recursos ({} sin usar, {} rotos)", r.unused.len(), r.missing.len()))}
+                </h2>
+                <div class="flex items-center gap-1">
+                    <button
+                        class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-red-500 disabled:opacity-50 disabled:hover:text-base-500 transition-colors"
+                        title="Mover los recursos seleccionados a la papelera del sistema"
+                        disabled=move || is_loading.get() || selected.with(BTreeSet::is_empty)
+                        on:click=move |_| on_trash.run(selected.get_untracked().into_iter().collect())
+                    >
+                        {move || format!("Mover a la papelera ({})", selected.with(BTreeSet::len))}
+                    </button>
+                    <button
+                        class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-brand-orange disabled:opacity-50 transition-colors"
+                        disabled=move || is_loading.get()
+                        on:click=move |_| on_refresh.run(())
+                    >
+                        {move || if is_loading.get() { "Analizando..." } else { "Actualizar" }}
+                    </button>
+                    <button
+                        class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-brand-orange transition-colors"
+                        title="Cerrar panel"
+                        on:click=move |_| on_close.run(())
+                    >
+                        "✕"
+                    </button>
+                </div>
+            </div>
+            <div class="flex-1 overflow-y-auto custom-scrollbar pb-2">
+                {move || {
+                    let AssetReport { unused, missing } = report.get();
+                    if unused.is_empty() && missing.is_empty() {
+                        return view! {
+                            <p class="text-xs text-base-400 dark:text-base-600 italic px-4 py-2">
+                                "Todos los recursos están en uso y existen"
+                            </p>
+                        }.into_any();
+                    }
+
+                    view! {
+                        {(!unused.is_empty()).then(|| view! {
+                            <h3 class=SECTION_CLASS>"Sin usar"</h3>
+                            {unused.into_iter().map(|file| {
+                                let toggled = file.clone();
+                                let checked = file.clone();
+                                view! {
+                                    <label class="flex items-center gap-2 pl-6 pr-4 py-0.5 text-xs hover:bg-base-100 dark:hover:bg-base-800/50 cursor-pointer">
+                                        <input
+                                            type="checkbox"
+                                            class="flex-shrink-0"
+                                            prop:checked=move || selected.with(|s| s.contains(&checked))
+                                            on:change=move |_| selected.update(|s| {
+                                                if !s.remove(&toggled) {
+                                                    s.insert(toggled.clone());
+                                                }
+                                            })
+                                        />
+                                        <span class="font-mono text-base-700 dark:text-base-300 truncate" title=file.clone()>
+                                            {display(&file)}
+                                        </span>
+                                    </label>
+                                }
+                            }).collect_view()}
+                        })}
+                        {(!missing.is_empty()).then(|| view! {
+                            <h3 class=SECTION_CLASS>"Referencias a archivos que no existen"</h3>
+                            {missing.into_iter().map(|reference| {
+                                let target = (reference.file.clone(), reference.line);
+                                view! {
+                                    <button
+                                        class="w-full flex items-center gap-2 pl-6 pr-4 py-0.5 text-xs text-left hover:bg-base-100 dark:hover:bg-base-800/50"
+                                        on:click=move |_| on_open.run(target.clone())
+                                    >
+                                        <span class="font-mono text-red-500 truncate">{reference.target.clone()}</span>
+                                        <span class="font-mono text-[11px] text-base-400 dark:text-base-600 flex-shrink-0">
+                                            {format!("{}:{}", display(&reference.file), reference.line)}
+                                        </span>
+                                    </button>
+                                }
+                            }).collect_view()}
+                        })}
+                    }.into_any()
+                }}
+            </div>
+        </div>
+    }
+}
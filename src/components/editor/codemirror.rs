@@ -1,4 +1,7 @@
+use codedocs_md::lint::{LintDiagnostic, Severity};
+use codedocs_md::live::{LiveKind, LiveSpan};
 use leptos::prelude::*;
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
@@ -23,6 +26,30 @@ extern "C" {
     #[wasm_bindgen(js_name = __codedocs_setOnChange)]
     pub fn cm_set_on_change(callback: &js_sys::Function);
 
+    #[wasm_bindgen(js_name = __codedocs_setOnCursorLine)]
+    pub fn cm_set_on_cursor_line(callback: &js_sys::Function);
+
+    #[wasm_bindgen(js_name = __codedocs_setOnScrollLine)]
+    pub fn cm_set_on_scroll_line(callback: &js_sys::Function);
+
+    #[wasm_bindgen(js_name = __codedocs_scroll_to_line)]
+    pub fn cm_scroll_to_line(line: f64);
+
+    #[wasm_bindgen(js_name = __codedocs_goto_line)]
+    pub fn cm_goto_line(line: u32);
+
+    #[wasm_bindgen(js_name = __codedocs_set_wiki_targets)]
+    pub fn cm_set_wiki_targets(targets: &JsValue);
+
+    #[wasm_bindgen(js_name = __codedocs_set_diagnostics)]
+    fn cm_set_diagnostics_js(diagnostics: &JsValue);
+
+    #[wasm_bindgen(js_name = __codedocs_set_live_preview)]
+    pub fn cm_set_live_preview(enabled: bool);
+
+    #[wasm_bindgen(js_name = __codedocs_set_live_spans)]
+    fn cm_set_live_spans_js(length: usize, spans: &JsValue);
+
     #[wasm_bindgen(js_name = __codedocs_focus)]
     pub fn cm_focus();
 
@@ -32,16 +59,111 @@ extern "C" {
     #[wasm_bindgen(js_name = __codedocs_wrap_selection)]
     pub fn cm_wrap_selection(wrapper: &str);
 
+    #[wasm_bindgen(js_name = __codedocs_insert_text)]
+    pub fn cm_insert_text(text: &str);
+
     #[wasm_bindgen(js_name = __codedocs_insert_link)]
     pub fn cm_insert_link();
 }
 
+#[derive(Serialize)]
+struct CmChange {
+    from: usize,
+    to: usize,
+    insert: String,
+}
+
+#[derive(Serialize)]
+struct CmDiagnostic {
+    from: usize,
+    to: usize,
+    severity: &'static str,
+    source: String,
+    message: String,
+    fix: Option<CmChange>,
+}
+
+/// CodeMirror positions count UTF-16 code units, the linter counts bytes.
+/// Built once per document so each conversion is a binary search.
+struct Utf16Offsets {
+    /// The byte offset just past each non-ASCII character, and how many more
+    /// bytes than UTF-16 units the text up to there takes.
+    shifts: Vec<(usize, usize)>,
+    length: usize,
+}
+
+impl Utf16Offsets {
+    fn new(content: &str) -> Self {
+        let mut shifts = Vec::new();
+        let mut shift = 0;
+        for (ix, ch) in content.char_indices().filter(|(_, ch)| !ch.is_ascii()) {
+            shift += ch.len_utf8() - ch.len_utf16();
+            shifts.push((ix + ch.len_utf8(), shift));
+        }
+        Self { shifts, length: content.len() - shift }
+    }
+
+    fn at(&self, byte: usize) -> usize {
+        let before = self.shifts.partition_point(|&(end, _)| end <= byte);
+        byte - before.checked_sub(1).map_or(0, |ix| self.shifts[ix].1)
+    }
+}
+
+#[derive(Serialize)]
+struct CmLiveSpan<'a> {
+    from: usize,
+    to: usize,
+    hidden: Vec<(usize, usize)>,
+    #[serde(flatten)]
+    kind: &'a LiveKind,
+}
+
+/// Hands the live preview the constructs to render in `content`.
+pub fn cm_set_live_spans(content: &str, spans: &[LiveSpan]) {
+    let offsets = Utf16Offsets::new(content);
+    let items: Vec<CmLiveSpan> = spans
+        .iter()
+        .map(|span| CmLiveSpan {
+            from: offsets.at(span.range.start),
+            to: offsets.at(span.range.end),
+            hidden: span.hidden.iter().map(|range| (offsets.at(range.start), offsets.at(range.end))).collect(),
+            kind: &span.kind,
+        })
+        .collect();
+    cm_set_live_spans_js(offsets.length, &serde_wasm_bindgen::to_value(&items).unwrap_or(JsValue::NULL));
+}
+
+/// Shows the linter's results for `content` in the editor, with quick fixes.
+pub fn cm_set_diagnostics(content: &str, diagnostics: &[LintDiagnostic]) {
+    let offsets = Utf16Offsets::new(content);
+    let items: Vec<CmDiagnostic> = diagnostics
+        .iter()
+        .map(|d| CmDiagnostic {
+            from: offsets.at(d.start),
+            to: offsets.at(d.end),
+            severity: if d.severity == Severity::Error { "error" } else { "warning" },
+            source: d.rule.clone(),
+            message: d.message.clone(),
+            fix: d.fix.as_ref().map(|fix| CmChange {
+                from: offsets.at(fix.start),
+                to: offsets.at(fix.end),
+                insert: fix.replacement.clone(),
+            }),
+        })
+        .collect();
+    cm_set_diagnostics_js(&serde_wasm_bindgen::to_value(&items).unwrap_or(JsValue::NULL));
+}
+
 #[component]
 pub fn CodeMirrorEditor(
     content: ReadSignal<String>,
     set_content: WriteSignal<String>,
     is_dark: ReadSignal<bool>,
     on_save: Callback<()>,
+    on_cursor_line: Callback<usize>,
+    on_scroll_line: Callback<f64>,
+    on_rename_heading: Callback<usize>,
+    on_paste_image: Callback<(String, Vec<u8>)>,
 ) -> impl IntoView {
     let container_ref = NodeRef::<leptos::html::Div>::new();
     let is_initialized = RwSignal::new(false);
@@ -63,6 +185,18 @@ pub fn CodeMirrorEditor(
                 cm_set_on_change(closure.as_ref().unchecked_ref());
                 closure.forget();
 
+                let cursor_closure = Closure::<dyn Fn(u32)>::new(move |line: u32| {
+                    on_cursor_line.run(line as usize);
+                });
+                cm_set_on_cursor_line(cursor_closure.as_ref().unchecked_ref());
+                cursor_closure.forget();
+
+                let scroll_closure = Closure::<dyn Fn(f64)>::new(move |line: f64| {
+                    on_scroll_line.run(line);
+                });
+                cm_set_on_scroll_line(scroll_closure.as_ref().unchecked_ref());
+                scroll_closure.forget();
+
                 let save_cb = on_save;
                 let save_closure = Closure::<dyn Fn()>::new(move || {
                     save_cb.run(());
@@ -75,6 +209,30 @@ pub fn CodeMirrorEditor(
                 .ok();
                 save_closure.forget();
 
+                let rename_closure = Closure::<dyn Fn(u32)>::new(move |line: u32| {
+                    on_rename_heading.run(line as usize);
+                });
+                js_sys::Reflect::set(
+                    &js_sys::global(),
+                    &JsValue::from_str("__codedocs_rename_heading"),
+                    rename_closure.as_ref(),
+                )
+                .ok();
+                rename_closure.forget();
+
+                let image_closure = Closure::<dyn Fn(String, js_sys::Uint8Array)>::new(
+                    move |name: String, bytes: js_sys::Uint8Array| {
+                        on_paste_image.run((name, bytes.to_vec()));
+                    },
+                );
+                js_sys::Reflect::set(
+                    &js_sys::global(),
+                    &JsValue::from_str("__codedocs_paste_image"),
+                    image_closure.as_ref(),
+                )
+                .ok();
+                image_closure.forget();
+
                 cm_create_editor(&el, &initial, dark);
                 is_initialized.set(true);
             }
@@ -0,0 +1,100 @@
+use codedocs_md::frontmatter::{parse_front_matter, update_front_matter, MetaValue};
+use leptos::prelude::*;
+
+const LABEL_CLASS: &str = "text-[11px] font-medium text-base-500 dark:text-base-400";
+const INPUT_CLASS: &str = "w-full px-2 py-1 text-sm bg-base-50 dark:bg-base-800 border border-base-200 dark:border-base-700 rounded-md text-base-900 dark:text-base-100 focus:outline-none focus:border-brand-orange transition-colors";
+
+#[component]
+pub fn MetadataForm(
+    editor_content: ReadSignal<String>,
+    set_editor_content: WriteSignal<String>,
+) -> impl IntoView {
+    let front_matter = Memo::new(move |_| parse_front_matter(&editor_content.get()));
+
+    let update_field = move |key: &'static str, value: Option<MetaValue>| {
+        set_editor_content.update(|content| *content = update_front_matter(content, key, value));
+    };
+
+    move || {
+        front_matter.get().map(|fm| {
+            let title = fm.title();
+            let tags = fm.tags().join(", ");
+            let date = fm.date().chars().take(10).collect::<String>();
+            let draft = fm.draft();
+            let others: Vec<(String, String)> = fm
+                .fields
+                .iter()
+                .filter(|(k, _)| !matches!(k.as_str(), "title" | "tags" | "date" | "draft"))
+                .map(|(k, v)| (k.clone(), v.display()))
+                .collect();
+
+            view! {
+                <div class="mb-8 p-4 rounded-lg border border-base-200 dark:border-base-800 bg-base-50 dark:bg-base-900/50">
+                    <h2 class="text-[10px] font-bold uppercase tracking-widest text-base-400 dark:text-base-500 mb-3">
+                        "Metadatos"
+                    </h2>
+                    {fm.error.map(|err| view! {
+                        <p class="text-xs text-brand-orange mb-3 font-mono truncate" title=err.clone()>
+                            {format!("Front matter inválido: {}", err)}
+                        </p>
+                    })}
+                    <div class="grid grid-cols-[6rem_1fr] gap-x-3 gap-y-2 items-center">
+                        <label class=LABEL_CLASS>"Título"</label>
+                        <input
+                            type="text"
+                            class=INPUT_CLASS
+                            prop:value=title
+                            on:change=move |ev| {
+                                let value = event_target_value(&ev);
+                                let value = (!value.trim().is_empty()).then_some(MetaValue::Text(value));
+                                update_field("title", value);
+                            }
+                        />
+
+                        <label class=LABEL_CLASS>"Etiquetas"</label>
+                        <input
+                            type="text"
+                            class=INPUT_CLASS
+                            placeholder="etiqueta, otra-etiqueta"
+                            prop:value=tags
+                            on:change=move |ev| {
+                                let tags: Vec<String> = event_target_value(&ev)
+                                    .split(',')
+                                    .map(|t| t.trim().to_string())
+                                    .filter(|t| !t.is_empty())
+                                    .collect();
+                                update_field("tags", (!tags.is_empty()).then_some(MetaValue::List(tags)));
+                            }
+                        />
+
+                        <label class=LABEL_CLASS>"Fecha"</label>
+                        <input
+                            type="date"
+                            class=INPUT_CLASS
+                            prop:value=date
+                            on:change=move |ev| {
+                                let value = event_target_value(&ev);
+                                update_field("date", (!value.is_empty()).then_some(MetaValue::Date(value)));
+                            }
+                        />
+
+                        <label class=LABEL_CLASS>"Borrador"</label>
+                        <input
+                            type="checkbox"
+                            class="justify-self-start accent-brand-orange"
+                            prop:checked=draft
+                            on:change=move |ev| update_field("draft", Some(MetaValue::Bool(event_target_checked(&ev))))
+                        />
+
+                        {others.into_iter().map(|(key, value)| view! {
+                            <span class=LABEL_CLASS>{key}</span>
+                            <span class="text-xs font-mono text-base-500 dark:text-base-400 truncate" title=value.clone()>
+                                {value}
+                            </span>
+                        }).collect_view()}
+                    </div>
+                </div>
+            }
+        })
+    }
+}
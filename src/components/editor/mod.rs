@@ -1,9 +1,32 @@
 mod codemirror;
+mod metadata;
+mod scroll_sync;
 
-pub use codemirror::CodeMirrorEditor;
+pub use codemirror::{cm_goto_line, cm_insert_text, cm_set_diagnostics, cm_set_live_spans, cm_set_wiki_targets, CodeMirrorEditor};
+pub use metadata::MetadataForm;
 
 use crate::components::layout::ViewMode;
+use codemirror::{cm_scroll_to_line, cm_set_live_preview};
+use scroll_sync::{line_at_preview_top, preview_offset_for_line};
+use crate::utils::markdown::Heading;
+use crate::utils::tauri_bridge::{self, invoke};
+use codedocs_md::links::{parse_doc_link, percent_decode};
+use leptos::logging::error;
 use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use wasm_bindgen::JsCast;
+
+/// Scrolls whichever view is mounted to the given heading.
+pub fn reveal_heading(view_mode: ViewMode, heading: &Heading) {
+    match view_mode {
+        ViewMode::Raw | ViewMode::Split | ViewMode::Live => codemirror::cm_goto_line(heading.line as u32),
+        ViewMode::Formatted => {
+            if let Some(el) = document().get_element_by_id(&heading.slug) {
+                el.scroll_into_view_with_bool(true);
+            }
+        }
+    }
+}
 
 #[component]
 pub fn EditorPane(
@@ -12,8 +35,39 @@ pub fn EditorPane(
     preview_html: ReadSignal<String>,
     view_mode: ReadSignal<ViewMode>,
     on_save: Callback<()>,
+    headings: ReadSignal<Vec<Heading>>,
+    set_active_heading: WriteSignal<Option<usize>>,
+    set_cursor_line: WriteSignal<usize>,
+    on_open_document: Callback<(String, Option<String>)>,
+    on_rename_heading: Callback<usize>,
+    on_toggle_task: Callback<usize>,
+    on_paste_image: Callback<(String, Vec<u8>)>,
 ) -> impl IntoView {
     let (is_dark, set_is_dark) = signal(false);
+    let preview_ref = NodeRef::<leptos::html::Div>::new();
+    let pane_ref = NodeRef::<leptos::html::Div>::new();
+    let split_ratio = RwSignal::new(0.5);
+    let (is_resizing_split, set_is_resizing_split) = signal(false);
+    // Scrolling one pane scrolls the other, whose own scroll events are then
+    // ignored for a moment so the two do not keep driving each other.
+    let ignore_editor_scroll_until = RwSignal::new(0.0);
+    let ignore_preview_scroll_until = RwSignal::new(0.0);
+
+    let _ = window_event_listener(leptos::ev::mousemove, move |ev: leptos::ev::MouseEvent| {
+        if !is_resizing_split.get_untracked() {
+            return;
+        }
+        if let Some(pane) = pane_ref.get_untracked() {
+            let rect = pane.get_bounding_client_rect();
+            if rect.width() > 0.0 {
+                split_ratio.set(((ev.client_x() as f64 - rect.left()) / rect.width()).clamp(0.2, 0.8));
+            }
+        }
+    });
+
+    let _ = window_event_listener(leptos::ev::mouseup, move |_| {
+        set_is_resizing_split.set(false);
+    });
 
     Effect::new(move |_| {
         if let Some(doc) = web_sys::window().and_then(|w| w.document()) {
@@ -23,30 +77,131 @@ pub fn EditorPane(
         }
     });
 
+    Effect::new(move |_| {
+        cm_set_live_preview(view_mode.get() == ViewMode::Live);
+    });
+
+    let on_cursor_line = Callback::new(move |line: usize| {
+        set_cursor_line.set(line);
+        let active = headings.with_untracked(|h| h.iter().rposition(|heading| heading.line <= line));
+        set_active_heading.set(active);
+    });
+
+    let on_editor_scroll = Callback::new(move |line: f64| {
+        if view_mode.get_untracked() != ViewMode::Split || js_sys::Date::now() < ignore_editor_scroll_until.get_untracked() {
+            return;
+        }
+        let Some(container) = preview_ref.get_untracked() else { return };
+        let total_lines = editor_content.with_untracked(|content| content.lines().count());
+        let offset = preview_offset_for_line(&container, line, total_lines);
+        ignore_preview_scroll_until.set(js_sys::Date::now() + 100.0);
+        container.set_scroll_top(offset.round() as i32);
+    });
+
+    let on_preview_scroll = move |_: leptos::ev::Event| {
+        let Some(container) = preview_ref.get() else { return };
+        if view_mode.get_untracked() == ViewMode::Split && js_sys::Date::now() >= ignore_preview_scroll_until.get_untracked() {
+            let total_lines = editor_content.with_untracked(|content| content.lines().count());
+            ignore_editor_scroll_until.set(js_sys::Date::now() + 100.0);
+            cm_scroll_to_line(line_at_preview_top(&container, total_lines));
+        }
+        let top = container.get_bounding_client_rect().top();
+        let doc = document();
+        let active = headings.with_untracked(|h| {
+            h.iter().rposition(|heading| {
+                doc.get_element_by_id(&heading.slug)
+                    .is_some_and(|el| el.get_bounding_client_rect().top() - top <= 80.0)
+            })
+        });
+        set_active_heading.set(active);
+    };
+
+    let on_preview_click = move |ev: leptos::ev::MouseEvent| {
+        let Some(target) = ev.target().and_then(|t| t.dyn_into::<web_sys::Element>().ok()) else { return };
+        // Task checkboxes toggle the marker in the source; the re-render shows the new state.
+        if let Some(line) = target.get_attribute("data-task-line").and_then(|line| line.parse().ok()) {
+            ev.prevent_default();
+            on_toggle_task.run(line);
+            return;
+        }
+        // No link navigates the webview: links to other documents open them in
+        // the editor, anchors scroll the preview and web links open in the browser.
+        let Some(href) = target
+            .closest("a")
+            .ok()
+            .flatten()
+            .and_then(|link| link.get_attribute("href"))
+        else {
+            return;
+        };
+        ev.prevent_default();
+        if let Some(document) = parse_doc_link(&href) {
+            on_open_document.run(document);
+        } else if let Some(id) = href.strip_prefix('#') {
+            if let Some(el) = document().get_element_by_id(&percent_decode(id)) {
+                el.scroll_into_view_with_bool(true);
+            }
+        } else if ["http://", "https://", "mailto:"].iter().any(|scheme| href.starts_with(scheme)) {
+            spawn_local(async move {
+                if let Err(e) = invoke("plugin:opener|open_url", tauri_bridge::args_with("url", &href)).await {
+                    error!("Error opening {}: {:?}", href, e);
+                }
+            });
+        }
+    };
+
+    // The editor stays mounted in every mode so its undo history survives
+    // switching views; the hidden pane is only taken out of the layout.
     view! {
-        <div class="flex-1 overflow-hidden w-full h-full relative">
-            {move || match view_mode.get() {
-                ViewMode::Raw => view! {
-                    <div class="w-full h-full overflow-hidden">
-                        <CodeMirrorEditor
-                            content=editor_content
-                            set_content=set_editor_content
-                            is_dark=is_dark
-                            on_save=on_save
-                        />
-                    </div>
-                }.into_any(),
-                ViewMode::Formatted => view! {
-                    <div class="w-full h-full flex flex-col bg-base-100/50 dark:bg-base-900/10 overflow-hidden">
-                        <div class="flex-1 overflow-y-auto p-8 custom-scrollbar overflow-x-auto">
-                            <div
-                                class="prose dark:prose-invert prose-slate max-w-none break-words prose-headings:font-bold prose-h1:text-3xl prose-h1:mb-6 prose-p:text-base-600 dark:prose-p:text-base-400 prose-p:leading-7 prose-pre:overflow-x-auto prose-code:text-brand-orange prose-code:bg-brand-orange/10 prose-code:px-1 prose-code:py-0.5 prose-code:rounded prose-code:before:content-none prose-code:after:content-none prose-blockquote:border-brand-orange/50 prose-blockquote:bg-brand-orange/5 prose-img:rounded-lg prose-img:shadow-md"
-                                inner_html=preview_html
-                            ></div>
-                        </div>
-                    </div>
-                }.into_any(),
-            }}
+        <div node_ref=pane_ref class="flex-1 overflow-hidden w-full h-full relative flex">
+            <div
+                class="h-full overflow-hidden flex-shrink-0"
+                style=move || match view_mode.get() {
+                    ViewMode::Raw | ViewMode::Live => "width: 100%".to_string(),
+                    ViewMode::Split => format!("width: {:.2}%", split_ratio.get() * 100.0),
+                    ViewMode::Formatted => "display: none".to_string(),
+                }
+            >
+                <CodeMirrorEditor
+                    content=editor_content
+                    set_content=set_editor_content
+                    is_dark=is_dark
+                    on_save=on_save
+                    on_cursor_line=on_cursor_line
+                    on_scroll_line=on_editor_scroll
+                    on_rename_heading=on_rename_heading
+                    on_paste_image=on_paste_image
+                />
+            </div>
+            {move || (view_mode.get() == ViewMode::Split).then(|| view! {
+                <div
+                    class="w-1 hover:w-1.5 bg-base-200 dark:bg-base-800 hover:bg-brand-orange/40 cursor-col-resize transition-all flex-shrink-0"
+                    on:mousedown=move |ev| {
+                        ev.prevent_default();
+                        set_is_resizing_split.set(true);
+                    }
+                />
+            })}
+            <div
+                class="flex-1 min-w-0 h-full flex flex-col bg-base-100/50 dark:bg-base-900/10 overflow-hidden"
+                style=move || if matches!(view_mode.get(), ViewMode::Raw | ViewMode::Live) { "display: none" } else { "" }
+            >
+                <div
+                    node_ref=preview_ref
+                    class="flex-1 overflow-y-auto p-8 custom-scrollbar overflow-x-auto"
+                    on:scroll=on_preview_scroll
+                >
+                    <MetadataForm
+                        editor_content=editor_content
+                        set_editor_content=set_editor_content
+                    />
+                    <div
+                        class="prose dark:prose-invert prose-slate max-w-none break-words prose-headings:font-bold prose-h1:text-3xl prose-h1:mb-6 prose-p:text-base-600 dark:prose-p:text-base-400 prose-p:leading-7 prose-pre:overflow-x-auto prose-code:text-brand-orange prose-code:bg-brand-orange/10 prose-code:px-1 prose-code:py-0.5 prose-code:rounded prose-code:before:content-none prose-code:after:content-none prose-blockquote:border-brand-orange/50 prose-blockquote:bg-brand-orange/5 prose-img:rounded-lg prose-img:shadow-md"
+                        inner_html=preview_html
+                        on:click=on_preview_click
+                    ></div>
+                </div>
+            </div>
         </div>
     }
 }
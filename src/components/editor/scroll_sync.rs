@@ -0,0 +1,55 @@
+use wasm_bindgen::JsCast;
+
+/// `(source line, offset)` of each block the renderer marked with
+/// `data-source-line`, offsets measured from the top of the preview's
+/// scrollable content. Both ends of the document are included so every
+/// position falls between two anchors.
+fn anchors(container: &web_sys::Element, total_lines: usize) -> Vec<(f64, f64)> {
+    let top = container.get_bounding_client_rect().top() - container.scroll_top() as f64;
+    let mut anchors = vec![(1.0, 0.0)];
+    if let Ok(nodes) = container.query_selector_all("[data-source-line]") {
+        for ix in 0..nodes.length() {
+            let Some(el) = nodes.item(ix).and_then(|node| node.dyn_into::<web_sys::Element>().ok()) else {
+                continue;
+            };
+            let Some(line) = el.get_attribute("data-source-line").and_then(|l| l.parse::<f64>().ok()) else {
+                continue;
+            };
+            let offset = el.get_bounding_client_rect().top() - top;
+            // Blocks out of order (floats, odd HTML) would make the mapping jump back.
+            if anchors.last().is_some_and(|&(l, o)| line > l && offset >= o) {
+                anchors.push((line, offset));
+            }
+        }
+    }
+    let end = (total_lines as f64 + 1.0, container.scroll_height() as f64);
+    if anchors.last().is_some_and(|&(l, o)| end.0 > l && end.1 >= o) {
+        anchors.push(end);
+    }
+    anchors
+}
+
+/// Linear interpolation over points sorted by `x`.
+fn interpolate(points: impl Iterator<Item = (f64, f64)>, x: f64) -> f64 {
+    let mut previous: Option<(f64, f64)> = None;
+    for (px, py) in points {
+        match previous {
+            Some((qx, qy)) if x < px => {
+                return if px > qx { qy + (py - qy) * (x - qx) / (px - qx) } else { qy };
+            }
+            _ => previous = Some((px, py)),
+        }
+    }
+    previous.map_or(0.0, |(_, y)| y)
+}
+
+/// Where the preview should be scrolled to show the given (fractional) source line at the top.
+pub fn preview_offset_for_line(container: &web_sys::Element, line: f64, total_lines: usize) -> f64 {
+    interpolate(anchors(container, total_lines).into_iter(), line)
+}
+
+/// The (fractional) source line at the top of the preview.
+pub fn line_at_preview_top(container: &web_sys::Element, total_lines: usize) -> f64 {
+    let offset = container.scroll_top() as f64;
+    interpolate(anchors(container, total_lines).into_iter().map(|(line, y)| (y, line)), offset)
+}
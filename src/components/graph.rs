@@ -0,0 +1,174 @@
+use crate::types::LinkGraph;
+use leptos::prelude::*;
+use std::collections::HashMap;
+
+const WIDTH: f64 = 1000.0;
+const HEIGHT: f64 = 700.0;
+const MARGIN: f64 = 40.0;
+
+/// Fruchterman-Reingold layout: linked pages pull together, every pair pushes
+/// apart, and a light pull to the centre keeps orphans on screen.
+fn layout(graph: &LinkGraph) -> Vec<(f64, f64)> {
+    let n = graph.nodes.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let center = (WIDTH / 2.0, HEIGHT / 2.0);
+    let radius = HEIGHT / 2.5;
+    let mut positions: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let angle = i as f64 / n as f64 * std::f64::consts::TAU;
+            (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+        })
+        .collect();
+
+    let index: HashMap<&str, usize> = graph.nodes.iter().enumerate().map(|(i, node)| (node.path.as_str(), i)).collect();
+    let edges: Vec<(usize, usize)> = graph
+        .edges
+        .iter()
+        .filter_map(|edge| Some((*index.get(edge.source.as_str())?, *index.get(edge.target.as_str())?)))
+        .collect();
+
+    let k = (WIDTH * HEIGHT / n as f64).sqrt() * 0.5;
+    let mut temperature = WIDTH / 10.0;
+
+    for _ in 0..200 {
+        let mut displacement = vec![(0.0, 0.0); n];
+
+        for i in 0..n {
+            for j in i + 1..n {
+                let (dx, dy) = (positions[i].0 - positions[j].0, positions[i].1 - positions[j].1);
+                let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+                let force = k * k / distance;
+                displacement[i].0 += dx / distance * force;
+                displacement[i].1 += dy / distance * force;
+                displacement[j].0 -= dx / distance * force;
+                displacement[j].1 -= dy / distance * force;
+            }
+        }
+
+        for &(i, j) in &edges {
+            let (dx, dy) = (positions[i].0 - positions[j].0, positions[i].1 - positions[j].1);
+            let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+            let force = distance * distance / k;
+            displacement[i].0 -= dx / distance * force;
+            displacement[i].1 -= dy / distance * force;
+            displacement[j].0 += dx / distance * force;
+            displacement[j].1 += dy / distance * force;
+        }
+
+        for (position, shift) in positions.iter_mut().zip(displacement.iter_mut()) {
+            shift.0 -= (position.0 - center.0) * 0.05;
+            shift.1 -= (position.1 - center.1) * 0.05;
+            let length = (shift.0 * shift.0 + shift.1 * shift.1).sqrt().max(0.01);
+            let step = length.min(temperature);
+            position.0 = (position.0 + shift.0 / length * step).clamp(MARGIN, WIDTH - MARGIN);
+            position.1 = (position.1 + shift.1 / length * step).clamp(MARGIN, HEIGHT - MARGIN);
+        }
+
+        temperature *= 0.97;
+    }
+
+    positions
+}
+
+#[component]
+pub fn LinkGraphView(
+    graph: LinkGraph,
+    current: Option<String>,
+    on_open: Callback<String>,
+    on_close: Callback<()>,
+) -> impl IntoView {
+    let _ = window_event_listener(leptos::ev::keydown, move |ev: leptos::ev::KeyboardEvent| {
+        if ev.key() == "Escape" {
+            on_close.run(());
+        }
+    });
+
+    let positions = layout(&graph);
+    let position_of: HashMap<String, (f64, f64)> = graph
+        .nodes
+        .iter()
+        .zip(positions.iter())
+        .map(|(node, position)| (node.path.clone(), *position))
+        .collect();
+    let orphan_count = graph.nodes.iter().filter(|node| node.orphan).count();
+
+    let edges = graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let (x1, y1) = *position_of.get(&edge.source)?;
+            let (x2, y2) = *position_of.get(&edge.target)?;
+            Some(view! {
+                <line x1=x1 y1=y1 x2=x2 y2=y2 class="stroke-base-300 dark:stroke-base-700" stroke-width="1.5" />
+            })
+        })
+        .collect_view();
+
+    let nodes = graph
+        .nodes
+        .into_iter()
+        .zip(positions)
+        .map(|(node, (x, y))| {
+            let radius = 6.0 + node.incoming.min(10) as f64;
+            let is_current = current.as_deref() == Some(node.path.as_str());
+            let circle_class = if is_current {
+                "fill-brand-orange stroke-brand-orange"
+            } else if node.orphan {
+                "fill-base-50 dark:fill-base-900 stroke-brand-orange"
+            } else {
+                "fill-base-400 dark:fill-base-500 stroke-base-50 dark:stroke-base-900"
+            };
+            let path = node.path.clone();
+            view! {
+                <g class="cursor-pointer group" on:click=move |_| on_open.run(path.clone())>
+                    <circle
+                        cx=x
+                        cy=y
+                        r=radius
+                        class=circle_class
+                        stroke-width="2"
+                        stroke-dasharray=if node.orphan && !is_current { "3 2" } else { "none" }
+                    />
+                    <text
+                        x=x
+                        y=y + radius + 14.0
+                        text-anchor="middle"
+                        class="fill-base-600 dark:fill-base-300 group-hover:fill-brand-orange text-[12px] select-none"
+                    >
+                        {node.name}
+                    </text>
+                </g>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class="fixed inset-0 z-[100] flex items-center justify-center bg-base-900/50 backdrop-blur-sm p-4">
+            <div class="bg-base-50 dark:bg-base-900 w-full max-w-5xl h-[85vh] flex flex-col rounded-lg shadow-2xl border border-base-200 dark:border-base-800 animate-in zoom-in-95 duration-200">
+                <div class="flex items-center justify-between px-6 py-4 border-b border-base-200 dark:border-base-800">
+                    <div>
+                        <h3 class="text-lg font-bold text-base-900 dark:text-base-50">
+                            "Grafo de enlaces"
+                        </h3>
+                        <p class="text-xs text-base-500 dark:text-base-400">
+                            {format!("{} huérfanos: documentos sin enlaces entrantes ni salientes", orphan_count)}
+                        </p>
+                    </div>
+                    <button
+                        on:click=move |_| on_close.run(())
+                        class="px-4 py-2 text-sm font-medium text-base-600 dark:text-base-400 hover:bg-base-100 dark:hover:bg-base-800 rounded-md transition-colors"
+                    >
+                        "Cerrar"
+                    </button>
+                </div>
+                <svg class="flex-1 w-full" viewBox=format!("0 0 {} {}", WIDTH, HEIGHT) preserveAspectRatio="xMidYMid meet">
+                    <g>{edges}</g>
+                    <g>{nodes}</g>
+                </svg>
+            </div>
+        </div>
+    }
+}
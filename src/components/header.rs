@@ -1,14 +1,58 @@
 use leptos::prelude::*;
 
+use crate::utils::env::is_tauri;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+    Docx,
+}
+
+const EXPORT_CLASS: &str = "px-2 py-1 rounded-md text-[10px] font-mono text-base-400 hover:text-base-600 dark:hover:text-base-300 disabled:opacity-50 disabled:cursor-not-allowed transition-all";
+
 #[component]
 pub fn EditorHeader(
     selected_file: ReadSignal<Option<String>>,
+    is_exporting: ReadSignal<bool>,
+    on_export: Callback<ExportFormat>,
 ) -> impl IntoView {
+    let disabled = move || !is_tauri() || is_exporting.get() || selected_file.with(Option::is_none);
+
     view! {
-        <div class="h-12 border-b border-base-200 dark:border-base-800 flex items-center px-4 bg-base-50 dark:bg-base-900/50 backdrop-blur-md z-10">
+        <div class="h-12 border-b border-base-200 dark:border-base-800 flex items-center justify-between gap-4 px-4 bg-base-50 dark:bg-base-900/50 backdrop-blur-md z-10">
             <span class="text-xs font-mono text-base-400 dark:text-base-500 truncate">
                 {move || selected_file.get().unwrap_or_else(|| "Sin archivo seleccionado".to_string())}
             </span>
+            <div class="flex items-center gap-1 flex-shrink-0">
+                {move || is_exporting.get().then(|| view! {
+                    <span class="text-[10px] font-mono text-base-400 animate-pulse">"Exportando..."</span>
+                })}
+                <button
+                    class=EXPORT_CLASS
+                    title="Exportar como página HTML independiente"
+                    disabled=disabled
+                    on:click=move |_| on_export.run(ExportFormat::Html)
+                >
+                    "HTML"
+                </button>
+                <button
+                    class=EXPORT_CLASS
+                    title="Exportar como PDF"
+                    disabled=disabled
+                    on:click=move |_| on_export.run(ExportFormat::Pdf)
+                >
+                    "PDF"
+                </button>
+                <button
+                    class=EXPORT_CLASS
+                    title="Exportar como documento de Word"
+                    disabled=disabled
+                    on:click=move |_| on_export.run(ExportFormat::Docx)
+                >
+                    "DOCX"
+                </button>
+            </div>
         </div>
     }
 }
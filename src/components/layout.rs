@@ -1,11 +1,34 @@
-use crate::components::modals::{AlertModal, DeleteConfirmModal, RenameConfirmModal};
+use crate::components::modals::{
+    AlertModal, DeleteConfirmModal, RefactorPreviewModal, RenameConfirmModal, RenameHeadingModal,
+};
+use crate::components::assets::AssetsPanel;
 use crate::components::sidebar::Sidebar;
-use crate::components::editor::EditorPane;
-use crate::components::header::EditorHeader;
-use crate::types::FileEntry;
+use crate::components::editor::{
+    cm_goto_line, cm_insert_text, cm_set_diagnostics, cm_set_live_spans, cm_set_wiki_targets, reveal_heading,
+    EditorPane,
+};
+use crate::components::graph::LinkGraphView;
+use crate::components::header::{EditorHeader, ExportFormat};
+use crate::components::problems::ProblemsPanel;
+use crate::components::table_editor::TableEditor;
+use crate::components::tasks::TasksPanel;
+use crate::types::{file_paths, Backlink, FileEntry, LinkGraph, RenamePlan, UndoOutcome};
 use crate::utils::env::is_tauri;
-use crate::utils::markdown::{extract_headings, render_markdown, Heading};
+use crate::utils::export::prerender;
+use crate::utils::markdown::{extract_headings, render_markdown, update_toc_blocks, Heading};
 use crate::utils::tauri_bridge::{self, invoke};
+use codedocs_md::assets::{image_markdown, AssetReport};
+use codedocs_md::check::LinkDiagnostic;
+use codedocs_md::edit::FileEdits;
+use codedocs_md::export::HtmlExportOptions;
+use codedocs_md::format::format_markdown;
+use codedocs_md::links::parent_dir;
+use codedocs_md::lint::lint_document;
+use codedocs_md::refactor::RefactorEdits;
+use codedocs_md::live::live_spans;
+use codedocs_md::table::table_at_line;
+use codedocs_md::tasks::{extract_tasks, toggle_task, Task};
+use codedocs_md::{ProjectConfig, RenderOptions};
 use wasm_bindgen::{JsCast, JsValue};
 use leptos::logging::error;
 use leptos::prelude::*;
@@ -15,6 +38,41 @@ use leptos::reactive::spawn_local;
 pub enum ViewMode {
     Raw,
     Formatted,
+    /// Editor and preview side by side, scrolling together.
+    Split,
+    /// The editor rendering markdown in place, except on the cursor's line.
+    Live,
+}
+
+async fn fetch_link_problems(folder_path: String) -> Option<Vec<LinkDiagnostic>> {
+    let args = tauri_bridge::args_with("folderPath", &folder_path);
+    match invoke("check_links", args).await {
+        Ok(problems_js) => match serde_wasm_bindgen::from_value::<Vec<LinkDiagnostic>>(problems_js) {
+            Ok(problems) => Some(problems),
+            Err(err) => {
+                error!("Error deserializing link problems: {:?}", err);
+                None
+            }
+        },
+        Err(err) => {
+            error!("Error checking links: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Runs `action` once `delay` milliseconds pass without another call on the
+/// same `timer`, so work that follows the text waits for a pause in typing.
+fn debounce(timer: RwSignal<Option<i32>>, delay: i32, action: impl FnOnce() + 'static) {
+    let Some(window) = web_sys::window() else { return };
+    if let Some(id) = timer.get_untracked() {
+        window.clear_timeout_with_handle(id);
+    }
+    let callback = wasm_bindgen::closure::Closure::once_into_js(action);
+    let id = window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), delay)
+        .unwrap_or(0);
+    timer.set(Some(id));
 }
 
 #[component]
@@ -28,6 +86,43 @@ pub fn Layout() -> impl IntoView {
     let is_saving = RwSignal::new(false);
     let is_loading_file = RwSignal::new(false);
     let (headings, set_headings) = signal(Vec::<Heading>::new());
+    let (active_heading, set_active_heading) = signal::<Option<usize>>(None);
+    let project_config = RwSignal::new(ProjectConfig::default());
+    let trusted_html = RwSignal::new(false);
+    let (pending_anchor, set_pending_anchor) = signal::<Option<String>>(None);
+    let (pending_line, set_pending_line) = signal::<Option<usize>>(None);
+    let (problems, set_problems) = signal(Vec::<LinkDiagnostic>::new());
+    let (show_problems, set_show_problems) = signal(false);
+    let (is_checking_links, set_is_checking_links) = signal(false);
+    let (cursor_line, set_cursor_line) = signal(1usize);
+    // The table grid opens by itself while the cursor is in a table, unless
+    // it was dismissed for that table; outside tables it shows the form to
+    // create one.
+    let (show_table_form, set_show_table_form) = signal(false);
+    let dismissed_table = RwSignal::new(None::<usize>);
+    let table_under_cursor = Memo::new(move |_| {
+        let line = cursor_line.get();
+        editor_content.with(|content| table_at_line(content, line).map(|table| table.range.start))
+    });
+    Effect::new(move |_| match table_under_cursor.get() {
+        Some(_) => set_show_table_form.set(false),
+        None => dismissed_table.set(None),
+    });
+    let show_table_editor = Memo::new(move |_| {
+        let grid = table_under_cursor.get().is_some_and(|start| dismissed_table.get() != Some(start));
+        (grid || show_table_form.get()) && view_mode.get() != ViewMode::Formatted
+    });
+    let close_table_editor = move || match table_under_cursor.get_untracked() {
+        Some(start) => dismissed_table.set(Some(start)),
+        None => set_show_table_form.set(false),
+    };
+    let (tasks, set_tasks) = signal(Vec::<Task>::new());
+    let (show_tasks, set_show_tasks) = signal(false);
+    let (is_loading_tasks, set_is_loading_tasks) = signal(false);
+    let (asset_report, set_asset_report) = signal(AssetReport::default());
+    let (show_assets, set_show_assets) = signal(false);
+    let (is_loading_assets, set_is_loading_assets) = signal(false);
+    let asset_prefix = is_tauri().then(|| tauri_bridge::convert_file_src("", "project"));
 
     let (sidebar_width, set_sidebar_width) = signal(280.0);
     let (is_resizing_sidebar, set_is_resizing_sidebar) = signal(false);
@@ -35,6 +130,17 @@ pub fn Layout() -> impl IntoView {
     let (file_to_delete, set_file_to_delete) = signal::<Option<String>>(None);
     let (file_to_rename, set_file_to_rename) = signal::<Option<String>>(None);
     let (show_clear_confirm, set_show_clear_confirm) = signal(false);
+    let (is_exporting, set_is_exporting) = signal(false);
+    let (file_to_create, set_file_to_create) = signal::<Option<String>>(None);
+    let (backlinks, set_backlinks) = signal(Vec::<Backlink>::new());
+    let (link_graph, set_link_graph) = signal::<Option<LinkGraph>>(None);
+    let (auto_save_timer_id, set_auto_save_timer_id) = signal::<Option<i32>>(None);
+    let (rename_plan, set_rename_plan) = signal::<Option<RenamePlan>>(None);
+    let (heading_to_rename, set_heading_to_rename) = signal::<Option<Heading>>(None);
+    let (heading_plan, set_heading_plan) = signal::<Option<(String, RefactorEdits)>>(None);
+    let (undo_notice, set_undo_notice) = signal::<Option<String>>(None);
+    // Label and file moves of each refactoring the backend can still undo, newest last.
+    let refactor_history = RwSignal::new(Vec::<(String, Vec<(String, String)>)>::new());
 
     let _ = window_event_listener(leptos::ev::mousemove, move |ev: leptos::ev::MouseEvent| {
         if is_resizing_sidebar.get() {
@@ -49,6 +155,14 @@ pub fn Layout() -> impl IntoView {
         set_is_resizing_sidebar.set(false);
     });
 
+    let format_document = Callback::new(move |_| {
+        let content = editor_content.get_untracked();
+        let formatted = project_config.with_untracked(|config| format_markdown(&content, &config.format));
+        if formatted != content {
+            set_editor_content.set(formatted);
+        }
+    });
+
     let _ = window_event_listener(leptos::ev::keydown, move |ev: leptos::ev::KeyboardEvent| {
         let key = ev.key();
         let ctrl = ev.ctrl_key() || ev.meta_key();
@@ -58,12 +172,33 @@ pub fn Layout() -> impl IntoView {
         } else if ctrl && key == "2" {
             ev.prevent_default();
             set_view_mode.set(ViewMode::Formatted);
+        } else if ctrl && key == "3" {
+            ev.prevent_default();
+            set_view_mode.set(ViewMode::Split);
+        } else if ctrl && key == "4" {
+            ev.prevent_default();
+            set_view_mode.set(ViewMode::Live);
+        } else if ev.shift_key() && ev.alt_key() && ev.code() == "KeyF" {
+            ev.prevent_default();
+            format_document.run(());
+        }
+    });
+
+    let render_options = Memo::new(move |_| {
+        let project_root = path.get();
+        RenderOptions {
+            document_path: selected_file.get(),
+            project_root: (project_root != "No se ha seleccionado ninguna carpeta").then_some(project_root),
+            asset_prefix: asset_prefix.clone(),
+            project_files: files.with(|tree| file_paths(tree)),
+            trusted_html: trusted_html.get(),
+            ..project_config.with(|config| config.render_options())
         }
     });
 
     Effect::new(move |_| {
         let content = editor_content.get();
-        let html_output = render_markdown(&content);
+        let html_output = render_options.with(|options| render_markdown(&content, options));
         set_preview_html.set(html_output);
         set_headings.set(extract_headings(&content));
 
@@ -76,18 +211,71 @@ pub fn Layout() -> impl IntoView {
         });
     });
 
+    let lint_timer = RwSignal::new(None::<i32>);
+    Effect::new(move |_| {
+        let content = editor_content.get();
+        let config = project_config.with(|config| config.lint.clone());
+        debounce(lint_timer, 250, move || {
+            cm_set_diagnostics(&content, &lint_document(&content, &config));
+        });
+    });
+
+    let live_timer = RwSignal::new(None::<i32>);
+    Effect::new(move |_| {
+        if view_mode.get() != ViewMode::Live {
+            return;
+        }
+        let content = editor_content.get();
+        let options = render_options.get();
+        debounce(live_timer, 200, move || {
+            cm_set_live_spans(&content, &live_spans(&content, &options));
+        });
+    });
+
     Effect::new(move |_| {
         let current_path = path.get();
         if current_path == "No se ha seleccionado ninguna carpeta" || !is_tauri() {
             return;
         }
+        // Until the app says otherwise, a newly opened project is not trusted.
+        trusted_html.set(false);
         let fp = current_path.clone();
         spawn_local(async move {
             let args = tauri_bridge::args_with("folderPath", &fp);
             let _ = invoke("watch_folder", args).await;
+
+            let args = tauri_bridge::args_with("folderPath", &fp);
+            match invoke("load_project_config", args).await {
+                Ok(config_js) => match serde_wasm_bindgen::from_value::<ProjectConfig>(config_js) {
+                    Ok(config) => project_config.set(config),
+                    Err(err) => error!("Error deserializing project config: {:?}", err),
+                },
+                Err(err) => {
+                    error!("Error loading project config: {:?}", err);
+                    project_config.set(ProjectConfig::default());
+                }
+            }
+
+            let args = tauri_bridge::args_with("folderPath", &fp);
+            let trusted = invoke("is_project_trusted", args).await.ok().and_then(|trusted| trusted.as_bool());
+            trusted_html.set(trusted.unwrap_or(false));
         });
     });
 
+    let toggle_trusted_html = move |_: leptos::ev::MouseEvent| {
+        let current_path = path.get_untracked();
+        let trusted = !trusted_html.get_untracked();
+        spawn_local(async move {
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "folderPath", JsValue::from(current_path));
+            tauri_bridge::set_arg(&args, "trusted", JsValue::from_bool(trusted));
+            match invoke("set_project_trusted", args.into()).await {
+                Ok(_) => trusted_html.set(trusted),
+                Err(err) => error!("Error saving project trust: {:?}", err),
+            }
+        });
+    };
+
     let refresh_files = move || {
         let current_path = path.get();
         if current_path != "No se ha seleccionado ninguna carpeta" {
@@ -103,6 +291,46 @@ pub fn Layout() -> impl IntoView {
         }
     };
 
+    let refresh_backlinks = move || {
+        let Some(file_path) = selected_file.get_untracked() else {
+            set_backlinks.set(Vec::new());
+            return;
+        };
+        if !is_tauri() {
+            return;
+        }
+        spawn_local(async move {
+            let args = tauri_bridge::args_with("path", &file_path);
+            match invoke("get_backlinks", args).await {
+                Ok(links_js) => match serde_wasm_bindgen::from_value::<Vec<Backlink>>(links_js) {
+                    Ok(links) => set_backlinks.set(links),
+                    Err(err) => error!("Error deserializing backlinks: {:?}", err),
+                },
+                Err(err) => error!("Error loading backlinks: {:?}", err),
+            }
+        });
+    };
+
+    Effect::new(move |_| {
+        selected_file.track();
+        refresh_backlinks();
+    });
+
+    let show_link_graph = Callback::new(move |_| {
+        if !is_tauri() {
+            return;
+        }
+        spawn_local(async move {
+            match invoke("get_link_graph", JsValue::NULL).await {
+                Ok(graph_js) => match serde_wasm_bindgen::from_value::<LinkGraph>(graph_js) {
+                    Ok(graph) => set_link_graph.set(Some(graph)),
+                    Err(err) => error!("Error deserializing link graph: {:?}", err),
+                },
+                Err(err) => error!("Error loading link graph: {:?}", err),
+            }
+        });
+    });
+
     {
         let set_editor_content = set_editor_content.clone();
         let selected_file = selected_file.clone();
@@ -114,8 +342,9 @@ pub fn Layout() -> impl IntoView {
                 if is_saving.get() {
                     return;
                 }
+                refresh_backlinks();
                 if let Some(payload) = js_sys::Reflect::get(&event, &JsValue::from_str("payload")).ok() {
-                    if let Some(changed_paths) = payload.as_string() {
+                    if let Ok(changed_paths) = serde_wasm_bindgen::from_value::<Vec<String>>(payload) {
                         let current_file = selected_file.get();
                         if let Some(ref current) = current_file {
                             if changed_paths.contains(current) {
@@ -169,26 +398,249 @@ pub fn Layout() -> impl IntoView {
         }
     };
 
+    // Refactorings rewrite files on disk, so the open buffer is reloaded afterwards.
+    let reload_selected_file = move || {
+        let Some(file_path) = selected_file.get_untracked() else { return };
+        spawn_local(async move {
+            is_loading_file.set(true);
+            let args = tauri_bridge::args_with("pathStr", &file_path);
+            match invoke("read_file", args).await {
+                Ok(content_js) => {
+                    if let Some(content) = content_js.as_string() {
+                        set_editor_content.set(content);
+                    }
+                }
+                Err(err) => error!("Error reading file: {:?}", err),
+            }
+            is_loading_file.set(false);
+        });
+    };
+
+    let apply_rename_plan = move |plan: RenamePlan| {
+        spawn_local(async move {
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "plan", serde_wasm_bindgen::to_value(&plan).unwrap_or(JsValue::NULL));
+            match invoke("apply_rename", args.into()).await {
+                Ok(label_js) => {
+                    let label = label_js.as_string().unwrap_or_default();
+                    refactor_history.update(|history| {
+                        history.push((label, vec![(plan.old_path.clone(), plan.new_path.clone())]))
+                    });
+                    if selected_file.get_untracked().as_deref() == Some(plan.old_path.as_str()) {
+                        set_selected_file.set(Some(plan.new_path.clone()));
+                    }
+                    refresh_files();
+                    reload_selected_file();
+                }
+                Err(err) => error!("Error renaming file: {:?}", err),
+            }
+        });
+    };
+
+    // Refactorings plan against the files on disk, so the open buffer is saved
+    // first. A pending auto-save would otherwise write stale content, or
+    // recreate a renamed file at its old path, after the refactoring.
+    let flush_selected_file = move || {
+        if let (Some(id), Some(window)) = (auto_save_timer_id.get_untracked(), web_sys::window()) {
+            window.clear_timeout_with_handle(id);
+        }
+        async move {
+            if let Some(file_path) = selected_file.get_untracked() {
+                is_saving.set(true);
+                let args = js_sys::Object::new();
+                tauri_bridge::set_arg(&args, "pathStr", JsValue::from(file_path));
+                tauri_bridge::set_arg(&args, "content", JsValue::from(editor_content.get_untracked()));
+                let _ = invoke("save_file", args.into()).await;
+                is_saving.set(false);
+            }
+        }
+    };
+
+    // Exports read the saved file, so pending edits are written first.
+    let export_document = Callback::new(move |format: ExportFormat| {
+        let Some(file_path) = selected_file.get_untracked() else { return };
+        let flushed = flush_selected_file();
+        set_is_exporting.set(true);
+        spawn_local(async move {
+            flushed.await;
+            let content = editor_content.get_untracked();
+            let dark = document().document_element().is_some_and(|el| el.class_list().contains("dark"));
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "path", JsValue::from(file_path));
+            let command = match format {
+                ExportFormat::Html => {
+                    let options = HtmlExportOptions {
+                        dark,
+                        prerendered: prerender(&content).await,
+                        ..HtmlExportOptions::default()
+                    };
+                    tauri_bridge::set_arg(&args, "options", serde_wasm_bindgen::to_value(&options).unwrap_or(JsValue::NULL));
+                    "export_html"
+                }
+                ExportFormat::Pdf => "export_pdf",
+                ExportFormat::Docx => {
+                    let prerendered = prerender(&content).await;
+                    tauri_bridge::set_arg(&args, "prerendered", serde_wasm_bindgen::to_value(&prerendered).unwrap_or(JsValue::NULL));
+                    "export_docx"
+                }
+            };
+            if let Err(err) = invoke(command, args.into()).await {
+                error!("Error exporting document: {:?}", err);
+            }
+            set_is_exporting.set(false);
+        });
+    });
+
+    let export_project = Callback::new(move |command: &'static str| {
+        let current_path = path.get_untracked();
+        if current_path == "No se ha seleccionado ninguna carpeta" || !is_tauri() {
+            return;
+        }
+        let flushed = flush_selected_file();
+        set_is_exporting.set(true);
+        spawn_local(async move {
+            flushed.await;
+            let args = tauri_bridge::args_with("folderPath", &current_path);
+            if let Err(err) = invoke(command, args).await {
+                error!("Error exporting project: {:?}", err);
+            }
+            set_is_exporting.set(false);
+        });
+    });
+
+    // Shows the link edits for review, or renames right away if there are none.
     let handle_rename_confirm = move |new_name: String| {
         if let Some(old_path) = file_to_rename.get() {
+            set_file_to_rename.set(None);
+            let flushed = flush_selected_file();
             spawn_local(async move {
+                flushed.await;
                 let args = js_sys::Object::new();
-                tauri_bridge::set_arg(&args, "oldPath", JsValue::from(old_path.clone()));
+                tauri_bridge::set_arg(&args, "oldPath", JsValue::from(old_path));
                 tauri_bridge::set_arg(&args, "newName", JsValue::from(new_name));
-                let result = invoke("rename_file", args.into()).await;
-                match result {
-                    Ok(_) => {
-                        set_file_to_rename.set(None);
-                        refresh_files();
-                    }
-                    Err(err) => {
-                        error!("Error renaming file: {:?}", err);
-                    }
+                match invoke("preview_rename", args.into()).await {
+                    Ok(plan_js) => match serde_wasm_bindgen::from_value::<RenamePlan>(plan_js) {
+                        Ok(plan) if plan.edits.is_empty() && plan.skipped.is_empty() => apply_rename_plan(plan),
+                        Ok(plan) => set_rename_plan.set(Some(plan)),
+                        Err(err) => error!("Error deserializing rename plan: {:?}", err),
+                    },
+                    Err(err) => error!("Error renaming file: {:?}", err),
                 }
             });
         }
     };
 
+    let on_heading_rename = Callback::new(move |index: usize| {
+        if !is_tauri() || selected_file.get_untracked().is_none() {
+            return;
+        }
+        set_heading_to_rename.set(headings.with_untracked(|h| h.get(index).cloned()));
+    });
+
+    // F2 in the editor renames the heading of the section the cursor is in.
+    let on_rename_heading_at_line = Callback::new(move |line: usize| {
+        if let Some(index) = headings.with_untracked(|h| h.iter().rposition(|heading| heading.line <= line)) {
+            on_heading_rename.run(index);
+        }
+    });
+
+    // Pasted or dropped images are saved as assets of the document and
+    // linked at the cursor.
+    let on_paste_image = Callback::new(move |(name, bytes): (String, Vec<u8>)| {
+        let Some(file_path) = selected_file.get_untracked() else { return };
+        if !is_tauri() {
+            return;
+        }
+        spawn_local(async move {
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "documentPath", JsValue::from(file_path));
+            tauri_bridge::set_arg(&args, "fileName", JsValue::from(name.clone()));
+            tauri_bridge::set_arg(&args, "bytes", serde_wasm_bindgen::to_value(&bytes).unwrap_or(JsValue::NULL));
+            match invoke("save_asset", args.into()).await {
+                Ok(link_js) => {
+                    let link = link_js.as_string().unwrap_or_default();
+                    cm_insert_text(&image_markdown(&name, &link));
+                }
+                Err(err) => error!("Error saving image: {:?}", err),
+            }
+        });
+    });
+
+    let apply_heading_plan = move |new_text: String, edits: Vec<FileEdits>| {
+        spawn_local(async move {
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "edits", serde_wasm_bindgen::to_value(&edits).unwrap_or(JsValue::NULL));
+            tauri_bridge::set_arg(&args, "newText", JsValue::from(new_text));
+            match invoke("apply_heading_rename", args.into()).await {
+                Ok(label_js) => {
+                    let label = label_js.as_string().unwrap_or_default();
+                    refactor_history.update(|history| history.push((label, Vec::new())));
+                    reload_selected_file();
+                }
+                Err(err) => error!("Error renaming heading: {:?}", err),
+            }
+        });
+    };
+
+    // Edits that stay inside the open document are applied right away;
+    // anything touching other files is shown for review first.
+    let handle_heading_rename_confirm = move |new_text: String| {
+        let Some(heading) = heading_to_rename.get_untracked() else { return };
+        let Some(file_path) = selected_file.get_untracked() else { return };
+        set_heading_to_rename.set(None);
+        if new_text.trim() == heading.text {
+            return;
+        }
+        let flushed = flush_selected_file();
+        spawn_local(async move {
+            flushed.await;
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "path", JsValue::from(file_path.clone()));
+            tauri_bridge::set_arg(&args, "line", JsValue::from(heading.line as u32));
+            tauri_bridge::set_arg(&args, "newText", JsValue::from(new_text.clone()));
+            match invoke("preview_heading_rename", args.into()).await {
+                Ok(plan_js) => match serde_wasm_bindgen::from_value::<RefactorEdits>(plan_js) {
+                    Ok(plan) if plan.skipped.is_empty() && plan.edits.iter().all(|file| file.path == file_path) => {
+                        apply_heading_plan(new_text, plan.edits)
+                    }
+                    Ok(plan) => set_heading_plan.set(Some((new_text, plan))),
+                    Err(err) => error!("Error deserializing heading rename: {:?}", err),
+                },
+                Err(err) => error!("Error renaming heading: {:?}", err),
+            }
+        });
+    };
+
+    let undo_refactor = move |_| {
+        spawn_local(async move {
+            match invoke("undo_refactor", JsValue::NULL).await {
+                Ok(outcome_js) => {
+                    let outcome = serde_wasm_bindgen::from_value::<UndoOutcome>(outcome_js).ok();
+                    let kept = outcome.as_ref().map(|outcome| outcome.kept.clone()).unwrap_or_default();
+                    let moves = refactor_history
+                        .try_update(|history| history.pop().map(|(_, moves)| moves))
+                        .flatten()
+                        .unwrap_or_default();
+                    for (from, to) in moves.into_iter().rev() {
+                        if !kept.contains(&to) && selected_file.get_untracked().as_deref() == Some(to.as_str()) {
+                            set_selected_file.set(Some(from));
+                        }
+                    }
+                    if let Some(outcome) = outcome.filter(|outcome| !outcome.kept.is_empty()) {
+                        set_undo_notice.set(Some(format!(
+                            "«{}» se deshizo salvo en estos archivos, que cambiaron después y se dejaron como están: {}",
+                            outcome.label,
+                            outcome.kept.join(", ")
+                        )));
+                    }
+                    refresh_files();
+                    reload_selected_file();
+                }
+                Err(err) => error!("Error undoing refactoring: {:?}", err),
+            }
+        });
+    };
+
     let create_new_file = Callback::new(move |_| {
         let current_path = path.get();
         if current_path != "No se ha seleccionado ninguna carpeta" {
@@ -212,8 +664,14 @@ pub fn Layout() -> impl IntoView {
 
     let on_save = Callback::new(move |_| {
         if let Some(file_path) = selected_file.get() {
+            if project_config.with_untracked(|config| config.format.format_on_save) {
+                format_document.run(());
+            }
+            let content = project_config.with_untracked(|config| update_toc_blocks(&editor_content.get(), &config.toc));
+            if content != editor_content.get_untracked() {
+                set_editor_content.set(content.clone());
+            }
             if is_tauri() {
-                let content = editor_content.get();
                 is_saving.set(true);
                 spawn_local(async move {
                     let args = js_sys::Object::new();
@@ -226,8 +684,6 @@ pub fn Layout() -> impl IntoView {
         }
     });
 
-    let (auto_save_timer_id, set_auto_save_timer_id) = signal::<Option<i32>>(None);
-
     Effect::new(move |_| {
         let content = editor_content.get();
         let file_path = selected_file.get();
@@ -309,6 +765,244 @@ pub fn Layout() -> impl IntoView {
         });
     });
 
+    let on_heading_click = Callback::new(move |index: usize| {
+        if let Some(heading) = headings.with_untracked(|h| h.get(index).cloned()) {
+            set_active_heading.set(Some(index));
+            reveal_heading(view_mode.get_untracked(), &heading);
+        }
+    });
+
+    let reveal_anchor = move |anchor: &str| {
+        let heading = headings.with_untracked(|h| h.iter().position(|heading| heading.slug == anchor));
+        if let Some(index) = heading {
+            on_heading_click.run(index);
+        }
+    };
+
+    let on_open_document = Callback::new(move |(target, anchor): (String, Option<String>)| {
+        if selected_file.get_untracked().as_deref() == Some(target.as_str()) {
+            if let Some(anchor) = anchor {
+                reveal_anchor(&anchor);
+            }
+        } else if is_tauri() && !files.with_untracked(|tree| file_paths(tree).contains(&target)) {
+            set_file_to_create.set(Some(target));
+        } else {
+            set_pending_anchor.set(anchor);
+            on_file_click.run(target);
+        }
+    });
+
+    let handle_create_confirm = move |_| {
+        let Some(target) = file_to_create.get_untracked() else { return };
+        set_file_to_create.set(None);
+        spawn_local(async move {
+            let name = target.rsplit(['/', '\\']).next().unwrap_or_default().to_string();
+            let title = name.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(&name).to_string();
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "folderPath", JsValue::from(parent_dir(&target)));
+            tauri_bridge::set_arg(&args, "name", JsValue::from(name));
+            tauri_bridge::set_arg(&args, "title", JsValue::from(title));
+            match invoke("create_file", args.into()).await {
+                Ok(new_path_js) => {
+                    refresh_files();
+                    if let Some(new_path) = new_path_js.as_string() {
+                        on_file_click.run(new_path);
+                    }
+                }
+                Err(err) => error!("Error creating file: {:?}", err),
+            }
+        });
+    };
+
+    // Keep the editor's `[[` completions in step with the project tree.
+    Effect::new(move |_| {
+        files.track();
+        let current_path = path.get_untracked();
+        if current_path == "No se ha seleccionado ninguna carpeta" || !is_tauri() {
+            return;
+        }
+        spawn_local(async move {
+            let args = tauri_bridge::args_with("folderPath", &current_path);
+            match invoke("list_wiki_targets", args).await {
+                Ok(targets) => cm_set_wiki_targets(&targets),
+                Err(err) => error!("Error listing wiki targets: {:?}", err),
+            }
+        });
+    });
+
+    // Jump to the heading or line a link or problem pointed at once the new file is rendered.
+    Effect::new(move |_| {
+        headings.track();
+        if is_loading_file.get() {
+            return;
+        }
+        if let Some(anchor) = pending_anchor.get_untracked() {
+            set_pending_anchor.set(None);
+            spawn_local(async move { reveal_anchor(&anchor) });
+        }
+        if let Some(line) = pending_line.get_untracked() {
+            set_pending_line.set(None);
+            spawn_local(async move { cm_goto_line(line as u32) });
+        }
+    });
+
+    let open_at_line = Callback::new(move |(target, line): (String, usize)| {
+        if view_mode.get_untracked() == ViewMode::Formatted {
+            set_view_mode.set(ViewMode::Raw);
+        }
+        if selected_file.get_untracked().as_deref() == Some(target.as_str()) {
+            cm_goto_line(line as u32);
+        } else {
+            set_pending_line.set(Some(line));
+            on_file_click.run(target);
+        }
+    });
+
+    let check_links = Callback::new(move |_| {
+        let current_path = path.get_untracked();
+        if current_path == "No se ha seleccionado ninguna carpeta" || !is_tauri() {
+            return;
+        }
+        set_show_problems.set(true);
+        set_is_checking_links.set(true);
+        spawn_local(async move {
+            if let Some(found) = fetch_link_problems(current_path).await {
+                set_problems.set(found);
+            }
+            set_is_checking_links.set(false);
+        });
+    });
+
+    // The open document is fixed in the editor and saved so the buffer never
+    // goes stale; other files are patched on disk by the backend.
+    let apply_link_fix = Callback::new(move |problem: LinkDiagnostic| {
+        let Some(fix) = problem.fix.clone() else { return };
+        spawn_local(async move {
+            let result = if selected_file.get_untracked().as_deref() == Some(problem.file.as_str()) {
+                match fix.apply(&editor_content.get_untracked()) {
+                    Some(fixed) => {
+                        set_editor_content.set(fixed.clone());
+                        is_saving.set(true);
+                        let args = js_sys::Object::new();
+                        tauri_bridge::set_arg(&args, "pathStr", JsValue::from(problem.file.clone()));
+                        tauri_bridge::set_arg(&args, "content", JsValue::from(fixed));
+                        let result = invoke("save_file", args.into()).await;
+                        is_saving.set(false);
+                        result.map(|_| ())
+                    }
+                    None => Err(JsValue::from_str("El documento cambió desde la última comprobación")),
+                }
+            } else {
+                let args = js_sys::Object::new();
+                tauri_bridge::set_arg(&args, "path", JsValue::from(problem.file.clone()));
+                tauri_bridge::set_arg(&args, "fix", serde_wasm_bindgen::to_value(&fix).unwrap_or(JsValue::NULL));
+                invoke("apply_link_fix", args.into()).await.map(|_| ())
+            };
+            if let Err(err) = result {
+                error!("Error applying link fix: {:?}", err);
+            }
+            check_links.run(());
+        });
+    });
+
+    let load_tasks = Callback::new(move |_| {
+        let current_path = path.get_untracked();
+        if current_path == "No se ha seleccionado ninguna carpeta" || !is_tauri() {
+            return;
+        }
+        set_show_tasks.set(true);
+        set_is_loading_tasks.set(true);
+        spawn_local(async move {
+            let args = tauri_bridge::args_with("folderPath", &current_path);
+            match invoke("list_tasks", args).await {
+                Ok(tasks_js) => match serde_wasm_bindgen::from_value::<Vec<Task>>(tasks_js) {
+                    Ok(found) => set_tasks.set(found),
+                    Err(err) => error!("Error deserializing tasks: {:?}", err),
+                },
+                Err(err) => error!("Error listing tasks: {:?}", err),
+            }
+            set_is_loading_tasks.set(false);
+        });
+    });
+
+    let load_assets = Callback::new(move |_| {
+        let current_path = path.get_untracked();
+        if current_path == "No se ha seleccionado ninguna carpeta" || !is_tauri() {
+            return;
+        }
+        set_show_assets.set(true);
+        set_is_loading_assets.set(true);
+        spawn_local(async move {
+            let args = tauri_bridge::args_with("folderPath", &current_path);
+            match invoke("asset_report", args).await {
+                Ok(report_js) => match serde_wasm_bindgen::from_value::<AssetReport>(report_js) {
+                    Ok(report) => set_asset_report.set(report),
+                    Err(err) => error!("Error deserializing asset report: {:?}", err),
+                },
+                Err(err) => error!("Error building asset report: {:?}", err),
+            }
+            set_is_loading_assets.set(false);
+        });
+    });
+
+    let trash_assets = Callback::new(move |paths: Vec<String>| {
+        let current_path = path.get_untracked();
+        set_is_loading_assets.set(true);
+        spawn_local(async move {
+            let args = js_sys::Object::new();
+            tauri_bridge::set_arg(&args, "folderPath", JsValue::from(current_path));
+            tauri_bridge::set_arg(&args, "paths", serde_wasm_bindgen::to_value(&paths).unwrap_or(JsValue::NULL));
+            if let Err(err) = invoke("trash_assets", args.into()).await {
+                error!("Error moving assets to trash: {:?}", err);
+            }
+            load_assets.run(());
+        });
+    });
+
+    // Clicking a checkbox in the preview; auto-save writes the change.
+    let on_toggle_task = Callback::new(move |line: usize| {
+        let content = editor_content.get_untracked();
+        if let Some(toggled) = toggle_task(&content, line).and_then(|edit| edit.apply(&content)) {
+            set_editor_content.set(toggled);
+        }
+    });
+
+    // Like link fixes: the open document is changed in the editor and saved,
+    // other files are patched on disk by the backend.
+    let toggle_listed_task = Callback::new(move |task: Task| {
+        spawn_local(async move {
+            let result = if selected_file.get_untracked().as_deref() == Some(task.file.as_str()) {
+                let content = editor_content.get_untracked();
+                // Unsaved edits may have moved the task since the list was loaded.
+                let same_task = extract_tasks(&content)
+                    .iter()
+                    .any(|t| t.line == task.line && t.text == task.text && !t.checked);
+                match toggle_task(&content, task.line).and_then(|edit| edit.apply(&content)).filter(|_| same_task) {
+                    Some(toggled) => {
+                        set_editor_content.set(toggled.clone());
+                        is_saving.set(true);
+                        let args = js_sys::Object::new();
+                        tauri_bridge::set_arg(&args, "pathStr", JsValue::from(task.file.clone()));
+                        tauri_bridge::set_arg(&args, "content", JsValue::from(toggled));
+                        let result = invoke("save_file", args.into()).await;
+                        is_saving.set(false);
+                        result.map(|_| ())
+                    }
+                    None => Err(JsValue::from_str("El documento cambió desde que se cargaron las tareas")),
+                }
+            } else {
+                let args = js_sys::Object::new();
+                tauri_bridge::set_arg(&args, "path", JsValue::from(task.file.clone()));
+                tauri_bridge::set_arg(&args, "line", JsValue::from(task.line as u32));
+                invoke("toggle_task_in_file", args.into()).await.map(|_| ())
+            };
+            if let Err(err) = result {
+                error!("Error toggling task: {:?}", err);
+            }
+            load_tasks.run(());
+        });
+    });
+
     let clear_editor = Callback::new(move |_| {
         set_show_clear_confirm.set(true);
     });
@@ -348,6 +1042,11 @@ pub fn Layout() -> impl IntoView {
                     on_rename=on_rename_request
                     create_new_file=create_new_file
                     headings=headings
+                    active_heading=active_heading
+                    on_heading_click=on_heading_click
+                    on_heading_rename=on_heading_rename
+                    backlinks=backlinks
+                    on_show_graph=show_link_graph
                 />
 
                 <div
@@ -356,7 +1055,7 @@ pub fn Layout() -> impl IntoView {
                 />
 
                 <main class="flex-1 flex flex-col min-w-0 bg-base-50 dark:bg-base-900 overflow-hidden">
-                    <EditorHeader selected_file=selected_file />
+                    <EditorHeader selected_file=selected_file is_exporting=is_exporting on_export=export_document />
 
                     <EditorPane
                         editor_content=editor_content
@@ -364,10 +1063,60 @@ pub fn Layout() -> impl IntoView {
                         preview_html=preview_html
                         view_mode=view_mode
                         on_save=on_save
+                        headings=headings
+                        set_active_heading=set_active_heading
+                        set_cursor_line=set_cursor_line
+                        on_open_document=on_open_document
+                        on_rename_heading=on_rename_heading_at_line
+                        on_toggle_task=on_toggle_task
+                        on_paste_image=on_paste_image
                     />
                 </main>
             </div>
 
+            {move || show_table_editor.get().then(|| view! {
+                <TableEditor
+                    editor_content=editor_content
+                    set_editor_content=set_editor_content
+                    cursor_line=cursor_line
+                    on_close=Callback::new(move |_| close_table_editor())
+                />
+            })}
+
+            {move || show_tasks.get().then(|| view! {
+                <TasksPanel
+                    tasks=tasks
+                    is_loading=is_loading_tasks
+                    on_open=open_at_line
+                    on_toggle=toggle_listed_task
+                    on_refresh=load_tasks
+                    on_close=Callback::new(move |_| set_show_tasks.set(false))
+                />
+            })}
+
+            {move || show_assets.get().then(|| view! {
+                <AssetsPanel
+                    report=asset_report
+                    root=path
+                    is_loading=is_loading_assets
+                    on_open=open_at_line
+                    on_trash=trash_assets
+                    on_refresh=load_assets
+                    on_close=Callback::new(move |_| set_show_assets.set(false))
+                />
+            })}
+
+            {move || show_problems.get().then(|| view! {
+                <ProblemsPanel
+                    problems=problems
+                    is_checking=is_checking_links
+                    on_open=open_at_line
+                    on_fix=apply_link_fix
+                    on_refresh=check_links
+                    on_close=Callback::new(move |_| set_show_problems.set(false))
+                />
+            })}
+
             <footer class="h-8 border-t border-base-200 dark:border-base-800 flex items-center justify-between px-4 bg-base-50 dark:bg-base-900/80 backdrop-blur-md z-20 flex-shrink-0">
                 <div class="flex items-center gap-3">
                     <div class="flex gap-0.5 bg-base-100 dark:bg-base-800/50 rounded-md p-0.5">
@@ -397,6 +1146,32 @@ pub fn Layout() -> impl IntoView {
                         >
                             "Format"
                         </button>
+                        <button
+                            class=move || format!(
+                                "px-2.5 py-0.5 rounded text-[11px] font-medium transition-all {}",
+                                if view_mode.get() == ViewMode::Split {
+                                    "bg-base-50 dark:bg-base-700 text-base-900 dark:text-base-50 shadow-sm"
+                                } else {
+                                    "text-base-500 dark:text-base-400 hover:text-base-700 dark:hover:text-base-300"
+                                }
+                            )
+                            on:click=move |_| set_view_mode.set(ViewMode::Split)
+                        >
+                            "Split"
+                        </button>
+                        <button
+                            class=move || format!(
+                                "px-2.5 py-0.5 rounded text-[11px] font-medium transition-all {}",
+                                if view_mode.get() == ViewMode::Live {
+                                    "bg-base-50 dark:bg-base-700 text-base-900 dark:text-base-50 shadow-sm"
+                                } else {
+                                    "text-base-500 dark:text-base-400 hover:text-base-700 dark:hover:text-base-300"
+                                }
+                            )
+                            on:click=move |_| set_view_mode.set(ViewMode::Live)
+                        >
+                            "Live"
+                        </button>
                     </div>
 
                     <div class="h-3 w-px bg-base-200 dark:bg-base-800"></div>
@@ -410,6 +1185,150 @@ pub fn Layout() -> impl IntoView {
                 </div>
 
                 <div class="flex items-center gap-1">
+                    <button
+                        class="px-2 py-1 rounded-md text-[10px] font-mono text-base-400 hover:text-base-600 dark:hover:text-base-300 transition-all"
+                        title="Formatear documento (Shift+Alt+F)"
+                        on:click=move |_| format_document.run(())
+                    >
+                        "Formatear"
+                    </button>
+
+                    <button
+                        class=move || format!(
+                            "px-2 py-1 rounded-md text-[10px] font-mono transition-all {}",
+                            if show_table_editor.get() {
+                                "text-brand-orange bg-brand-orange/10"
+                            } else {
+                                "text-base-400 hover:text-base-600 dark:hover:text-base-300"
+                            }
+                        )
+                        title=move || if table_under_cursor.get().is_some() {
+                            "Ocultar o mostrar el editor de la tabla bajo el cursor"
+                        } else {
+                            "Crear una tabla vacía o desde CSV/TSV"
+                        }
+                        on:click=move |_| if show_table_editor.get_untracked() {
+                            close_table_editor();
+                        } else if table_under_cursor.get_untracked().is_some() {
+                            dismissed_table.set(None);
+                        } else {
+                            if view_mode.get_untracked() == ViewMode::Formatted {
+                                set_view_mode.set(ViewMode::Raw);
+                            }
+                            set_show_table_form.set(true);
+                        }
+                    >
+                        "Tabla"
+                    </button>
+
+                    {move || refactor_history.with(|history| history.last().map(|(label, _)| label.clone())).map(|label| view! {
+                        <button
+                            class="px-2 py-1 rounded-md text-[10px] font-mono text-base-400 hover:text-base-600 dark:hover:text-base-300 transition-all"
+                            title=format!("Deshacer: {}", label)
+                            on:click=undo_refactor
+                        >
+                            "Deshacer"
+                        </button>
+                    })}
+
+                    <button
+                        class=move || format!(
+                            "px-2 py-1 rounded-md text-[10px] font-mono transition-all disabled:opacity-50 disabled:cursor-not-allowed {}",
+                            if show_tasks.get() {
+                                "text-brand-orange bg-brand-orange/10"
+                            } else {
+                                "text-base-400 hover:text-base-600 dark:hover:text-base-300"
+                            }
+                        )
+                        disabled=move || !is_tauri() || path.get() == "No se ha seleccionado ninguna carpeta"
+                        title="Tareas pendientes de todos los documentos del proyecto"
+                        on:click=move |_| if show_tasks.get_untracked() {
+                            set_show_tasks.set(false);
+                        } else {
+                            load_tasks.run(());
+                        }
+                    >
+                        {move || format!("Tareas: {}", tasks.with(Vec::len))}
+                    </button>
+
+                    <button
+                        class=move || format!(
+                            "px-2 py-1 rounded-md text-[10px] font-mono transition-all disabled:opacity-50 disabled:cursor-not-allowed {}",
+                            if show_assets.get() {
+                                "text-brand-orange bg-brand-orange/10"
+                            } else {
+                                "text-base-400 hover:text-base-600 dark:hover:text-base-300"
+                            }
+                        )
+                        disabled=move || !is_tauri() || path.get() == "No se ha seleccionado ninguna carpeta"
+                        title="Recursos sin usar y referencias a archivos que no existen"
+                        on:click=move |_| if show_assets.get_untracked() {
+                            set_show_assets.set(false);
+                        } else {
+                            load_assets.run(());
+                        }
+                    >
+                        "Recursos"
+                    </button>
+
+                    <button
+                        class="px-2 py-1 rounded-md text-[10px] font-mono transition-all disabled:opacity-50 disabled:cursor-not-allowed text-base-400 hover:text-base-600 dark:hover:text-base-300"
+                        disabled=move || !is_tauri() || is_exporting.get() || path.get() == "No se ha seleccionado ninguna carpeta"
+                        title="Exportar el proyecto como sitio web estático"
+                        on:click=move |_| export_project.run("export_site")
+                    >
+                        "Sitio"
+                    </button>
+
+                    <button
+                        class="px-2 py-1 rounded-md text-[10px] font-mono transition-all disabled:opacity-50 disabled:cursor-not-allowed text-base-400 hover:text-base-600 dark:hover:text-base-300"
+                        disabled=move || !is_tauri() || is_exporting.get() || path.get() == "No se ha seleccionado ninguna carpeta"
+                        title="Exportar el proyecto como libro EPUB"
+                        on:click=move |_| export_project.run("export_epub")
+                    >
+                        "EPUB"
+                    </button>
+
+                    <button
+                        class=move || format!(
+                            "px-2 py-1 rounded-md text-[10px] font-mono transition-all disabled:opacity-50 disabled:cursor-not-allowed {}",
+                            if problems.with(|p| !p.is_empty()) {
+                                "text-brand-orange bg-brand-orange/10"
+                            } else {
+                                "text-base-400 hover:text-base-600 dark:hover:text-base-300"
+                            }
+                        )
+                        disabled=move || !is_tauri() || path.get() == "No se ha seleccionado ninguna carpeta"
+                        title="Comprobar enlaces, imágenes y anclas del proyecto"
+                        on:click=move |_| if show_problems.get_untracked() {
+                            set_show_problems.set(false);
+                        } else {
+                            check_links.run(());
+                        }
+                    >
+                        {move || format!("Problemas: {}", problems.with(Vec::len))}
+                    </button>
+
+                    <button
+                        class=move || format!(
+                            "px-2 py-1 rounded-md text-[10px] font-mono transition-all disabled:opacity-50 disabled:cursor-not-allowed {}",
+                            if trusted_html.get() {
+                                "text-brand-orange bg-brand-orange/10"
+                            } else {
+                                "text-base-400 hover:text-base-600 dark:hover:text-base-300"
+                            }
+                        )
+                        disabled=move || !is_tauri() || path.get() == "No se ha seleccionado ninguna carpeta"
+                        title=move || if trusted_html.get() {
+                            "HTML confiable: el HTML del documento se muestra sin filtrar"
+                        } else {
+                            "HTML filtrado: scripts y atributos peligrosos se eliminan de la vista previa"
+                        }
+                        on:click=toggle_trusted_html
+                    >
+                        {move || if trusted_html.get() { "HTML confiable" } else { "HTML filtrado" }}
+                    </button>
+
                     <button
                         class="flex items-center gap-1.5 px-2.5 py-1 rounded-md text-[11px] font-medium transition-all bg-base-900 hover:bg-base-700 text-base-50 disabled:opacity-50 disabled:cursor-not-allowed"
                         disabled=move || !is_tauri()
@@ -450,6 +1369,81 @@ pub fn Layout() -> impl IntoView {
                 }
             })}
 
+            {move || rename_plan.get().map(|plan| {
+                let pending = plan.clone();
+                view! {
+                    <RefactorPreviewModal
+                        title="Actualizar enlaces".to_string()
+                        subtitle=format!("{} → {}", plan.old_path, plan.new_path)
+                        edits=plan.edits
+                        skipped=plan.skipped
+                        on_confirm=Callback::new(move |_| {
+                            set_rename_plan.set(None);
+                            apply_rename_plan(pending.clone());
+                        })
+                        on_cancel=Callback::new(move |_| set_rename_plan.set(None))
+                    />
+                }
+            })}
+
+            {move || heading_to_rename.get().map(|heading| view! {
+                <RenameHeadingModal
+                    text=heading.text
+                    on_confirm=Callback::new(handle_heading_rename_confirm)
+                    on_cancel=Callback::new(move |_| set_heading_to_rename.set(None))
+                />
+            })}
+
+            {move || heading_plan.get().map(|(new_text, plan)| {
+                let pending = (new_text.clone(), plan.edits.clone());
+                view! {
+                    <RefactorPreviewModal
+                        title="Renombrar encabezado".to_string()
+                        subtitle=format!("→ {}", new_text)
+                        edits=plan.edits
+                        skipped=plan.skipped
+                        on_confirm=Callback::new(move |_| {
+                            set_heading_plan.set(None);
+                            apply_heading_plan(pending.0.clone(), pending.1.clone());
+                        })
+                        on_cancel=Callback::new(move |_| set_heading_plan.set(None))
+                    />
+                }
+            })}
+
+            {move || link_graph.get().map(|graph| view! {
+                <LinkGraphView
+                    graph=graph
+                    current=selected_file.get_untracked()
+                    on_open=Callback::new(move |path: String| {
+                        set_link_graph.set(None);
+                        on_file_click.run(path);
+                    })
+                    on_close=Callback::new(move |_| set_link_graph.set(None))
+                />
+            })}
+
+            {move || file_to_create.get().map(|target| {
+                let name = target.rsplit(['/', '\\']).next().unwrap_or_default().to_string();
+                view! {
+                    <AlertModal
+                        title="Crear documento".to_string()
+                        message=format!("El documento «{}» todavía no existe. ¿Quieres crearlo?", name)
+                        on_confirm=Callback::new(handle_create_confirm)
+                        on_cancel=Callback::new(move |_| set_file_to_create.set(None))
+                    />
+                }
+            })}
+
+            {move || undo_notice.get().map(|message| view! {
+                <AlertModal
+                    title="Deshacer".to_string()
+                    message=message
+                    on_confirm=Callback::new(move |_| set_undo_notice.set(None))
+                    on_cancel=Callback::new(move |_| set_undo_notice.set(None))
+                />
+            })}
+
             {move || if show_clear_confirm.get() {
                 view! {
                     <AlertModal
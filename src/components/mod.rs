@@ -1,7 +1,12 @@
+pub mod assets;
 pub mod editor;
+pub mod graph;
 pub mod header;
 pub mod layout;
+pub mod problems;
 pub mod sidebar;
+pub mod table_editor;
+pub mod tasks;
 pub mod ui;
 
 pub mod modals;
@@ -1,3 +1,3 @@
 mod modals_impl;
 
-pub use modals_impl::{AlertModal, DeleteConfirmModal, RenameConfirmModal};
+pub use modals_impl::{AlertModal, DeleteConfirmModal, RefactorPreviewModal, RenameConfirmModal, RenameHeadingModal};
@@ -1,3 +1,5 @@
+use codedocs_md::edit::FileEdits;
+use codedocs_md::refactor::SkippedLink;
 use leptos::prelude::*;
 
 #[component]
@@ -113,6 +115,9 @@ pub fn RenameConfirmModal(
                 <p class="text-xs text-base-500 dark:text-base-400 mb-4 truncate">
                     "Ruta: " {path.clone()}
                 </p>
+                <p class="text-xs text-base-500 dark:text-base-400 mb-2">
+                    "Usa carpeta/nombre.md para moverlo. Los enlaces al archivo se actualizan."
+                </p>
 
                 <input
                     type="text"
@@ -144,3 +149,155 @@ pub fn RenameConfirmModal(
         </div>
     }
 }
+
+#[component]
+pub fn RefactorPreviewModal(
+    title: String,
+    subtitle: String,
+    edits: Vec<FileEdits>,
+    #[prop(optional)] skipped: Vec<SkippedLink>,
+    on_confirm: Callback<()>,
+    on_cancel: Callback<()>,
+) -> impl IntoView {
+    let _ = window_event_listener(leptos::ev::keydown, move |ev: leptos::ev::KeyboardEvent| {
+        if ev.key() == "Escape" {
+            on_cancel.run(());
+        }
+    });
+
+    let change_count: usize = edits.iter().map(|file| file.edits.len()).sum();
+    let files = edits
+        .into_iter()
+        .map(|file| {
+            let edits = file
+                .edits
+                .into_iter()
+                .map(|edit| {
+                    view! {
+                        <li class="font-mono text-xs flex flex-wrap gap-2">
+                            <span class="text-base-500 line-through">{edit.original}</span>
+                            <span class="text-base-400">"→"</span>
+                            <span class="text-brand-orange">{edit.replacement}</span>
+                        </li>
+                    }
+                })
+                .collect_view();
+            view! {
+                <div class="mb-3">
+                    <p class="text-xs font-semibold text-base-700 dark:text-base-300 truncate">{file.path}</p>
+                    <ul class="mt-1 ml-3 space-y-1">{edits}</ul>
+                </div>
+            }
+        })
+        .collect_view();
+    let skipped = (!skipped.is_empty()).then(|| {
+        let links = skipped
+            .into_iter()
+            .map(|link| view! {
+                <li class="font-mono text-xs truncate">{format!("{}:{} {}", link.path, link.line, link.target)}</li>
+            })
+            .collect_view();
+        view! {
+            <div class="mb-3">
+                <p class="text-xs font-semibold text-brand-orange">
+                    "Estos enlaces no se pueden actualizar y quedarán rotos:"
+                </p>
+                <ul class="mt-1 ml-3 space-y-1 text-base-500">{links}</ul>
+            </div>
+        }
+    });
+
+    view! {
+        <div class="fixed inset-0 z-[100] flex items-center justify-center bg-base-900/50 backdrop-blur-sm p-4">
+            <div class="bg-base-50 dark:bg-base-900 w-full max-w-2xl p-6 rounded-lg shadow-2xl border border-base-200 dark:border-base-800 animate-in zoom-in-95 duration-200">
+                <h3 class="text-lg font-bold text-base-900 dark:text-base-50 mb-2">
+                    {title}
+                </h3>
+                <p class="text-sm text-base-500 dark:text-base-400 mb-1 break-all font-mono text-xs">
+                    {subtitle}
+                </p>
+                <p class="text-sm text-base-500 dark:text-base-400 mb-4">
+                    {format!("Se harán {} cambios. Puedes deshacerlos desde la barra inferior.", change_count)}
+                </p>
+
+                <div class="max-h-80 overflow-y-auto mb-6 p-3 bg-base-100 dark:bg-base-800 rounded-md">
+                    {skipped}
+                    {files}
+                </div>
+
+                <div class="flex justify-end gap-3">
+                    <button
+                        on:click=move |_| on_cancel.run(())
+                        class="px-4 py-2 text-sm font-medium text-base-600 dark:text-base-400 hover:bg-base-100 dark:hover:bg-base-800 rounded-md transition-colors"
+                    >
+                        "Cancelar"
+                    </button>
+                    <button
+                        on:click=move |_| on_confirm.run(())
+                        class="px-4 py-2 text-sm font-medium text-base-50 bg-base-900 dark:bg-base-50 dark:text-base-900 hover:bg-base-700 dark:hover:bg-base-200 rounded-md shadow-sm transition-colors"
+                    >
+                        "Aplicar"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn RenameHeadingModal(
+    text: String,
+    on_confirm: Callback<String>,
+    on_cancel: Callback<()>,
+) -> impl IntoView {
+    let (new_text, set_new_text) = signal(text.clone());
+
+    let _ = window_event_listener(leptos::ev::keydown, move |ev: leptos::ev::KeyboardEvent| {
+        if ev.key() == "Escape" {
+            on_cancel.run(());
+        }
+    });
+
+    view! {
+        <div class="fixed inset-0 z-[100] flex items-center justify-center bg-base-900/50 backdrop-blur-sm p-4">
+            <div class="bg-base-50 dark:bg-base-900 w-full max-w-md p-6 rounded-lg shadow-2xl border border-base-200 dark:border-base-800 animate-in zoom-in-95 duration-200">
+                <h3 class="text-lg font-bold text-base-900 dark:text-base-50 mb-2">
+                    "Renombrar encabezado"
+                </h3>
+                <p class="text-xs text-base-500 dark:text-base-400 mb-4 truncate">
+                    "Actual: " {text}
+                </p>
+                <p class="text-xs text-base-500 dark:text-base-400 mb-2">
+                    "Los enlaces #ancla y [[página#Encabezado]] del proyecto se actualizan."
+                </p>
+
+                <input
+                    type="text"
+                    class="w-full px-3 py-2 bg-base-100 dark:bg-base-800 border border-base-200 dark:border-base-700 rounded-md text-sm focus:outline-none focus:ring-2 focus:ring-brand-orange mb-6 text-base-900 dark:text-base-100"
+                    prop:value=move || new_text.get()
+                    on:input=move |ev| set_new_text.set(event_target_value(&ev))
+                    on:keydown=move |ev| {
+                        if ev.key() == "Enter" {
+                            on_confirm.run(new_text.get());
+                        }
+                    }
+                />
+
+                <div class="flex justify-end gap-3">
+                    <button
+                        on:click=move |_| on_cancel.run(())
+                        class="px-4 py-2 text-sm font-medium text-base-600 dark:text-base-400 hover:bg-base-100 dark:hover:bg-base-800 rounded-md transition-colors"
+                    >
+                        "Cancelar"
+                    </button>
+                    <button
+                        on:click=move |_| on_confirm.run(new_text.get())
+                        class="px-4 py-2 text-sm font-medium text-base-50 bg-base-900 hover:bg-base-700 rounded-md shadow-sm transition-colors"
+                    >
+                        "Guardar cambios"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,83 @@
+use codedocs_md::check::LinkDiagnostic;
+use leptos::prelude::*;
+
+fn file_name(path: &str) -> String {
+    path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+#[component]
+pub fn ProblemsPanel(
+    problems: ReadSignal<Vec<LinkDiagnostic>>,
+    is_checking: ReadSignal<bool>,
+    on_open: Callback<(String, usize)>,
+    on_fix: Callback<LinkDiagnostic>,
+    on_refresh: Callback<()>,
+    on_close: Callback<()>,
+) -> impl IntoView {
+    view! {
+        <div class="h-48 flex flex-col border-t border-base-200 dark:border-base-800 bg-base-50 dark:bg-base-900 flex-shrink-0">
+            <div class="flex items-center justify-between px-4 py-1.5 border-b border-base-200 dark:border-base-800">
+                <h2 class="text-[10px] font-bold uppercase tracking-widest text-base-400 dark:text-base-500">
+                    {move || format!("Problemas ({})", problems.with(Vec::len))}
+                </h2>
+                <div class="flex items-center gap-1">
+                    <button
+                        class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-brand-orange disabled:opacity-50 transition-colors"
+                        disabled=move || is_checking.get()
+                        on:click=move |_| on_refresh.run(())
+                    >
+                        {move || if is_checking.get() { "Comprobando..." } else { "Comprobar enlaces" }}
+                    </button>
+                    <button
+                        class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-brand-orange transition-colors"
+                        title="Cerrar panel"
+                        on:click=move |_| on_close.run(())
+                    >
+                        "✕"
+                    </button>
+                </div>
+            </div>
+            <div class="flex-1 overflow-y-auto custom-scrollbar">
+                {move || {
+                    let list = problems.get();
+                    if list.is_empty() {
+                        view! {
+                            <p class="text-xs text-base-400 dark:text-base-600 italic px-4 py-2">
+                                "No hay enlaces rotos"
+                            </p>
+                        }.into_any()
+                    } else {
+                        list.into_iter().map(|problem| {
+                            let target = (problem.file.clone(), problem.line);
+                            let fix = problem.fix.as_ref().map(|fix| fix.replacement.clone());
+                            let fix_problem = problem.clone();
+                            view! {
+                                <div class="flex items-center gap-3 px-4 py-1 text-xs hover:bg-base-100 dark:hover:bg-base-800/50 group">
+                                    <button
+                                        class="flex-1 min-w-0 flex items-center gap-2 text-left"
+                                        title=problem.file.clone()
+                                        on:click=move |_| on_open.run(target.clone())
+                                    >
+                                        <span class="font-mono text-[11px] text-base-500 dark:text-base-400 flex-shrink-0">
+                                            {format!("{}:{}", file_name(&problem.file), problem.line)}
+                                        </span>
+                                        <span class="text-base-700 dark:text-base-300 truncate">{problem.reason.clone()}</span>
+                                    </button>
+                                    {fix.map(|replacement| view! {
+                                        <button
+                                            class="flex-shrink-0 px-2 py-0.5 rounded text-[11px] font-medium text-brand-orange bg-brand-orange/10 hover:bg-brand-orange/20 transition-colors"
+                                            title=format!("Reemplazar «{}» por «{}»", problem.target, replacement)
+                                            on:click=move |_| on_fix.run(fix_problem.clone())
+                                        >
+                                            {format!("Cambiar a {}", replacement)}
+                                        </button>
+                                    })}
+                                </div>
+                            }
+                        }).collect_view().into_any()
+                    }
+                }}
+            </div>
+        </div>
+    }
+}
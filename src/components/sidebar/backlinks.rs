@@ -0,0 +1,61 @@
+use crate::types::Backlink;
+use leptos::prelude::*;
+
+fn file_name(path: &str) -> String {
+    path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+#[component]
+pub fn BacklinksPanel(
+    backlinks: ReadSignal<Vec<Backlink>>,
+    on_open: Callback<String>,
+    on_show_graph: Callback<()>,
+) -> impl IntoView {
+    view! {
+        <div class="flex flex-col gap-1">
+            <div class="flex items-center justify-between px-1 mb-2">
+                <h2 class="text-[10px] font-bold uppercase tracking-widest text-base-400 dark:text-base-500">
+                    "Backlinks"
+                </h2>
+                <button
+                    class="text-[10px] font-medium text-base-500 dark:text-base-400 hover:text-brand-orange transition-colors"
+                    title="Ver el grafo de enlaces del proyecto"
+                    on:click=move |_| on_show_graph.run(())
+                >
+                    "Ver grafo"
+                </button>
+            </div>
+            {move || {
+                let links = backlinks.get();
+                if links.is_empty() {
+                    view! {
+                        <p class="text-xs text-base-400 dark:text-base-600 italic px-1">
+                            "Ningún documento enlaza aquí"
+                        </p>
+                    }.into_any()
+                } else {
+                    links.into_iter().map(|link| {
+                        let source = link.source.clone();
+                        view! {
+                            <button
+                                class="block w-full text-left px-2 py-1.5 rounded hover:bg-base-100 dark:hover:bg-base-800/50 transition-colors group"
+                                title=link.source.clone()
+                                on:click=move |_| on_open.run(source.clone())
+                            >
+                                <span class="block text-xs font-medium text-base-700 dark:text-base-300 group-hover:text-brand-orange truncate">
+                                    {file_name(&link.source)}
+                                    <span class="ml-1 text-[10px] font-mono text-base-400 dark:text-base-600">
+                                        {format!(":{}", link.line)}
+                                    </span>
+                                </span>
+                                <span class="block text-[11px] text-base-500 dark:text-base-500 line-clamp-2">
+                                    {link.snippet}
+                                </span>
+                            </button>
+                        }
+                    }).collect_view().into_any()
+                }
+            }}
+        </div>
+    }
+}
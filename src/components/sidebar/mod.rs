@@ -1,6 +1,7 @@
+mod backlinks;
 mod outline;
 
-use crate::types::FileEntry;
+use crate::types::{Backlink, FileEntry};
 use crate::utils::env::is_tauri;
 use crate::utils::tauri_bridge::{self, invoke};
 use crate::components::ui::{AppStatus, Button, FileTree};
@@ -102,6 +103,7 @@ pub fn OpenFolderButton(
 pub enum SidebarTab {
     Files,
     Outline,
+    Backlinks,
 }
 
 #[component]
@@ -116,6 +118,11 @@ pub fn Sidebar(
     on_rename: Callback<String>,
     create_new_file: Callback<()>,
     headings: ReadSignal<Vec<Heading>>,
+    active_heading: ReadSignal<Option<usize>>,
+    on_heading_click: Callback<usize>,
+    on_heading_rename: Callback<usize>,
+    backlinks: ReadSignal<Vec<Backlink>>,
+    on_show_graph: Callback<()>,
 ) -> impl IntoView {
     let (active_tab, set_active_tab) = signal(SidebarTab::Files);
 
@@ -192,6 +199,15 @@ pub fn Sidebar(
                         >
                             "Contenido"
                         </button>
+                        <button
+                            class=move || format!(
+                                "flex-1 text-[10px] font-bold uppercase tracking-wider py-1.5 rounded transition-colors {}",
+                                if active_tab.get() == SidebarTab::Backlinks { "bg-base-50 dark:bg-base-700 text-base-900 dark:text-base-50 shadow-sm" } else { "text-base-500 dark:text-base-400 hover:text-base-700 dark:hover:text-base-300" }
+                            )
+                            on:click=move |_| set_active_tab.set(SidebarTab::Backlinks)
+                        >
+                            "Enlaces"
+                        </button>
                     </div>
 
                     {move || if active_tab.get() == SidebarTab::Files {
@@ -220,8 +236,8 @@ pub fn Sidebar(
             </div>
 
             <div class="flex-1 overflow-y-auto p-4 custom-scrollbar">
-                {move || if active_tab.get() == SidebarTab::Files {
-                    view! {
+                {move || match active_tab.get() {
+                    SidebarTab::Files => view! {
                         <div>
                             <div class="mb-4">
                                 <p class="text-[11px] font-mono text-base-400 dark:text-base-600 truncate bg-base-100 dark:bg-base-900/50 p-2 rounded border border-base-200 dark:border-base-800">
@@ -230,11 +246,22 @@ pub fn Sidebar(
                             </div>
                             <FileTree items=files on_click=on_file_click on_delete=on_delete on_rename=on_rename />
                         </div>
-                    }.into_any()
-                } else {
-                    view! {
-                        <outline::OutlinePanel headings=headings />
-                    }.into_any()
+                    }.into_any(),
+                    SidebarTab::Outline => view! {
+                        <outline::OutlinePanel
+                            headings=headings
+                            active_heading=active_heading
+                            on_heading_click=on_heading_click
+                            on_heading_rename=on_heading_rename
+                        />
+                    }.into_any(),
+                    SidebarTab::Backlinks => view! {
+                        <backlinks::BacklinksPanel
+                            backlinks=backlinks
+                            on_open=on_file_click
+                            on_show_graph=on_show_graph
+                        />
+                    }.into_any(),
                 }}
             </div>
         </aside>
@@ -2,7 +2,12 @@ use crate::utils::markdown::Heading;
 use leptos::prelude::*;
 
 #[component]
-pub fn OutlinePanel(headings: ReadSignal<Vec<Heading>>) -> impl IntoView {
+pub fn OutlinePanel(
+    headings: ReadSignal<Vec<Heading>>,
+    active_heading: ReadSignal<Option<usize>>,
+    on_heading_click: Callback<usize>,
+    on_heading_rename: Callback<usize>,
+) -> impl IntoView {
     view! {
         <div class="flex flex-col gap-1">
             <h2 class="text-[10px] font-bold uppercase tracking-widest text-base-400 dark:text-base-500 px-1 mb-2">
@@ -17,15 +22,24 @@ pub fn OutlinePanel(headings: ReadSignal<Vec<Heading>>) -> impl IntoView {
                         </p>
                     }.into_any()
                 } else {
-                    h.into_iter().map(|heading| {
+                    h.into_iter().enumerate().map(|(index, heading)| {
                         let indent = (heading.level - 1) as usize * 12;
                         let text = heading.text.clone();
-                        let title = text.clone();
+                        let title = format!("{} (doble clic para renombrar)", text);
                         view! {
                             <button
-                                class="block w-full text-left text-xs text-base-600 dark:text-base-400 hover:text-brand-orange dark:hover:text-brand-orange hover:bg-base-100 dark:hover:bg-base-800/50 px-2 py-1 rounded transition-colors truncate"
+                                class=move || format!(
+                                    "block w-full text-left text-xs hover:text-brand-orange dark:hover:text-brand-orange hover:bg-base-100 dark:hover:bg-base-800/50 px-2 py-1 rounded transition-colors truncate {}",
+                                    if active_heading.get() == Some(index) {
+                                        "text-brand-orange bg-base-100 dark:bg-base-800/50 font-medium"
+                                    } else {
+                                        "text-base-600 dark:text-base-400"
+                                    }
+                                )
                                 style:padding-left=format!("{}px", indent + 4)
                                 title=title
+                                on:click=move |_| on_heading_click.run(index)
+                                on:dblclick=move |_| on_heading_rename.run(index)
                             >
                                 {text}
                             </button>
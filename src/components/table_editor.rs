@@ -0,0 +1,257 @@
+use codedocs_md::edit::apply_edits;
+use codedocs_md::table::{parse_delimited, table_at_line, ColumnAlignment, GfmTable};
+use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+
+use crate::components::editor::cm_goto_line;
+
+const CELL_CLASS: &str = "w-full min-w-[6rem] px-2 py-1 text-xs font-mono bg-transparent border border-transparent rounded focus:outline-none focus:border-brand-orange focus:bg-base-50 dark:focus:bg-base-800 text-base-900 dark:text-base-100";
+const TOOL_CLASS: &str = "px-1 text-[11px] text-base-400 hover:text-brand-orange disabled:opacity-30 disabled:hover:text-base-400 transition-colors";
+
+/// Applies `change` to the table under the cursor and writes it back to the
+/// document, padded.
+fn rewrite_table(
+    editor_content: ReadSignal<String>,
+    set_editor_content: WriteSignal<String>,
+    table: Memo<Option<GfmTable>>,
+    change: impl FnOnce(&mut GfmTable),
+) {
+    let Some(mut table) = table.get_untracked() else { return };
+    change(&mut table);
+    let content = editor_content.get_untracked();
+    let Some(edit) = table.edit(&content) else { return };
+    if let Some(updated) = apply_edits(&content, &[edit]).filter(|updated| *updated != content) {
+        set_editor_content.set(updated);
+    }
+}
+
+/// Grid over the GFM table the cursor is in; without one, a form to create a
+/// table, empty or from pasted CSV/TSV.
+#[component]
+pub fn TableEditor(
+    editor_content: ReadSignal<String>,
+    set_editor_content: WriteSignal<String>,
+    cursor_line: ReadSignal<usize>,
+    on_close: Callback<()>,
+) -> impl IntoView {
+    let table = Memo::new(move |_| {
+        let line = cursor_line.get();
+        editor_content.with(|content| table_at_line(content, line))
+    });
+    let row_count = Memo::new(move |_| table.with(|t| t.as_ref().map_or(0, |t| t.rows.len())));
+    let column_count = Memo::new(move |_| table.with(|t| t.as_ref().map_or(0, GfmTable::columns)));
+    let has_table = Memo::new(move |_| table.with(Option::is_some));
+    let (pasted, set_pasted) = signal(String::new());
+
+    let update = move |change: Box<dyn FnOnce(&mut GfmTable)>| {
+        rewrite_table(editor_content, set_editor_content, table, change);
+    };
+
+    let create_table = move |_| {
+        let text = pasted.get_untracked();
+        let new_table = if text.trim().is_empty() {
+            Some(GfmTable::empty(3))
+        } else {
+            GfmTable::from_rows(parse_delimited(&text))
+        };
+        let Some(new_table) = new_table else { return };
+        let content = editor_content.get_untracked();
+        let (edit, line) = new_table.insert_edit(&content, cursor_line.get_untracked());
+        if let Some(updated) = apply_edits(&content, &[edit]) {
+            set_editor_content.set(updated);
+            set_pasted.set(String::new());
+            // Moving the cursor into the new table brings up its grid.
+            spawn_local(async move { cm_goto_line(line as u32) });
+        }
+    };
+
+    let cell_value = move |row: usize, column: usize| {
+        table.with(|t| {
+            t.as_ref()
+                .and_then(|t| t.rows.get(row)?.get(column).cloned())
+                .unwrap_or_default()
+        })
+    };
+
+    let column_tools = move |column: usize| {
+        let alignment = move || {
+            table.with(|t| t.as_ref().and_then(|t| t.alignments.get(column).copied()).unwrap_or_default())
+        };
+        view! {
+            <th class="px-1 pb-1 font-normal">
+                <div class="flex items-center gap-0.5">
+                    <select
+                        class="text-[11px] bg-transparent text-base-500 dark:text-base-400 focus:outline-none"
+                        title="Alineación de la columna"
+                        on:change=move |ev| {
+                            let alignment = match event_target_value(&ev).as_str() {
+                                "left" => ColumnAlignment::Left,
+                                "center" => ColumnAlignment::Center,
+                                "right" => ColumnAlignment::Right,
+                                _ => ColumnAlignment::None,
+                            };
+                            update(Box::new(move |t| t.set_alignment(column, alignment)));
+                        }
+                    >
+                        <option value="none" selected=move || alignment() == ColumnAlignment::None>"—"</option>
+                        <option value="left" selected=move || alignment() == ColumnAlignment::Left>"Izquierda"</option>
+                        <option value="center" selected=move || alignment() == ColumnAlignment::Center>"Centro"</option>
+                        <option value="right" selected=move || alignment() == ColumnAlignment::Right>"Derecha"</option>
+                    </select>
+                    <button
+                        class=TOOL_CLASS
+                        title="Mover columna a la izquierda"
+                        disabled=column == 0
+                        on:click=move |_| update(Box::new(move |t| t.move_column(column, column - 1)))
+                    >
+                        "←"
+                    </button>
+                    <button
+                        class=TOOL_CLASS
+                        title="Mover columna a la derecha"
+                        disabled=move || column + 1 >= column_count.get()
+                        on:click=move |_| update(Box::new(move |t| t.move_column(column, column + 1)))
+                    >
+                        "→"
+                    </button>
+                    <button
+                        class=TOOL_CLASS
+                        title="Insertar columna a la derecha"
+                        on:click=move |_| update(Box::new(move |t| t.insert_column(column + 1)))
+                    >
+                        "+"
+                    </button>
+                    <button
+                        class=TOOL_CLASS
+                        title="Eliminar columna"
+                        disabled=move || column_count.get() <= 1
+                        on:click=move |_| update(Box::new(move |t| t.remove_column(column)))
+                    >
+                        "✕"
+                    </button>
+                </div>
+            </th>
+        }
+    };
+
+    let row_tools = move |row: usize| {
+        view! {
+            <td class="px-1 whitespace-nowrap">
+                <button
+                    class=TOOL_CLASS
+                    title="Subir fila"
+                    disabled=row <= 1
+                    on:click=move |_| update(Box::new(move |t| t.move_row(row, row - 1)))
+                >
+                    "↑"
+                </button>
+                <button
+                    class=TOOL_CLASS
+                    title="Bajar fila"
+                    disabled=move || row == 0 || row + 1 >= row_count.get()
+                    on:click=move |_| update(Box::new(move |t| t.move_row(row, row + 1)))
+                >
+                    "↓"
+                </button>
+                <button
+                    class=TOOL_CLASS
+                    title="Insertar fila debajo"
+                    on:click=move |_| update(Box::new(move |t| t.insert_row(row + 1)))
+                >
+                    "+"
+                </button>
+                <button
+                    class=TOOL_CLASS
+                    title="Eliminar fila"
+                    disabled=move || row == 0 || row_count.get() <= 2
+                    on:click=move |_| update(Box::new(move |t| t.remove_row(row)))
+                >
+                    "✕"
+                </button>
+            </td>
+        }
+    };
+
+    view! {
+        <div class="h-56 flex flex-col border-t border-base-200 dark:border-base-800 bg-base-50 dark:bg-base-900 flex-shrink-0">
+            <div class="flex items-center justify-between px-4 py-1.5 border-b border-base-200 dark:border-base-800">
+                <h2 class="text-[10px] font-bold uppercase tracking-widest text-base-400 dark:text-base-500">
+                    {move || if has_table.get() {
+                        format!("Tabla ({} × {})", row_count.get().saturating_sub(1), column_count.get())
+                    } else {
+                        "Nueva tabla".to_string()
+                    }}
+                </h2>
+                <button
+                    class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-brand-orange transition-colors"
+                    title="Cerrar editor de tablas"
+                    on:click=move |_| on_close.run(())
+                >
+                    "✕"
+                </button>
+            </div>
+            <div class="flex-1 overflow-auto custom-scrollbar px-4 py-2">
+                {move || if has_table.get() {
+                    view! {
+                        <table class="border-collapse">
+                            <thead>
+                                <tr>
+                                    <For each=move || 0..column_count.get() key=|column| *column let:column>
+                                        {column_tools(column)}
+                                    </For>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                <For each=move || 0..row_count.get() key=|row| *row let:row>
+                                    <tr class=if row == 0 {
+                                        "border-b-2 border-base-300 dark:border-base-700 font-bold"
+                                    } else {
+                                        "border-b border-base-200 dark:border-base-800"
+                                    }>
+                                        <For each=move || 0..column_count.get() key=|column| *column let:column>
+                                            <td class="p-0.5">
+                                                <input
+                                                    type="text"
+                                                    class=CELL_CLASS
+                                                    prop:value=move || cell_value(row, column)
+                                                    on:change=move |ev| {
+                                                        let value = event_target_value(&ev);
+                                                        update(Box::new(move |t| t.set_cell(row, column, &value)));
+                                                    }
+                                                />
+                                            </td>
+                                        </For>
+                                        {row_tools(row)}
+                                    </tr>
+                                </For>
+                            </tbody>
+                        </table>
+                    }.into_any()
+                } else {
+                    view! {
+                        <div class="flex flex-col gap-2 h-full">
+                            <p class="text-xs text-base-400 dark:text-base-600">
+                                "Coloca el cursor en una tabla para editarla, o pega datos CSV/TSV para crear una debajo del cursor."
+                            </p>
+                            <textarea
+                                class="flex-1 min-h-[4rem] px-2 py-1 text-xs font-mono bg-base-100 dark:bg-base-800 border border-base-200 dark:border-base-700 rounded-md text-base-900 dark:text-base-100 focus:outline-none focus:border-brand-orange resize-none"
+                                placeholder="nombre,valor\nuno,1"
+                                prop:value=pasted
+                                on:input=move |ev| set_pasted.set(event_target_value(&ev))
+                            ></textarea>
+                            <div>
+                                <button
+                                    class="px-3 py-1 text-xs font-medium rounded-md bg-brand-orange text-white hover:bg-brand-orange/90 transition-colors"
+                                    on:click=create_table
+                                >
+                                    {move || if pasted.with(|text| text.trim().is_empty()) { "Insertar tabla vacía" } else { "Crear tabla" }}
+                                </button>
+                            </div>
+                        </div>
+                    }.into_any()
+                }}
+            </div>
+        </div>
+    }
+}
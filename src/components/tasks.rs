@@ -0,0 +1,101 @@
+use codedocs_md::tasks::Task;
+use leptos::prelude::*;
+
+fn file_name(path: &str) -> String {
+    path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+#[component]
+pub fn TasksPanel(
+    tasks: ReadSignal<Vec<Task>>,
+    is_loading: ReadSignal<bool>,
+    on_open: Callback<(String, usize)>,
+    on_toggle: Callback<Task>,
+    on_refresh: Callback<()>,
+    on_close: Callback<()>,
+) -> impl IntoView {
+    view! {
+        <div class="h-48 flex flex-col border-t border-base-200 dark:border-base-800 bg-base-50 dark:bg-base-900 flex-shrink-0">
+            <div class="flex items-center justify-between px-4 py-1.5 border-b border-base-200 dark:border-base-800">
+                <h2 class="text-[10px] font-bold uppercase tracking-widest text-base-400 dark:text-base-500">
+                    {move || format!("Tareas pendientes ({})", tasks.with(Vec::len))}
+                </h2>
+                <div class="flex items-center gap-1">
+                    <button
+                        class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-brand-orange disabled:opacity-50 transition-colors"
+                        disabled=move || is_loading.get()
+                        on:click=move |_| on_refresh.run(())
+                    >
+                        {move || if is_loading.get() { "Cargando..." } else { "Actualizar" }}
+                    </button>
+                    <button
+                        class="px-2 py-0.5 text-[11px] text-base-500 dark:text-base-400 hover:text-brand-orange transition-colors"
+                        title="Cerrar panel"
+                        on:click=move |_| on_close.run(())
+                    >
+                        "✕"
+                    </button>
+                </div>
+            </div>
+            <div class="flex-1 overflow-y-auto custom-scrollbar">
+                {move || {
+                    let list = tasks.get();
+                    if list.is_empty() {
+                        return view! {
+                            <p class="text-xs text-base-400 dark:text-base-600 italic px-4 py-2">
+                                "No hay tareas pendientes"
+                            </p>
+                        }.into_any();
+                    }
+
+                    let mut groups: Vec<(String, Vec<Task>)> = Vec::new();
+                    for task in list {
+                        match groups.last_mut() {
+                            Some((file, group)) if *file == task.file => group.push(task),
+                            _ => groups.push((task.file.clone(), vec![task])),
+                        }
+                    }
+
+                    groups.into_iter().map(|(file, group)| view! {
+                        <div class="py-1">
+                            <h3 class="px-4 py-0.5 text-[11px] font-medium text-base-500 dark:text-base-400 truncate" title=file.clone()>
+                                {format!("{} ({})", file_name(&file), group.len())}
+                            </h3>
+                            {group.into_iter().map(|task| {
+                                let target = (task.file.clone(), task.line);
+                                let toggled = task.clone();
+                                view! {
+                                    <div class="flex items-center gap-2 pl-6 pr-4 py-0.5 text-xs hover:bg-base-100 dark:hover:bg-base-800/50">
+                                        <input
+                                            type="checkbox"
+                                            class="flex-shrink-0 cursor-pointer"
+                                            title="Marcar como hecha"
+                                            on:click=move |ev| {
+                                                ev.prevent_default();
+                                                on_toggle.run(toggled.clone());
+                                            }
+                                        />
+                                        <button
+                                            class="flex-1 min-w-0 flex items-center gap-2 text-left"
+                                            on:click=move |_| on_open.run(target.clone())
+                                        >
+                                            <span class="text-base-700 dark:text-base-300 truncate">{task.text.clone()}</span>
+                                            {task.heading.clone().map(|heading| view! {
+                                                <span class="text-[11px] text-base-400 dark:text-base-600 truncate flex-shrink-0 max-w-[40%]">
+                                                    {format!("§ {}", heading)}
+                                                </span>
+                                            })}
+                                            <span class="font-mono text-[11px] text-base-400 dark:text-base-600 flex-shrink-0">
+                                                {format!(":{}", task.line)}
+                                            </span>
+                                        </button>
+                                    </div>
+                                }
+                            }).collect_view()}
+                        </div>
+                    }).collect_view().into_any()
+                }}
+            </div>
+        </div>
+    }
+}
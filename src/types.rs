@@ -1,3 +1,5 @@
+use codedocs_md::edit::FileEdits;
+use codedocs_md::refactor::SkippedLink;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,3 +9,61 @@ pub struct FileEntry {
     pub is_dir: bool,
     pub children: Vec<FileEntry>,
 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backlink {
+    pub source: String,
+    pub line: usize,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub path: String,
+    pub name: String,
+    pub incoming: usize,
+    pub outgoing: usize,
+    pub orphan: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Mirror of the backend's plan for a link-aware rename or move.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenamePlan {
+    pub old_path: String,
+    pub new_path: String,
+    pub edits: Vec<FileEdits>,
+    #[serde(default)]
+    pub skipped: Vec<SkippedLink>,
+}
+
+/// Mirror of the backend's report of an undone refactoring.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndoOutcome {
+    pub label: String,
+    pub kept: Vec<String>,
+}
+
+/// Paths of every file in the tree, depth first.
+pub fn file_paths(entries: &[FileEntry]) -> Vec<String> {
+    let mut paths = Vec::new();
+    for entry in entries {
+        if entry.is_dir {
+            paths.extend(file_paths(&entry.children));
+        } else {
+            paths.push(entry.path.clone());
+        }
+    }
+    paths
+}
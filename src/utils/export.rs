@@ -0,0 +1,23 @@
+use codedocs_md::export::enhancements;
+use codedocs_md::{render_markdown, RenderOptions};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = __codedocs_prerender, catch)]
+    async fn prerender_js(items: JsValue) -> Result<JsValue, JsValue>;
+}
+
+/// The math and diagrams of `content` rendered by the webview, in the order
+/// the backend's export finds them in the same document.
+pub async fn prerender(content: &str) -> Vec<String> {
+    let items = enhancements(&render_markdown(content, &RenderOptions::default()));
+    if items.is_empty() {
+        return Vec::new();
+    }
+    let Ok(items) = serde_wasm_bindgen::to_value(&items) else { return Vec::new() };
+    match prerender_js(items).await {
+        Ok(rendered) => serde_wasm_bindgen::from_value(rendered).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
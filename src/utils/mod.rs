@@ -1,3 +1,4 @@
 pub mod env;
+pub mod export;
 pub mod markdown;
 pub mod tauri_bridge;
@@ -6,6 +6,9 @@ extern "C" {
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], catch)]
     pub async fn invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
 
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = convertFileSrc)]
+    pub fn convert_file_src(path: &str, protocol: &str) -> String;
+
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
     pub async fn listen(event: &str, handler: &js_sys::Function) -> JsValue;
 }